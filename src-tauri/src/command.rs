@@ -1,11 +1,13 @@
 use trivium::BitOrder;
 use trivium::{PackOrder, Trivium};
 
+use crate::crypto::secret::SecretBytes;
+
 #[tauri::command]
 pub fn trivium_xor(
-    key: Vec<u8>,
-    iv: Vec<u8>,
-    data: Vec<u8>,
+    key: SecretBytes,
+    iv: SecretBytes,
+    data: SecretBytes,
     bit_order: Option<String>,
 ) -> Result<Vec<u8>, String> {
     let order = parse_bit_order(bit_order)?;
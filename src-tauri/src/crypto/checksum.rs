@@ -0,0 +1,701 @@
+//! Checksum manifests: verifying and writing GNU coreutils (`sha256sum`),
+//! BSD tag (`sha256sum --tag`, `openssl dgst`) and SFV files. Paths in a
+//! manifest are relative to the directory the manifest lives in.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+use data_encoding::{HEXLOWER, HEXUPPER};
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Channel;
+
+use super::hash::{stream_file, HashAlgorithm, HashProgress, Hasher};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestFormat {
+	/// `<digest>  <path>`, as written by `sha256sum`.
+	Gnu,
+	/// `SHA256 (<path>) = <digest>`, as written by `sha256sum --tag`.
+	Bsd,
+	/// `<path> <CRC-32>`.
+	Sfv,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumVerifyRequest {
+	pub manifest_path: String,
+	/// Algorithm for GNU-style lines; guessed from the manifest name
+	/// (`.sha256`, `SHA256SUMS`, ...) or the digest length when absent.
+	pub algorithm: Option<HashAlgorithm>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumStatus {
+	Ok,
+	Mismatch,
+	Missing,
+	/// The file exists but could not be read.
+	Unreadable,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumEntryResult {
+	/// As written in the manifest.
+	pub path: String,
+	pub algorithm: HashAlgorithm,
+	pub status: ChecksumStatus,
+	pub expected: String,
+	pub actual: Option<String>,
+	pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumReport {
+	pub entries: Vec<ChecksumEntryResult>,
+	pub ok: usize,
+	pub mismatched: usize,
+	pub missing: usize,
+	pub unreadable: usize,
+	/// 1-based numbers of lines that are neither entries nor comments, or whose
+	/// path is absolute or leaves the manifest's directory.
+	pub malformed_lines: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumGenerateRequest {
+	pub folder: String,
+	pub format: ManifestFormat,
+	/// Ignored for SFV, which always uses CRC-32.
+	pub algorithm: HashAlgorithm,
+	/// Include files in subfolders.
+	#[serde(default)]
+	pub recursive: bool,
+	/// Also write the manifest to this path; a manifest inside `folder` is not listed in itself.
+	pub output_path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumManifest {
+	pub content: String,
+	pub files: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumProgress {
+	/// Path of the file being hashed, relative to the manifest directory.
+	pub path: String,
+	/// 0-based index of the file and the number of files.
+	pub file: usize,
+	pub files: usize,
+	pub processed: u64,
+	pub total: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ManifestEntry {
+	path: String,
+	algorithm: HashAlgorithm,
+	/// Lowercase hex.
+	expected: String,
+}
+
+/// Maps a BSD tag or manifest name (`SHA256`, `sha3-256`, `b2`, ...) to an algorithm.
+fn algorithm_from_tag(tag: &str) -> Option<HashAlgorithm> {
+	let tag: String = tag
+		.chars()
+		.filter(|c| !matches!(c, '-' | '_' | '/'))
+		.collect::<String>()
+		.to_ascii_lowercase();
+	Some(match tag.as_str() {
+		"md2" => HashAlgorithm::Md2,
+		"md4" => HashAlgorithm::Md4,
+		"md5" => HashAlgorithm::Md5,
+		"sha1" => HashAlgorithm::Sha1,
+		"sha224" => HashAlgorithm::Sha224,
+		"sha256" => HashAlgorithm::Sha256,
+		"sha384" => HashAlgorithm::Sha384,
+		"sha512" => HashAlgorithm::Sha512,
+		"sha512224" => HashAlgorithm::Sha512_224,
+		"sha512256" => HashAlgorithm::Sha512_256,
+		"sha3224" => HashAlgorithm::Sha3_224,
+		"sha3256" => HashAlgorithm::Sha3_256,
+		"sha3384" => HashAlgorithm::Sha3_384,
+		"sha3512" => HashAlgorithm::Sha3_512,
+		"keccak224" => HashAlgorithm::Keccak224,
+		"keccak256" => HashAlgorithm::Keccak256,
+		"keccak384" => HashAlgorithm::Keccak384,
+		"keccak512" => HashAlgorithm::Keccak512,
+		"shake128" => HashAlgorithm::Shake128,
+		"shake256" => HashAlgorithm::Shake256,
+		"b2" | "blake2" | "blake2b" | "blake2b512" => HashAlgorithm::Blake2b,
+		"blake2s" | "blake2s256" => HashAlgorithm::Blake2s,
+		"b3" | "blake3" => HashAlgorithm::Blake3,
+		"rmd160" | "ripemd160" => HashAlgorithm::Ripemd160,
+		"whirlpool" => HashAlgorithm::Whirlpool,
+		"tiger" => HashAlgorithm::Tiger,
+		"streebog256" => HashAlgorithm::Streebog256,
+		"streebog512" => HashAlgorithm::Streebog512,
+		"sm3" => HashAlgorithm::Sm3,
+		"crc32" | "sfv" => HashAlgorithm::Crc32,
+		_ => return None,
+	})
+}
+
+/// Tag written in BSD-style lines; matches coreutils for the algorithms it knows.
+fn bsd_tag(algorithm: HashAlgorithm) -> String {
+	match algorithm {
+		HashAlgorithm::Blake2b => "BLAKE2b".to_string(),
+		HashAlgorithm::Crc32 => "CRC32".to_string(),
+		_ => algorithm.to_string().replacen("SHA-", "SHA", 1),
+	}
+}
+
+/// `SHA256SUMS`, `files.sha256`, `release.md5` and the like.
+fn algorithm_from_manifest_name(path: &Path) -> Option<HashAlgorithm> {
+	if let Some(algorithm) = path
+		.extension()
+		.and_then(|ext| ext.to_str())
+		.and_then(algorithm_from_tag)
+	{
+		return Some(algorithm);
+	}
+	let stem = path.file_stem()?.to_str()?.to_ascii_lowercase();
+	let stem = stem
+		.strip_suffix("sums")
+		.or_else(|| stem.strip_suffix("sum"))?;
+	algorithm_from_tag(stem)
+}
+
+/// The usual algorithm behind a GNU-style digest of this many hex digits.
+fn algorithm_from_length(hex_len: usize) -> Option<HashAlgorithm> {
+	Some(match hex_len {
+		8 => HashAlgorithm::Crc32,
+		32 => HashAlgorithm::Md5,
+		40 => HashAlgorithm::Sha1,
+		56 => HashAlgorithm::Sha224,
+		64 => HashAlgorithm::Sha256,
+		96 => HashAlgorithm::Sha384,
+		128 => HashAlgorithm::Sha512,
+		_ => return None,
+	})
+}
+
+fn is_hex_digest(value: &str) -> bool {
+	!value.is_empty()
+		&& value.len().is_multiple_of(2)
+		&& value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Undoes the escaping coreutils applies to names containing `\` or a newline.
+fn unescape_gnu_path(path: &str) -> String {
+	let mut out = String::with_capacity(path.len());
+	let mut chars = path.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			out.push(c);
+			continue;
+		}
+		match chars.next() {
+			Some('n') => out.push('\n'),
+			Some('r') => out.push('\r'),
+			Some(other) => out.push(other),
+			None => out.push('\\'),
+		}
+	}
+	out
+}
+
+fn escape_gnu_path(path: &str) -> Option<String> {
+	path.contains(['\\', '\n', '\r']).then(|| {
+		path.replace('\\', "\\\\")
+			.replace('\n', "\\n")
+			.replace('\r', "\\r")
+	})
+}
+
+/// `TAG (path) = digest`, also without the space before `=` as `openssl dgst` writes it.
+fn parse_bsd_line(line: &str) -> Option<ManifestEntry> {
+	let (rest, digest) = line.rsplit_once('=')?;
+	let digest = digest.trim();
+	let (tag, path) = rest.trim_end().strip_suffix(')')?.split_once('(')?;
+	if !is_hex_digest(digest) {
+		return None;
+	}
+	// `BLAKE2b-256` names the output length; it is taken from the digest instead.
+	let tag = tag.trim();
+	let algorithm = algorithm_from_tag(tag).or_else(|| {
+		let (name, bits) = tag.rsplit_once('-')?;
+		bits.parse::<u32>().ok()?;
+		algorithm_from_tag(name)
+	})?;
+	Some(ManifestEntry {
+		path: path.to_string(),
+		algorithm,
+		expected: digest.to_ascii_lowercase(),
+	})
+}
+
+/// `digest  path` or `digest *path`, optionally with a leading `\` for escaped names.
+fn parse_gnu_line(line: &str, algorithm: Option<HashAlgorithm>) -> Option<ManifestEntry> {
+	let (escaped, line) = match line.strip_prefix('\\') {
+		Some(rest) => (true, rest),
+		None => (false, line),
+	};
+	let (digest, rest) = line.split_once(' ')?;
+	if !is_hex_digest(digest) {
+		return None;
+	}
+	let path = rest
+		.strip_prefix(' ')
+		.or_else(|| rest.strip_prefix('*'))
+		.unwrap_or(rest);
+	if path.is_empty() {
+		return None;
+	}
+	Some(ManifestEntry {
+		path: if escaped {
+			unescape_gnu_path(path)
+		} else {
+			path.to_string()
+		},
+		algorithm: algorithm.or_else(|| algorithm_from_length(digest.len()))?,
+		expected: digest.to_ascii_lowercase(),
+	})
+}
+
+/// `path CRC32`; the path may itself contain spaces.
+fn parse_sfv_line(line: &str) -> Option<ManifestEntry> {
+	let (path, crc) = line.trim_end().rsplit_once([' ', '\t'])?;
+	let path = path.trim_end();
+	if crc.len() != 8 || !is_hex_digest(crc) || path.is_empty() {
+		return None;
+	}
+	Some(ManifestEntry {
+		path: path.to_string(),
+		algorithm: HashAlgorithm::Crc32,
+		expected: crc.to_ascii_lowercase(),
+	})
+}
+
+/// Entries in manifest order, plus the numbers of lines that could not be parsed.
+fn parse_manifest(
+	content: &str,
+	sfv: bool,
+	algorithm: Option<HashAlgorithm>,
+) -> (Vec<ManifestEntry>, Vec<usize>) {
+	let mut entries = Vec::new();
+	let mut malformed = Vec::new();
+	let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+	for (index, line) in content.lines().enumerate() {
+		if line.trim().is_empty() || line.starts_with(';') || line.starts_with('#') {
+			continue;
+		}
+		let entry = if sfv {
+			parse_sfv_line(line)
+		} else {
+			parse_bsd_line(line)
+				.or_else(|| parse_gnu_line(line, algorithm))
+				.or_else(|| parse_sfv_line(line))
+		};
+		match entry {
+			Some(entry) if stays_inside(&entry.path) => entries.push(entry),
+			_ => malformed.push(index + 1),
+		}
+	}
+	(entries, malformed)
+}
+
+/// Whether `path` names something under the manifest's directory: not absolute
+/// and never climbing out through `..`.
+fn stays_inside(path: &str) -> bool {
+	Path::new(path)
+		.components()
+		.all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// Hashes one file, passing on progress as `(processed, total)`.
+fn hash_file(
+	path: &Path,
+	algorithm: HashAlgorithm,
+	output_length: Option<usize>,
+	progress: &mut dyn FnMut(HashProgress),
+) -> Result<Vec<u8>, String> {
+	let mut hasher = Hasher::new(algorithm, output_length)?;
+	stream_file(path, &mut |chunk| hasher.update(chunk), progress)?;
+	Ok(hasher.finalize())
+}
+
+/// Only SHAKE, BLAKE2 and BLAKE3 take their length from the manifest.
+fn output_length(algorithm: HashAlgorithm, hex_len: usize) -> Option<usize> {
+	matches!(
+		algorithm,
+		HashAlgorithm::Shake128
+			| HashAlgorithm::Shake256
+			| HashAlgorithm::Blake2b
+			| HashAlgorithm::Blake2s
+			| HashAlgorithm::Blake3
+	)
+	.then_some(hex_len / 2)
+}
+
+pub(super) fn verify_manifest(
+	request: &ChecksumVerifyRequest,
+	progress: &mut dyn FnMut(ChecksumProgress),
+) -> Result<ChecksumReport, String> {
+	let manifest_path = Path::new(request.manifest_path.trim());
+	let content =
+		fs::read_to_string(manifest_path).map_err(|e| format!("Failed to read manifest: {e}"))?;
+	let base = manifest_path.parent().unwrap_or(Path::new(""));
+	let sfv = manifest_path
+		.extension()
+		.is_some_and(|ext| ext.eq_ignore_ascii_case("sfv"));
+	let algorithm = request
+		.algorithm
+		.or_else(|| algorithm_from_manifest_name(manifest_path));
+	let (entries, malformed_lines) = parse_manifest(&content, sfv, algorithm);
+	if entries.is_empty() {
+		return Err("No checksum entries found in the manifest".to_string());
+	}
+
+	let files = entries.len();
+	let mut report = ChecksumReport {
+		entries: Vec::with_capacity(files),
+		ok: 0,
+		mismatched: 0,
+		missing: 0,
+		unreadable: 0,
+		malformed_lines,
+	};
+	for (file, entry) in entries.into_iter().enumerate() {
+		let path = base.join(&entry.path);
+		let result = hash_file(
+			&path,
+			entry.algorithm,
+			output_length(entry.algorithm, entry.expected.len()),
+			&mut |p| {
+				progress(ChecksumProgress {
+					path: entry.path.clone(),
+					file,
+					files,
+					processed: p.processed,
+					total: p.total,
+				})
+			},
+		);
+		let (status, actual, error) = match result {
+			Ok(digest) => {
+				let actual = HEXLOWER.encode(&digest);
+				let status = if actual == entry.expected {
+					ChecksumStatus::Ok
+				} else {
+					ChecksumStatus::Mismatch
+				};
+				(status, Some(actual), None)
+			}
+			Err(e) if fs::metadata(&path).is_err_and(|m| m.kind() == ErrorKind::NotFound) => {
+				(ChecksumStatus::Missing, None, Some(e))
+			}
+			Err(e) => (ChecksumStatus::Unreadable, None, Some(e)),
+		};
+		match status {
+			ChecksumStatus::Ok => report.ok += 1,
+			ChecksumStatus::Mismatch => report.mismatched += 1,
+			ChecksumStatus::Missing => report.missing += 1,
+			ChecksumStatus::Unreadable => report.unreadable += 1,
+		}
+		report.entries.push(ChecksumEntryResult {
+			path: entry.path,
+			algorithm: entry.algorithm,
+			status,
+			expected: entry.expected,
+			actual,
+			error,
+		});
+	}
+	Ok(report)
+}
+
+/// Files under `dir` as paths relative to `root`, sorted.
+fn collect_files(
+	root: &Path,
+	dir: &Path,
+	recursive: bool,
+	skip: Option<&Path>,
+	out: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+	let read_dir =
+		fs::read_dir(dir).map_err(|e| format!("Failed to list {}: {e}", dir.display()))?;
+	let mut children = read_dir
+		.map(|entry| entry.map(|entry| entry.path()))
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(|e| format!("Failed to list {}: {e}", dir.display()))?;
+	children.sort();
+	for path in children {
+		if path.is_dir() {
+			// Symlinked folders are not followed, so link cycles cannot recurse forever.
+			if recursive && !path.is_symlink() {
+				collect_files(root, &path, recursive, skip, out)?;
+			}
+		} else if path.is_file() && skip.is_none_or(|skip| !same_file(&path, skip)) {
+			out.push(path.strip_prefix(root).unwrap_or(&path).to_path_buf());
+		}
+	}
+	Ok(())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+	match (fs::canonicalize(a), fs::canonicalize(b)) {
+		(Ok(a), Ok(b)) => a == b,
+		_ => false,
+	}
+}
+
+/// Manifest paths always use `/`, which coreutils and most Windows tools accept.
+fn manifest_path_text(path: &Path) -> String {
+	path.components()
+		.map(|c| c.as_os_str().to_string_lossy())
+		.collect::<Vec<_>>()
+		.join("/")
+}
+
+pub(super) fn generate_manifest(
+	request: &ChecksumGenerateRequest,
+	progress: &mut dyn FnMut(ChecksumProgress),
+) -> Result<ChecksumManifest, String> {
+	let folder = Path::new(request.folder.trim());
+	if !folder.is_dir() {
+		return Err(format!("{} is not a folder", folder.display()));
+	}
+	let algorithm = match request.format {
+		ManifestFormat::Sfv => HashAlgorithm::Crc32,
+		_ => request.algorithm,
+	};
+	let output_path = request.output_path.as_deref().map(str::trim);
+	let mut paths = Vec::new();
+	collect_files(
+		folder,
+		folder,
+		request.recursive,
+		output_path.map(Path::new),
+		&mut paths,
+	)?;
+
+	let files = paths.len();
+	let mut content = String::new();
+	if request.format == ManifestFormat::Sfv {
+		content.push_str("; Generated by TroveKit\n");
+	}
+	for (file, relative) in paths.iter().enumerate() {
+		let name = manifest_path_text(relative);
+		let digest = hash_file(&folder.join(relative), algorithm, None, &mut |p| {
+			progress(ChecksumProgress {
+				path: name.clone(),
+				file,
+				files,
+				processed: p.processed,
+				total: p.total,
+			})
+		})?;
+		let line = match request.format {
+			ManifestFormat::Gnu => match escape_gnu_path(&name) {
+				Some(escaped) => format!("\\{}  {escaped}", HEXLOWER.encode(&digest)),
+				None => format!("{}  {name}", HEXLOWER.encode(&digest)),
+			},
+			ManifestFormat::Bsd => format!(
+				"{} ({name}) = {}",
+				bsd_tag(algorithm),
+				HEXLOWER.encode(&digest)
+			),
+			ManifestFormat::Sfv => format!("{name} {}", HEXUPPER.encode(&digest)),
+		};
+		content.push_str(&line);
+		content.push('\n');
+	}
+
+	if let Some(output_path) = output_path {
+		fs::write(output_path, &content).map_err(|e| format!("Failed to write manifest: {e}"))?;
+	}
+	Ok(ChecksumManifest { content, files })
+}
+
+#[tauri::command(async)]
+pub fn checksum_verify(
+	request: ChecksumVerifyRequest,
+	on_progress: Channel<ChecksumProgress>,
+) -> Result<ChecksumReport, String> {
+	verify_manifest(&request, &mut |progress| {
+		let _ = on_progress.send(progress);
+	})
+}
+
+#[tauri::command(async)]
+pub fn checksum_generate(
+	request: ChecksumGenerateRequest,
+	on_progress: Channel<ChecksumProgress>,
+) -> Result<ChecksumManifest, String> {
+	generate_manifest(&request, &mut |progress| {
+		let _ = on_progress.send(progress);
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+	const ABC_MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+
+	fn temp_folder(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("trovekit-checksum-{name}"));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(dir.join("sub")).unwrap();
+		fs::write(dir.join("a.txt"), "abc").unwrap();
+		fs::write(dir.join("sub").join("b.txt"), "123456789").unwrap();
+		dir
+	}
+
+	fn verify(manifest: &Path, algorithm: Option<HashAlgorithm>) -> ChecksumReport {
+		verify_manifest(
+			&ChecksumVerifyRequest {
+				manifest_path: manifest.to_str().unwrap().to_string(),
+				algorithm,
+			},
+			&mut |_| {},
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn parses_every_manifest_style() {
+		let content = format!(
+			"# comment\n\
+			 {ABC_SHA256}  a.txt\n\
+			 {ABC_MD5} *b c.txt\n\
+			 \\{ABC_SHA256}  back\\\\slash\n\
+			 SHA256 (x.bin) = {ABC_SHA256}\n\
+			 MD5(y.bin)= {ABC_MD5}\n\
+			 BLAKE2b-256 (z.bin) = {ABC_SHA256}\n\
+			 movie part.avi CBF43926\n\
+			 not a checksum line\n"
+		);
+		let (entries, malformed) = parse_manifest(&content, false, None);
+		let summary: Vec<_> = entries
+			.iter()
+			.map(|e| (e.path.as_str(), e.algorithm))
+			.collect();
+		assert_eq!(
+			summary,
+			[
+				("a.txt", HashAlgorithm::Sha256),
+				("b c.txt", HashAlgorithm::Md5),
+				("back\\slash", HashAlgorithm::Sha256),
+				("x.bin", HashAlgorithm::Sha256),
+				("y.bin", HashAlgorithm::Md5),
+				("z.bin", HashAlgorithm::Blake2b),
+				("movie part.avi", HashAlgorithm::Crc32),
+			]
+		);
+		assert_eq!(entries[6].expected, "cbf43926");
+		assert_eq!(malformed, [9]);
+	}
+
+	#[test]
+	fn verify_reports_ok_mismatch_and_missing() {
+		let dir = temp_folder("verify");
+		let manifest = dir.join("SHA256SUMS");
+		fs::write(
+			&manifest,
+			format!("{ABC_SHA256}  a.txt\n{ABC_SHA256}  sub/b.txt\n{ABC_SHA256}  gone.txt\n"),
+		)
+		.unwrap();
+		let report = verify(&manifest, None);
+		let sfv = dir.join("check.sfv");
+		fs::write(&sfv, "; comment\nsub/b.txt CBF43926\n").unwrap();
+		let sfv_report = verify(&sfv, None);
+		fs::remove_dir_all(&dir).unwrap();
+
+		let statuses: Vec<_> = report.entries.iter().map(|e| e.status).collect();
+		assert_eq!(
+			statuses,
+			[
+				ChecksumStatus::Ok,
+				ChecksumStatus::Mismatch,
+				ChecksumStatus::Missing
+			]
+		);
+		assert_eq!((report.ok, report.mismatched, report.missing), (1, 1, 1));
+		assert_eq!(sfv_report.ok, 1);
+	}
+
+	#[test]
+	fn entries_outside_the_manifest_folder_are_malformed() {
+		let dir = temp_folder("escape");
+		let manifest = dir.join("sub").join("SHA256SUMS");
+		fs::write(
+			&manifest,
+			format!(
+				"{ABC_SHA256}  ../a.txt\n\
+				 {ABC_SHA256}  {}\n\
+				 SHA256 (./b/../../a.txt) = {ABC_SHA256}\n\
+				 ../a.txt CBF43926\n\
+				 {ABC_SHA256}  ./b.txt\n",
+				dir.join("a.txt").display()
+			),
+		)
+		.unwrap();
+		let report = verify(&manifest, None);
+		fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!(report.malformed_lines, [1, 2, 3, 4]);
+		assert_eq!(report.entries.len(), 1);
+		assert_eq!(report.entries[0].path, "./b.txt");
+		assert_eq!(report.entries[0].status, ChecksumStatus::Mismatch);
+	}
+
+	#[test]
+	fn generated_manifests_verify() {
+		let dir = temp_folder("generate");
+		let mut reports = Vec::new();
+		for (format, name) in [
+			(ManifestFormat::Gnu, "files.md5"),
+			(ManifestFormat::Bsd, "files.tag"),
+			(ManifestFormat::Sfv, "files.sfv"),
+		] {
+			let output = dir.join(name);
+			let manifest = generate_manifest(
+				&ChecksumGenerateRequest {
+					folder: dir.to_str().unwrap().to_string(),
+					format,
+					algorithm: HashAlgorithm::Md5,
+					recursive: true,
+					output_path: Some(output.to_str().unwrap().to_string()),
+				},
+				&mut |_| {},
+			)
+			.unwrap();
+			reports.push((manifest, verify(&output, None)));
+			fs::remove_file(&output).unwrap();
+		}
+		fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!(
+			reports[0].0.content,
+			format!("{ABC_MD5}  a.txt\n25f9e794323b453885f5181f1b624d0b  sub/b.txt\n")
+		);
+		assert!(reports[1].0.content.starts_with("MD5 (a.txt) = "));
+		assert!(reports[2].0.content.contains("sub/b.txt CBF43926\n"));
+		for (manifest, report) in &reports {
+			assert_eq!(manifest.files, 2);
+			assert_eq!(report.ok, 2, "{}", manifest.content);
+		}
+	}
+}
@@ -0,0 +1,704 @@
+//! Parameterised CRC engine (the Rocksoft model: width, poly, init, refin,
+//! refout, xorout) with the CRC-RevEng catalogue of named CRCs from 3 to 64
+//! bits, and a search for the catalogue entries that produce a given
+//! checksum over some input.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::encoding::{decode_bytes, DataFormat};
+use super::hash::{stream_file, HashInputType};
+
+/// One CRC in the Rocksoft model. `check` is the CRC of the ASCII string
+/// "123456789".
+#[derive(Debug, Clone, Copy)]
+pub(super) struct CrcSpec {
+	name: &'static str,
+	width: u32,
+	poly: u64,
+	init: u64,
+	refin: bool,
+	refout: bool,
+	xorout: u64,
+	check: u64,
+}
+
+/// A catalogue entry; `init` and `xorout` are cut down to `width` bits, so
+/// `!0` means all ones.
+const fn model(
+	name: &'static str,
+	width: u32,
+	poly: u64,
+	init: u64,
+	reflected: bool,
+	xorout: u64,
+	check: u64,
+) -> CrcSpec {
+	CrcSpec {
+		name,
+		width,
+		poly,
+		init,
+		refin: reflected,
+		refout: reflected,
+		xorout,
+		check,
+	}
+}
+
+/// The CRC-32 of zip, gzip, PNG and SFV files.
+pub(super) const CRC_32_ISO_HDLC: CrcSpec =
+	model("CRC-32/ISO-HDLC", 32, 0x04c11db7, !0, true, !0, 0xcbf43926);
+
+const CATALOGUE: &[CrcSpec] = &[
+	model("CRC-3/GSM", 3, 0x3, 0, false, !0, 0x4),
+	model("CRC-3/ROHC", 3, 0x3, !0, true, 0, 0x6),
+	model("CRC-4/G-704", 4, 0x3, 0, true, 0, 0x7),
+	model("CRC-4/INTERLAKEN", 4, 0x3, !0, false, !0, 0xb),
+	model("CRC-5/EPC-C1G2", 5, 0x09, 0x09, false, 0, 0x00),
+	model("CRC-5/G-704", 5, 0x15, 0, true, 0, 0x07),
+	model("CRC-5/USB", 5, 0x05, !0, true, !0, 0x19),
+	model("CRC-6/CDMA2000-A", 6, 0x27, !0, false, 0, 0x0d),
+	model("CRC-6/CDMA2000-B", 6, 0x07, !0, false, 0, 0x3b),
+	model("CRC-6/DARC", 6, 0x19, 0, true, 0, 0x26),
+	model("CRC-6/G-704", 6, 0x03, 0, true, 0, 0x06),
+	model("CRC-6/GSM", 6, 0x2f, 0, false, !0, 0x13),
+	model("CRC-7/MMC", 7, 0x09, 0, false, 0, 0x75),
+	model("CRC-7/ROHC", 7, 0x4f, !0, true, 0, 0x53),
+	model("CRC-7/UMTS", 7, 0x45, 0, false, 0, 0x61),
+	model("CRC-8/AUTOSAR", 8, 0x2f, !0, false, !0, 0xdf),
+	model("CRC-8/BLUETOOTH", 8, 0xa7, 0, true, 0, 0x26),
+	model("CRC-8/CDMA2000", 8, 0x9b, !0, false, 0, 0xda),
+	model("CRC-8/DARC", 8, 0x39, 0, true, 0, 0x15),
+	model("CRC-8/DVB-S2", 8, 0xd5, 0, false, 0, 0xbc),
+	model("CRC-8/GSM-A", 8, 0x1d, 0, false, 0, 0x37),
+	model("CRC-8/GSM-B", 8, 0x49, 0, false, !0, 0x94),
+	model("CRC-8/HITAG", 8, 0x1d, !0, false, 0, 0xb4),
+	model("CRC-8/I-432-1", 8, 0x07, 0, false, 0x55, 0xa1),
+	model("CRC-8/I-CODE", 8, 0x1d, 0xfd, false, 0, 0x7e),
+	model("CRC-8/LTE", 8, 0x9b, 0, false, 0, 0xea),
+	model("CRC-8/MAXIM-DOW", 8, 0x31, 0, true, 0, 0xa1),
+	model("CRC-8/MIFARE-MAD", 8, 0x1d, 0xc7, false, 0, 0x99),
+	model("CRC-8/NRSC-5", 8, 0x31, !0, false, 0, 0xf7),
+	model("CRC-8/OPENSAFETY", 8, 0x2f, 0, false, 0, 0x3e),
+	model("CRC-8/ROHC", 8, 0x07, !0, true, 0, 0xd0),
+	model("CRC-8/SAE-J1850", 8, 0x1d, !0, false, !0, 0x4b),
+	model("CRC-8/SMBUS", 8, 0x07, 0, false, 0, 0xf4),
+	model("CRC-8/TECH-3250", 8, 0x1d, !0, true, 0, 0x97),
+	model("CRC-8/WCDMA", 8, 0x9b, 0, true, 0, 0x25),
+	model("CRC-10/ATM", 10, 0x233, 0, false, 0, 0x199),
+	model("CRC-10/CDMA2000", 10, 0x3d9, !0, false, 0, 0x233),
+	model("CRC-10/GSM", 10, 0x175, 0, false, !0, 0x12a),
+	model("CRC-11/FLEXRAY", 11, 0x385, 0x01a, false, 0, 0x5a3),
+	model("CRC-11/UMTS", 11, 0x307, 0, false, 0, 0x061),
+	model("CRC-12/CDMA2000", 12, 0xf13, !0, false, 0, 0xd4d),
+	model("CRC-12/DECT", 12, 0x80f, 0, false, 0, 0xf5b),
+	model("CRC-12/GSM", 12, 0xd31, 0, false, !0, 0xb34),
+	// Reflected on output only, so it cannot go through `model`.
+	CrcSpec {
+		name: "CRC-12/UMTS",
+		width: 12,
+		poly: 0x80f,
+		init: 0,
+		refin: false,
+		refout: true,
+		xorout: 0,
+		check: 0xdaf,
+	},
+	model("CRC-13/BBC", 13, 0x1cf5, 0, false, 0, 0x04fa),
+	model("CRC-14/DARC", 14, 0x0805, 0, true, 0, 0x082d),
+	model("CRC-14/GSM", 14, 0x202d, 0, false, !0, 0x30ae),
+	model("CRC-15/CAN", 15, 0x4599, 0, false, 0, 0x059e),
+	model("CRC-15/MPT1327", 15, 0x6815, 0, false, 0x0001, 0x2566),
+	model("CRC-16/ARC", 16, 0x8005, 0, true, 0, 0xbb3d),
+	model("CRC-16/CDMA2000", 16, 0xc867, !0, false, 0, 0x4c06),
+	model("CRC-16/CMS", 16, 0x8005, !0, false, 0, 0xaee7),
+	model("CRC-16/DDS-110", 16, 0x8005, 0x800d, false, 0, 0x9ecf),
+	model("CRC-16/DECT-R", 16, 0x0589, 0, false, 0x0001, 0x007e),
+	model("CRC-16/DECT-X", 16, 0x0589, 0, false, 0, 0x007f),
+	model("CRC-16/DNP", 16, 0x3d65, 0, true, !0, 0xea82),
+	model("CRC-16/EN-13757", 16, 0x3d65, 0, false, !0, 0xc2b7),
+	model("CRC-16/GENIBUS", 16, 0x1021, !0, false, !0, 0xd64e),
+	model("CRC-16/GSM", 16, 0x1021, 0, false, !0, 0xce3c),
+	model("CRC-16/IBM-3740", 16, 0x1021, !0, false, 0, 0x29b1),
+	model("CRC-16/IBM-SDLC", 16, 0x1021, !0, true, !0, 0x906e),
+	model(
+		"CRC-16/ISO-IEC-14443-3-A",
+		16,
+		0x1021,
+		0xc6c6,
+		true,
+		0,
+		0xbf05,
+	),
+	model("CRC-16/KERMIT", 16, 0x1021, 0, true, 0, 0x2189),
+	model("CRC-16/LJ1200", 16, 0x6f63, 0, false, 0, 0xbdf4),
+	model("CRC-16/M17", 16, 0x5935, !0, false, 0, 0x772b),
+	model("CRC-16/MAXIM-DOW", 16, 0x8005, 0, true, !0, 0x44c2),
+	model("CRC-16/MCRF4XX", 16, 0x1021, !0, true, 0, 0x6f91),
+	model("CRC-16/MODBUS", 16, 0x8005, !0, true, 0, 0x4b37),
+	model("CRC-16/NRSC-5", 16, 0x080b, !0, true, 0, 0xa066),
+	model("CRC-16/OPENSAFETY-A", 16, 0x5935, 0, false, 0, 0x5d38),
+	model("CRC-16/OPENSAFETY-B", 16, 0x755b, 0, false, 0, 0x20fe),
+	model("CRC-16/PROFIBUS", 16, 0x1dcf, !0, false, !0, 0xa819),
+	model("CRC-16/RIELLO", 16, 0x1021, 0xb2aa, true, 0, 0x63d0),
+	model("CRC-16/SPI-FUJITSU", 16, 0x1021, 0x1d0f, false, 0, 0xe5cc),
+	model("CRC-16/T10-DIF", 16, 0x8bb7, 0, false, 0, 0xd0db),
+	model("CRC-16/TELEDISK", 16, 0xa097, 0, false, 0, 0x0fb3),
+	model("CRC-16/TMS37157", 16, 0x1021, 0x89ec, true, 0, 0x26b1),
+	model("CRC-16/UMTS", 16, 0x8005, 0, false, 0, 0xfee8),
+	model("CRC-16/USB", 16, 0x8005, !0, true, !0, 0xb4c8),
+	model("CRC-16/XMODEM", 16, 0x1021, 0, false, 0, 0x31c3),
+	model("CRC-17/CAN-FD", 17, 0x1685b, 0, false, 0, 0x04f03),
+	model("CRC-21/CAN-FD", 21, 0x102899, 0, false, 0, 0x0ed841),
+	model("CRC-24/BLE", 24, 0x00065b, 0x555555, true, 0, 0xc25a56),
+	model(
+		"CRC-24/FLEXRAY-A",
+		24,
+		0x5d6dcb,
+		0xfedcba,
+		false,
+		0,
+		0x7979bd,
+	),
+	model(
+		"CRC-24/FLEXRAY-B",
+		24,
+		0x5d6dcb,
+		0xabcdef,
+		false,
+		0,
+		0x1f23b8,
+	),
+	model("CRC-24/INTERLAKEN", 24, 0x328b63, !0, false, !0, 0xb4f3e6),
+	model("CRC-24/LTE-A", 24, 0x864cfb, 0, false, 0, 0xcde703),
+	model("CRC-24/LTE-B", 24, 0x800063, 0, false, 0, 0x23ef52),
+	model("CRC-24/OPENPGP", 24, 0x864cfb, 0xb704ce, false, 0, 0x21cf02),
+	model("CRC-24/OS-9", 24, 0x800063, !0, false, !0, 0x200fa5),
+	model("CRC-30/CDMA", 30, 0x2030b9c7, !0, false, !0, 0x04c34abf),
+	model("CRC-31/PHILIPS", 31, 0x04c11db7, !0, false, !0, 0x0ce9e46c),
+	model("CRC-32/AIXM", 32, 0x814141ab, 0, false, 0, 0x3010bf7f),
+	model("CRC-32/AUTOSAR", 32, 0xf4acfb13, !0, true, !0, 0x1697d06a),
+	model("CRC-32/BASE91-D", 32, 0xa833982b, !0, true, !0, 0x87315576),
+	model("CRC-32/BZIP2", 32, 0x04c11db7, !0, false, !0, 0xfc891918),
+	model("CRC-32/CD-ROM-EDC", 32, 0x8001801b, 0, true, 0, 0x6ec2edc4),
+	model("CRC-32/CKSUM", 32, 0x04c11db7, 0, false, !0, 0x765e7680),
+	model("CRC-32/ISCSI", 32, 0x1edc6f41, !0, true, !0, 0xe3069283),
+	CRC_32_ISO_HDLC,
+	model("CRC-32/JAMCRC", 32, 0x04c11db7, !0, true, 0, 0x340bc6d9),
+	model("CRC-32/MEF", 32, 0x741b8cd7, !0, true, 0, 0xd2c22f51),
+	model("CRC-32/MPEG-2", 32, 0x04c11db7, !0, false, 0, 0x0376e6e7),
+	model("CRC-32/XFER", 32, 0x000000af, 0, false, 0, 0xbd0be338),
+	model("CRC-40/GSM", 40, 0x0004820009, 0, false, !0, 0xd4164fc646),
+	model(
+		"CRC-64/ECMA-182",
+		64,
+		0x42f0e1eba9ea3693,
+		0,
+		false,
+		0,
+		0x6c40df5f0b497347,
+	),
+	model(
+		"CRC-64/GO-ISO",
+		64,
+		0x000000000000001b,
+		!0,
+		true,
+		!0,
+		0xb90956c775a41001,
+	),
+	model(
+		"CRC-64/MS",
+		64,
+		0x259c84cba6426349,
+		!0,
+		true,
+		0,
+		0x75d4b74f024eceea,
+	),
+	model(
+		"CRC-64/NVME",
+		64,
+		0xad93d23594c93659,
+		!0,
+		true,
+		!0,
+		0xae8b14860a799888,
+	),
+	model(
+		"CRC-64/REDIS",
+		64,
+		0xad93d23594c935a9,
+		0,
+		true,
+		0,
+		0xe9c6d914c4b8d9ca,
+	),
+	model(
+		"CRC-64/WE",
+		64,
+		0x42f0e1eba9ea3693,
+		!0,
+		false,
+		!0,
+		0x62ec59e3f1a4f00a,
+	),
+	model(
+		"CRC-64/XZ",
+		64,
+		0x42f0e1eba9ea3693,
+		!0,
+		true,
+		!0,
+		0x995dc9bbdf1939fa,
+	),
+];
+
+/// Other names in use for catalogue entries.
+const ALIASES: &[(&str, &str)] = &[
+	("CRC-4/ITU", "CRC-4/G-704"),
+	("CRC-5/EPC", "CRC-5/EPC-C1G2"),
+	("CRC-5/ITU", "CRC-5/G-704"),
+	("CRC-6/ITU", "CRC-6/G-704"),
+	("CRC-7", "CRC-7/MMC"),
+	("CRC-8", "CRC-8/SMBUS"),
+	("CRC-8/AES", "CRC-8/TECH-3250"),
+	("CRC-8/EBU", "CRC-8/TECH-3250"),
+	("CRC-8/ITU", "CRC-8/I-432-1"),
+	("CRC-8/MAXIM", "CRC-8/MAXIM-DOW"),
+	("DOW-CRC", "CRC-8/MAXIM-DOW"),
+	("CRC-10", "CRC-10/ATM"),
+	("CRC-10/I-610", "CRC-10/ATM"),
+	("X-CRC-12", "CRC-12/DECT"),
+	("CRC-12/3GPP", "CRC-12/UMTS"),
+	("CRC-15", "CRC-15/CAN"),
+	("CRC-16", "CRC-16/ARC"),
+	("CRC-16/LHA", "CRC-16/ARC"),
+	("CRC-IBM", "CRC-16/ARC"),
+	("CRC-16/DARC", "CRC-16/GENIBUS"),
+	("CRC-16/EPC", "CRC-16/GENIBUS"),
+	("CRC-16/I-CODE", "CRC-16/GENIBUS"),
+	("CRC-16/CCITT-FALSE", "CRC-16/IBM-3740"),
+	("CRC-16/AUTOSAR", "CRC-16/IBM-3740"),
+	("CRC-16/X-25", "CRC-16/IBM-SDLC"),
+	("CRC-16/ISO-HDLC", "CRC-16/IBM-SDLC"),
+	("CRC-B", "CRC-16/IBM-SDLC"),
+	("CRC-A", "CRC-16/ISO-IEC-14443-3-A"),
+	("CRC-16/CCITT", "CRC-16/KERMIT"),
+	("CRC-16/CCITT-TRUE", "CRC-16/KERMIT"),
+	("CRC-16/V-41-LSB", "CRC-16/KERMIT"),
+	("CRC-16/MAXIM", "CRC-16/MAXIM-DOW"),
+	("CRC-16/AUG-CCITT", "CRC-16/SPI-FUJITSU"),
+	("CRC-16/BUYPASS", "CRC-16/UMTS"),
+	("CRC-16/VERIFONE", "CRC-16/UMTS"),
+	("CRC-16/ACORN", "CRC-16/XMODEM"),
+	("CRC-16/LTE", "CRC-16/XMODEM"),
+	("CRC-16/V-41-MSB", "CRC-16/XMODEM"),
+	("ZMODEM", "CRC-16/XMODEM"),
+	("CRC-24", "CRC-24/OPENPGP"),
+	("CRC-32Q", "CRC-32/AIXM"),
+	("CRC-32D", "CRC-32/BASE91-D"),
+	("CRC-32/AAL5", "CRC-32/BZIP2"),
+	("CRC-32/DECT-B", "CRC-32/BZIP2"),
+	("CRC-32/POSIX", "CRC-32/CKSUM"),
+	("CRC-32C", "CRC-32/ISCSI"),
+	("CRC-32/CASTAGNOLI", "CRC-32/ISCSI"),
+	("CRC-32/INTERLAKEN", "CRC-32/ISCSI"),
+	("CRC-32", "CRC-32/ISO-HDLC"),
+	("CRC-32/ADCCP", "CRC-32/ISO-HDLC"),
+	("CRC-32/V-42", "CRC-32/ISO-HDLC"),
+	("CRC-32/XZ", "CRC-32/ISO-HDLC"),
+	("PKZIP", "CRC-32/ISO-HDLC"),
+	("CRC-64", "CRC-64/ECMA-182"),
+	("CRC-64/GO-ECMA", "CRC-64/XZ"),
+];
+
+/// Custom CRC parameters. Values are hex strings, since 64-bit polynomials
+/// do not fit in a JavaScript number.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrcParams {
+	/// 1-64 bits.
+	pub width: u32,
+	/// Normal (MSB-first) form, without the implicit top bit.
+	pub poly: String,
+	pub init: String,
+	pub refin: bool,
+	pub refout: bool,
+	pub xorout: String,
+}
+
+/// A CRC's parameters as shown to the user, values in hex.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrcModel {
+	pub name: String,
+	pub aliases: Vec<String>,
+	pub width: u32,
+	pub poly: String,
+	pub init: String,
+	pub refin: bool,
+	pub refout: bool,
+	pub xorout: String,
+	/// CRC of "123456789".
+	pub check: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrcRequest {
+	pub input: String,
+	pub input_type: HashInputType,
+	/// Catalogue name or alias, matched without regard to case.
+	pub algorithm: Option<String>,
+	/// Used when `algorithm` is absent.
+	pub params: Option<CrcParams>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrcResult {
+	/// Hex, zero-padded to the CRC's width.
+	pub value: String,
+	pub model: CrcModel,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrcFindRequest {
+	pub input: String,
+	pub input_type: HashInputType,
+	/// Hex, with or without `0x`.
+	pub expected: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrcMatch {
+	pub model: CrcModel,
+	/// The CRC matched only with its bytes reversed, as when a
+	/// little-endian target stores it in memory.
+	pub byte_swapped: bool,
+}
+
+fn mask(width: u32) -> u64 {
+	if width == 64 {
+		!0
+	} else {
+		(1 << width) - 1
+	}
+}
+
+fn reflect(value: u64, width: u32) -> u64 {
+	value.reverse_bits() >> (64 - width)
+}
+
+/// A running CRC. Reflected CRCs are computed LSB-first; the others are
+/// kept in the top bits of the register so any width shares one table.
+pub(super) struct Crc {
+	spec: CrcSpec,
+	table: Box<[u64; 256]>,
+	register: u64,
+}
+
+impl Crc {
+	pub(super) fn new(spec: CrcSpec) -> Self {
+		let mask = mask(spec.width);
+		let shift = 64 - spec.width;
+		let mut table = Box::new([0u64; 256]);
+		let register = if spec.refin {
+			let poly = reflect(spec.poly & mask, spec.width);
+			for (i, entry) in table.iter_mut().enumerate() {
+				*entry = (0..8).fold(i as u64, |crc, _| {
+					if crc & 1 == 1 {
+						(crc >> 1) ^ poly
+					} else {
+						crc >> 1
+					}
+				});
+			}
+			reflect(spec.init & mask, spec.width)
+		} else {
+			let poly = (spec.poly & mask) << shift;
+			for (i, entry) in table.iter_mut().enumerate() {
+				*entry = (0..8).fold((i as u64) << 56, |crc, _| {
+					if crc >> 63 == 1 {
+						(crc << 1) ^ poly
+					} else {
+						crc << 1
+					}
+				});
+			}
+			(spec.init & mask) << shift
+		};
+		Crc {
+			spec,
+			table,
+			register,
+		}
+	}
+
+	pub(super) fn update(&mut self, data: &[u8]) {
+		let table = &self.table;
+		self.register = if self.spec.refin {
+			data.iter().fold(self.register, |crc, &byte| {
+				table[((crc ^ u64::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+			})
+		} else {
+			data.iter().fold(self.register, |crc, &byte| {
+				table[(((crc >> 56) ^ u64::from(byte)) & 0xff) as usize] ^ (crc << 8)
+			})
+		};
+	}
+
+	pub(super) fn value(&self) -> u64 {
+		let spec = &self.spec;
+		let crc = if spec.refin {
+			self.register
+		} else {
+			self.register >> (64 - spec.width)
+		};
+		let crc = if spec.refin != spec.refout {
+			reflect(crc, spec.width)
+		} else {
+			crc
+		};
+		(crc ^ spec.xorout) & mask(spec.width)
+	}
+
+	/// The CRC as big-endian bytes, as many as its width needs.
+	pub(super) fn to_be_bytes(&self) -> Vec<u8> {
+		let len = self.spec.width.div_ceil(8) as usize;
+		self.value().to_be_bytes()[8 - len..].to_vec()
+	}
+}
+
+fn to_hex(value: u64, width: u32) -> String {
+	format!("{value:0digits$x}", digits = width.div_ceil(4) as usize)
+}
+
+fn parse_hex(value: &str, what: &str) -> Result<u64, String> {
+	let value = value.trim();
+	let digits = value
+		.strip_prefix("0x")
+		.or_else(|| value.strip_prefix("0X"))
+		.unwrap_or(value);
+	u64::from_str_radix(digits, 16).map_err(|_| format!("{what} must be at most 16 hex digits"))
+}
+
+fn describe(spec: &CrcSpec) -> CrcModel {
+	let hex = |value: u64| to_hex(value & mask(spec.width), spec.width);
+	CrcModel {
+		name: spec.name.to_string(),
+		aliases: ALIASES
+			.iter()
+			.filter(|(_, name)| *name == spec.name)
+			.map(|(alias, _)| alias.to_string())
+			.collect(),
+		width: spec.width,
+		poly: hex(spec.poly),
+		init: hex(spec.init),
+		refin: spec.refin,
+		refout: spec.refout,
+		xorout: hex(spec.xorout),
+		check: hex(spec.check),
+	}
+}
+
+fn lookup(name: &str) -> Result<CrcSpec, String> {
+	let name = name.trim();
+	let name = ALIASES
+		.iter()
+		.find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+		.map_or(name, |(_, canonical)| *canonical);
+	CATALOGUE
+		.iter()
+		.find(|spec| spec.name.eq_ignore_ascii_case(name))
+		.copied()
+		.ok_or_else(|| format!("Unknown CRC '{name}'"))
+}
+
+fn custom(params: &CrcParams) -> Result<CrcSpec, String> {
+	if !(1..=64).contains(&params.width) {
+		return Err(format!("CRC width must be 1-64 bits, got {}", params.width));
+	}
+	let mask = mask(params.width);
+	let field = |value: &str, what: &str| {
+		let value = parse_hex(value, what)?;
+		if value & !mask != 0 {
+			return Err(format!("{what} does not fit in {} bits", params.width));
+		}
+		Ok(value)
+	};
+	let mut spec = CrcSpec {
+		name: "Custom",
+		width: params.width,
+		poly: field(&params.poly, "Polynomial")?,
+		init: field(&params.init, "Init")?,
+		refin: params.refin,
+		refout: params.refout,
+		xorout: field(&params.xorout, "XorOut")?,
+		check: 0,
+	};
+	let mut crc = Crc::new(spec);
+	crc.update(b"123456789");
+	spec.check = crc.value();
+	Ok(spec)
+}
+
+/// Feeds the request's input, decoded or streamed from disk, to `sink`.
+fn read_input(
+	input: &str,
+	input_type: HashInputType,
+	sink: &mut dyn FnMut(&[u8]),
+) -> Result<(), String> {
+	match input_type {
+		HashInputType::Text => sink(input.as_bytes()),
+		HashInputType::Hex => sink(&decode_bytes(input, DataFormat::Hex)?),
+		HashInputType::File => stream_file(Path::new(input.trim()), sink, &mut |_| {})?,
+	}
+	Ok(())
+}
+
+#[tauri::command]
+pub fn crc_catalogue() -> Vec<CrcModel> {
+	CATALOGUE.iter().map(describe).collect()
+}
+
+#[tauri::command(async)]
+pub fn crc_compute(request: CrcRequest) -> Result<CrcResult, String> {
+	let spec = match (&request.algorithm, &request.params) {
+		(Some(name), _) => lookup(name)?,
+		(None, Some(params)) => custom(params)?,
+		(None, None) => return Err("Choose a catalogue CRC or give its parameters".to_string()),
+	};
+	let mut crc = Crc::new(spec);
+	read_input(&request.input, request.input_type, &mut |chunk| {
+		crc.update(chunk)
+	})?;
+	Ok(CrcResult {
+		value: to_hex(crc.value(), spec.width),
+		model: describe(&spec),
+	})
+}
+
+#[tauri::command(async)]
+pub fn crc_find(request: CrcFindRequest) -> Result<Vec<CrcMatch>, String> {
+	let expected = parse_hex(&request.expected, "Expected CRC")?;
+	let mut crcs: Vec<Crc> = CATALOGUE.iter().copied().map(Crc::new).collect();
+	read_input(&request.input, request.input_type, &mut |chunk| {
+		crcs.iter_mut().for_each(|crc| crc.update(chunk))
+	})?;
+	Ok(crcs
+		.iter()
+		.filter_map(|crc| {
+			let width = crc.spec.width;
+			if expected & !mask(width) != 0 {
+				return None;
+			}
+			let value = crc.value();
+			let swapped = expected.swap_bytes() >> (64 - width);
+			let byte_swapped = if value == expected {
+				false
+			} else if width % 8 == 0 && width > 8 && value == swapped {
+				true
+			} else {
+				return None;
+			};
+			Some(CrcMatch {
+				model: describe(&crc.spec),
+				byte_swapped,
+			})
+		})
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn compute(algorithm: &str, input: &str) -> String {
+		crc_compute(CrcRequest {
+			input: input.to_string(),
+			input_type: HashInputType::Text,
+			algorithm: Some(algorithm.to_string()),
+			params: None,
+		})
+		.unwrap()
+		.value
+	}
+
+	#[test]
+	fn catalogue_matches_check_values() {
+		for spec in CATALOGUE {
+			let mut crc = Crc::new(*spec);
+			crc.update(b"1234");
+			crc.update(b"56789");
+			assert_eq!(crc.value(), spec.check, "{}", spec.name);
+		}
+		for (alias, name) in ALIASES {
+			assert_eq!(lookup(alias).unwrap().name, *name);
+		}
+	}
+
+	#[test]
+	fn computes_named_and_custom_crcs() {
+		assert_eq!(compute("crc-16/modbus", "123456789"), "4b37");
+		assert_eq!(compute("CRC-16/CCITT-FALSE", "123456789"), "29b1");
+		assert_eq!(compute("CRC-32C", "123456789"), "e3069283");
+		assert_eq!(compute("CRC-4/G-704", "123456789"), "7");
+
+		let crc64 = crc_compute(CrcRequest {
+			input: "313233343536373839".to_string(),
+			input_type: HashInputType::Hex,
+			algorithm: None,
+			params: Some(CrcParams {
+				width: 64,
+				poly: "0x42F0E1EBA9EA3693".to_string(),
+				init: "ffffffffffffffff".to_string(),
+				refin: true,
+				refout: true,
+				xorout: "ffffffffffffffff".to_string(),
+			}),
+		})
+		.unwrap();
+		assert_eq!(crc64.value, "995dc9bbdf1939fa");
+		assert_eq!(crc64.model.check, "995dc9bbdf1939fa");
+
+		let too_wide = CrcParams {
+			width: 8,
+			poly: "107".to_string(),
+			init: "0".to_string(),
+			refin: false,
+			refout: false,
+			xorout: "0".to_string(),
+		};
+		assert!(custom(&too_wide).is_err());
+	}
+
+	#[test]
+	fn finds_matching_crcs() {
+		let find = |expected: &str| {
+			crc_find(CrcFindRequest {
+				input: "123456789".to_string(),
+				input_type: HashInputType::Text,
+				expected: expected.to_string(),
+			})
+			.unwrap()
+		};
+		let matches = find("0x4B37");
+		assert_eq!(matches.len(), 1);
+		assert_eq!(matches[0].model.name, "CRC-16/MODBUS");
+		assert!(!matches[0].byte_swapped);
+
+		let swapped = find("374b");
+		assert_eq!(swapped[0].model.name, "CRC-16/MODBUS");
+		assert!(swapped[0].byte_swapped);
+
+		// CRC-8/I-432-1 and CRC-8/MAXIM-DOW share the check value 0xa1.
+		let names: Vec<_> = find("a1").into_iter().map(|m| m.model.name).collect();
+		assert_eq!(names, ["CRC-8/I-432-1", "CRC-8/MAXIM-DOW"]);
+	}
+}
@@ -0,0 +1,843 @@
+//! Unix crypt(3) and legacy application password hashes: DES crypt,
+//! md5crypt (`$1$`), Apache's `$apr1$` and `{SHA}`, sha256crypt (`$5$`),
+//! sha512crypt (`$6$`), MySQL 4.1+ `PASSWORD()`, PostgreSQL's `md5` and
+//! `SCRAM-SHA-256` verifiers, and NTLM. Hashes can be generated and checked
+//! against a password; `/etc/shadow` and htpasswd lines are accepted as-is.
+
+use data_encoding::{BASE64, HEXLOWER, HEXUPPER};
+use hmac::{Hmac, Mac};
+use md4::Md4;
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::digest::Digest;
+use sha2::{Sha256, Sha512};
+use subtle::ConstantTimeEq;
+
+use super::kdf::check_iterations;
+use super::secret::{SecretBytes, SecretString, Zeroizing};
+
+/// crypt(3)'s base64 alphabet, used for salts and digests alike.
+const CRYPT_ALPHABET: &[u8; 64] =
+	b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const SHA_CRYPT_DEFAULT_ROUNDS: u32 = 5000;
+const SHA_CRYPT_MIN_ROUNDS: u32 = 1000;
+const SHA_CRYPT_MAX_ROUNDS: u32 = 999_999_999;
+/// PostgreSQL's `scram_iterations` default.
+const SCRAM_DEFAULT_ITERATIONS: u32 = 4096;
+const SCRAM_PREFIX: &str = "SCRAM-SHA-256$";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CryptScheme {
+	/// Traditional 13-character crypt; only the first 8 characters count.
+	DesCrypt,
+	/// `$1$`, 8-character salt.
+	Md5Crypt,
+	/// Apache htpasswd `$apr1$`: md5crypt with a different magic.
+	Apr1,
+	/// Apache htpasswd `{SHA}`: unsalted base64 SHA-1.
+	HtpasswdSha,
+	/// `$5$`, 16-character salt, 5000 rounds by default.
+	Sha256Crypt,
+	/// `$6$`, 16-character salt, 5000 rounds by default.
+	Sha512Crypt,
+	/// `*` followed by upper-case hex SHA1(SHA1(password)).
+	Mysql41,
+	/// `md5` followed by hex MD5(password || username).
+	PostgresMd5,
+	/// `SCRAM-SHA-256$<iterations>:<salt>$<StoredKey>:<ServerKey>`.
+	ScramSha256,
+	/// MD4 of the UTF-16LE password, hex.
+	Ntlm,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CryptHashRequest {
+	pub password: SecretString,
+	pub scheme: CryptScheme,
+	/// As written in the hash: crypt-alphabet text, or base64 for SCRAM.
+	/// Random when absent.
+	pub salt: Option<String>,
+	/// sha256crypt/sha512crypt rounds (written out as `rounds=N$`), or SCRAM
+	/// iterations.
+	pub rounds: Option<u32>,
+	/// PostgreSQL md5 salts the hash with the role name.
+	pub username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CryptVerifyRequest {
+	pub password: SecretString,
+	/// A hash, or a `user:hash[:...]` shadow or htpasswd line.
+	pub hash: String,
+	/// Overrides detection, e.g. to tell NTLM from other bare 32-digit hex.
+	pub scheme: Option<CryptScheme>,
+	/// Needed for PostgreSQL md5 unless the line carries it.
+	pub username: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CryptVerifyResponse {
+	pub matches: bool,
+	pub scheme: CryptScheme,
+	/// Taken from the account line, when one was given.
+	pub username: Option<String>,
+}
+
+pub(super) fn is_crypt_char(c: u8) -> bool {
+	CRYPT_ALPHABET.contains(&c)
+}
+
+fn crypt_index(c: u8) -> Result<u64, String> {
+	CRYPT_ALPHABET
+		.iter()
+		.position(|&a| a == c)
+		.map(|i| i as u64)
+		.ok_or_else(|| format!("'{}' is not a crypt salt character", c as char))
+}
+
+fn random_crypt_salt(len: usize) -> Result<String, String> {
+	let mut bytes = vec![0u8; len];
+	getrandom::fill(&mut bytes).map_err(|e| format!("Random number generator failed: {e}"))?;
+	Ok(bytes
+		.iter()
+		.map(|b| CRYPT_ALPHABET[(b & 0x3f) as usize] as char)
+		.collect())
+}
+
+/// crypt's digest encoding: bytes taken three at a time in `order`, each
+/// group written as 24 bits least-significant character first; a one- or
+/// two-byte tail gives two or three characters.
+fn crypt_b64(digest: &[u8], order: &[usize]) -> String {
+	let mut out = String::new();
+	for group in order.chunks(3) {
+		let (value, chars) = match *group {
+			[a, b, c] => (
+				(u32::from(digest[a]) << 16) | (u32::from(digest[b]) << 8) | u32::from(digest[c]),
+				4,
+			),
+			[b, c] => ((u32::from(digest[b]) << 8) | u32::from(digest[c]), 3),
+			[c] => (u32::from(digest[c]), 2),
+			_ => unreachable!("chunks(3)"),
+		};
+		for i in 0..chars {
+			out.push(CRYPT_ALPHABET[((value >> (6 * i)) & 0x3f) as usize] as char);
+		}
+	}
+	out
+}
+
+// ---- DES crypt ----
+
+const IP: [u8; 64] = [
+	58, 50, 42, 34, 26, 18, 10, 2, 60, 52, 44, 36, 28, 20, 12, 4, 62, 54, 46, 38, 30, 22, 14, 6,
+	64, 56, 48, 40, 32, 24, 16, 8, 57, 49, 41, 33, 25, 17, 9, 1, 59, 51, 43, 35, 27, 19, 11, 3, 61,
+	53, 45, 37, 29, 21, 13, 5, 63, 55, 47, 39, 31, 23, 15, 7,
+];
+const FP: [u8; 64] = [
+	40, 8, 48, 16, 56, 24, 64, 32, 39, 7, 47, 15, 55, 23, 63, 31, 38, 6, 46, 14, 54, 22, 62, 30,
+	37, 5, 45, 13, 53, 21, 61, 29, 36, 4, 44, 12, 52, 20, 60, 28, 35, 3, 43, 11, 51, 19, 59, 27,
+	34, 2, 42, 10, 50, 18, 58, 26, 33, 1, 41, 9, 49, 17, 57, 25,
+];
+const E: [u8; 48] = [
+	32, 1, 2, 3, 4, 5, 4, 5, 6, 7, 8, 9, 8, 9, 10, 11, 12, 13, 12, 13, 14, 15, 16, 17, 16, 17, 18,
+	19, 20, 21, 20, 21, 22, 23, 24, 25, 24, 25, 26, 27, 28, 29, 28, 29, 30, 31, 32, 1,
+];
+const P: [u8; 32] = [
+	16, 7, 20, 21, 29, 12, 28, 17, 1, 15, 23, 26, 5, 18, 31, 10, 2, 8, 24, 14, 32, 27, 3, 9, 19,
+	13, 30, 6, 22, 11, 4, 25,
+];
+const PC1: [u8; 56] = [
+	57, 49, 41, 33, 25, 17, 9, 1, 58, 50, 42, 34, 26, 18, 10, 2, 59, 51, 43, 35, 27, 19, 11, 3, 60,
+	52, 44, 36, 63, 55, 47, 39, 31, 23, 15, 7, 62, 54, 46, 38, 30, 22, 14, 6, 61, 53, 45, 37, 29,
+	21, 13, 5, 28, 20, 12, 4,
+];
+const PC2: [u8; 48] = [
+	14, 17, 11, 24, 1, 5, 3, 28, 15, 6, 21, 10, 23, 19, 12, 4, 26, 8, 16, 7, 27, 20, 13, 2, 41, 52,
+	31, 37, 47, 55, 30, 40, 51, 45, 33, 48, 44, 49, 39, 56, 34, 53, 46, 42, 50, 36, 29, 32,
+];
+const KEY_SHIFTS: [u32; 16] = [1, 1, 2, 2, 2, 2, 2, 2, 1, 2, 2, 2, 2, 2, 2, 1];
+const SBOX: [[u8; 64]; 8] = [
+	[
+		14, 4, 13, 1, 2, 15, 11, 8, 3, 10, 6, 12, 5, 9, 0, 7, 0, 15, 7, 4, 14, 2, 13, 1, 10, 6, 12,
+		11, 9, 5, 3, 8, 4, 1, 14, 8, 13, 6, 2, 11, 15, 12, 9, 7, 3, 10, 5, 0, 15, 12, 8, 2, 4, 9,
+		1, 7, 5, 11, 3, 14, 10, 0, 6, 13,
+	],
+	[
+		15, 1, 8, 14, 6, 11, 3, 4, 9, 7, 2, 13, 12, 0, 5, 10, 3, 13, 4, 7, 15, 2, 8, 14, 12, 0, 1,
+		10, 6, 9, 11, 5, 0, 14, 7, 11, 10, 4, 13, 1, 5, 8, 12, 6, 9, 3, 2, 15, 13, 8, 10, 1, 3, 15,
+		4, 2, 11, 6, 7, 12, 0, 5, 14, 9,
+	],
+	[
+		10, 0, 9, 14, 6, 3, 15, 5, 1, 13, 12, 7, 11, 4, 2, 8, 13, 7, 0, 9, 3, 4, 6, 10, 2, 8, 5,
+		14, 12, 11, 15, 1, 13, 6, 4, 9, 8, 15, 3, 0, 11, 1, 2, 12, 5, 10, 14, 7, 1, 10, 13, 0, 6,
+		9, 8, 7, 4, 15, 14, 3, 11, 5, 2, 12,
+	],
+	[
+		7, 13, 14, 3, 0, 6, 9, 10, 1, 2, 8, 5, 11, 12, 4, 15, 13, 8, 11, 5, 6, 15, 0, 3, 4, 7, 2,
+		12, 1, 10, 14, 9, 10, 6, 9, 0, 12, 11, 7, 13, 15, 1, 3, 14, 5, 2, 8, 4, 3, 15, 0, 6, 10, 1,
+		13, 8, 9, 4, 5, 11, 12, 7, 2, 14,
+	],
+	[
+		2, 12, 4, 1, 7, 10, 11, 6, 8, 5, 3, 15, 13, 0, 14, 9, 14, 11, 2, 12, 4, 7, 13, 1, 5, 0, 15,
+		10, 3, 9, 8, 6, 4, 2, 1, 11, 10, 13, 7, 8, 15, 9, 12, 5, 6, 3, 0, 14, 11, 8, 12, 7, 1, 14,
+		2, 13, 6, 15, 0, 9, 10, 4, 5, 3,
+	],
+	[
+		12, 1, 10, 15, 9, 2, 6, 8, 0, 13, 3, 4, 14, 7, 5, 11, 10, 15, 4, 2, 7, 12, 9, 5, 6, 1, 13,
+		14, 0, 11, 3, 8, 9, 14, 15, 5, 2, 8, 12, 3, 7, 0, 4, 10, 1, 13, 11, 6, 4, 3, 2, 12, 9, 5,
+		15, 10, 11, 14, 1, 7, 6, 0, 8, 13,
+	],
+	[
+		4, 11, 2, 14, 15, 0, 8, 13, 3, 12, 9, 7, 5, 10, 6, 1, 13, 0, 11, 7, 4, 9, 1, 10, 14, 3, 5,
+		12, 2, 15, 8, 6, 1, 4, 11, 13, 12, 3, 7, 14, 10, 15, 6, 8, 0, 5, 9, 2, 6, 11, 13, 8, 1, 4,
+		10, 7, 9, 5, 0, 15, 14, 2, 3, 12,
+	],
+	[
+		13, 2, 8, 4, 6, 15, 11, 1, 10, 9, 3, 14, 5, 0, 12, 7, 1, 15, 13, 8, 10, 3, 7, 4, 12, 5, 6,
+		11, 0, 14, 9, 2, 7, 11, 4, 1, 9, 12, 14, 2, 0, 6, 10, 13, 15, 3, 5, 8, 2, 1, 14, 7, 4, 10,
+		8, 13, 15, 12, 9, 0, 3, 5, 6, 11,
+	],
+];
+
+/// Pick `table`'s bits (1-based, most significant first) out of a `width`-bit value.
+fn permute(input: u64, width: u32, table: &[u8]) -> u64 {
+	table.iter().fold(0, |out, &bit| {
+		(out << 1) | ((input >> (width - u32::from(bit))) & 1)
+	})
+}
+
+fn des_subkeys(key: u64) -> [u64; 16] {
+	let cd = permute(key, 64, &PC1);
+	let (mut c, mut d) = (cd >> 28, cd & 0x0fff_ffff);
+	let rotate = |half: u64, n: u32| ((half << n) | (half >> (28 - n))) & 0x0fff_ffff;
+	let mut subkeys = [0u64; 16];
+	for (subkey, &shift) in subkeys.iter_mut().zip(&KEY_SHIFTS) {
+		c = rotate(c, shift);
+		d = rotate(d, shift);
+		*subkey = permute((c << 28) | d, 56, &PC2);
+	}
+	subkeys
+}
+
+/// DES with crypt's salted expansion table `e`.
+fn des_encrypt(block: u64, subkeys: &[u64; 16], e: &[u8; 48]) -> u64 {
+	let block = permute(block, 64, &IP);
+	let (mut l, mut r) = (block >> 32, block & 0xffff_ffff);
+	for subkey in subkeys {
+		let x = permute(r, 32, e) ^ subkey;
+		let s = SBOX.iter().enumerate().fold(0u64, |out, (i, sbox)| {
+			let six = (x >> (42 - 6 * i)) & 0x3f;
+			let index = ((six & 0x20) | ((six & 1) << 4) | ((six >> 1) & 0x0f)) as usize;
+			(out << 4) | u64::from(sbox[index])
+		});
+		(l, r) = (r, l ^ permute(s, 32, &P));
+	}
+	permute((r << 32) | l, 64, &FP)
+}
+
+fn des_crypt(password: &[u8], salt: &str) -> Result<String, String> {
+	let salt = salt.as_bytes();
+	if salt.len() != 2 {
+		return Err("DES crypt needs a 2-character salt".to_string());
+	}
+	// Each salt bit swaps a pair of expansion outputs.
+	let mut e = E;
+	for (i, &c) in salt.iter().enumerate() {
+		let bits = crypt_index(c)?;
+		for j in 0..6 {
+			if bits >> j & 1 == 1 {
+				e.swap(6 * i + j, 6 * i + j + 24);
+			}
+		}
+	}
+	let key = Zeroizing::new((0..8).fold(0u64, |key, i| {
+		(key << 8) | u64::from(password.get(i).copied().unwrap_or(0) << 1)
+	}));
+	let subkeys = Zeroizing::new(des_subkeys(*key));
+	let block = (0..25).fold(0u64, |block, _| des_encrypt(block, &subkeys, &e));
+	// 64 bits padded to 66, six at a time.
+	let padded = u128::from(block) << 2;
+	let mut out = String::from_utf8_lossy(salt).into_owned();
+	for i in 0..11 {
+		out.push(CRYPT_ALPHABET[((padded >> (60 - 6 * i)) & 0x3f) as usize] as char);
+	}
+	Ok(out)
+}
+
+// ---- md5crypt and $apr1$ ----
+
+const MD5_CRYPT_ORDER: [usize; 16] = [0, 6, 12, 1, 7, 13, 2, 8, 14, 3, 9, 15, 4, 10, 5, 11];
+
+fn md5_crypt(password: &[u8], salt: &str, magic: &str) -> String {
+	let salt = &salt.as_bytes()[..salt.len().min(8)];
+	let alternate = Md5::new()
+		.chain_update(password)
+		.chain_update(salt)
+		.chain_update(password)
+		.finalize();
+	let mut ctx = Md5::new()
+		.chain_update(password)
+		.chain_update(magic)
+		.chain_update(salt);
+	for chunk in password.chunks(16) {
+		ctx.update(&alternate[..chunk.len()]);
+	}
+	let mut n = password.len();
+	while n > 0 {
+		if n & 1 == 1 {
+			ctx.update([0u8]);
+		} else {
+			ctx.update(&password[..1]);
+		}
+		n >>= 1;
+	}
+	let mut digest = ctx.finalize();
+	for i in 0..1000 {
+		let mut ctx = Md5::new();
+		if i & 1 == 1 {
+			ctx.update(password);
+		} else {
+			ctx.update(digest);
+		}
+		if i % 3 != 0 {
+			ctx.update(salt);
+		}
+		if i % 7 != 0 {
+			ctx.update(password);
+		}
+		if i & 1 == 1 {
+			ctx.update(digest);
+		} else {
+			ctx.update(password);
+		}
+		digest = ctx.finalize();
+	}
+	format!(
+		"{magic}{}${}",
+		String::from_utf8_lossy(salt),
+		crypt_b64(&digest, &MD5_CRYPT_ORDER)
+	)
+}
+
+// ---- sha256crypt and sha512crypt ----
+
+const SHA256_CRYPT_ORDER: [usize; 32] = [
+	0, 10, 20, 21, 1, 11, 12, 22, 2, 3, 13, 23, 24, 4, 14, 15, 25, 5, 6, 16, 26, 27, 7, 17, 18, 28,
+	8, 9, 19, 29, 31, 30,
+];
+const SHA512_CRYPT_ORDER: [usize; 64] = [
+	0, 21, 42, 22, 43, 1, 44, 2, 23, 3, 24, 45, 25, 46, 4, 47, 5, 26, 6, 27, 48, 28, 49, 7, 50, 8,
+	29, 9, 30, 51, 31, 52, 10, 53, 11, 32, 12, 33, 54, 34, 55, 13, 56, 14, 35, 15, 36, 57, 37, 58,
+	16, 59, 17, 38, 18, 39, 60, 40, 61, 19, 62, 20, 41, 63,
+];
+
+/// `bytes` repeated to fill `len` bytes.
+fn repeat_to(bytes: &[u8], len: usize) -> SecretBytes {
+	Zeroizing::new(bytes.iter().copied().cycle().take(len).collect())
+}
+
+/// Ulrich Drepper's SHA-crypt, returning the final digest.
+fn sha_crypt_digest<D: Digest>(password: &[u8], salt: &[u8], rounds: u32) -> Vec<u8> {
+	let size = <D as Digest>::output_size();
+	let b = D::new()
+		.chain_update(password)
+		.chain_update(salt)
+		.chain_update(password)
+		.finalize();
+	let mut ctx = D::new().chain_update(password).chain_update(salt);
+	for chunk in password.chunks(size) {
+		ctx.update(&b[..chunk.len()]);
+	}
+	let mut n = password.len();
+	while n > 0 {
+		if n & 1 == 1 {
+			ctx.update(&b);
+		} else {
+			ctx.update(password);
+		}
+		n >>= 1;
+	}
+	let a = ctx.finalize();
+
+	let mut ctx = D::new();
+	for _ in 0..password.len() {
+		ctx.update(password);
+	}
+	let p = repeat_to(&ctx.finalize(), password.len());
+	let mut ctx = D::new();
+	for _ in 0..16 + usize::from(a[0]) {
+		ctx.update(salt);
+	}
+	let s = repeat_to(&ctx.finalize(), salt.len());
+
+	let mut c = a;
+	for i in 0..rounds {
+		let mut ctx = D::new();
+		if i & 1 == 1 {
+			ctx.update(&*p);
+		} else {
+			ctx.update(&c);
+		}
+		if i % 3 != 0 {
+			ctx.update(&*s);
+		}
+		if i % 7 != 0 {
+			ctx.update(&*p);
+		}
+		if i & 1 == 1 {
+			ctx.update(&c);
+		} else {
+			ctx.update(&*p);
+		}
+		c = ctx.finalize();
+	}
+	c.to_vec()
+}
+
+fn sha_crypt(
+	scheme: CryptScheme,
+	password: &[u8],
+	salt: &str,
+	rounds: Option<u32>,
+) -> Result<String, String> {
+	let salt = &salt.as_bytes()[..salt.len().min(16)];
+	let effective = rounds
+		.map(|r| r.clamp(SHA_CRYPT_MIN_ROUNDS, SHA_CRYPT_MAX_ROUNDS))
+		.unwrap_or(SHA_CRYPT_DEFAULT_ROUNDS);
+	// The format allows up to 999,999,999 rounds, far more than we will run.
+	check_iterations(effective)?;
+	let (magic, encoded) = match scheme {
+		CryptScheme::Sha256Crypt => (
+			"$5$",
+			crypt_b64(
+				&sha_crypt_digest::<Sha256>(password, salt, effective),
+				&SHA256_CRYPT_ORDER,
+			),
+		),
+		CryptScheme::Sha512Crypt => (
+			"$6$",
+			crypt_b64(
+				&sha_crypt_digest::<Sha512>(password, salt, effective),
+				&SHA512_CRYPT_ORDER,
+			),
+		),
+		_ => return Err(format!("{scheme:?} is not a SHA-crypt scheme")),
+	};
+	let rounds = match rounds {
+		Some(_) => format!("rounds={effective}$"),
+		None => String::new(),
+	};
+	Ok(format!(
+		"{magic}{rounds}{}${encoded}",
+		String::from_utf8_lossy(salt)
+	))
+}
+
+/// Salt and explicit rounds from `$5$[rounds=N$]salt$...`.
+fn parse_sha_crypt(scheme: CryptScheme, hash: &str) -> Result<(&str, Option<u32>), String> {
+	let magic = if scheme == CryptScheme::Sha256Crypt {
+		"$5$"
+	} else {
+		"$6$"
+	};
+	let rest = hash.strip_prefix(magic).ok_or("Invalid SHA-crypt hash")?;
+	let (rounds, rest) = match rest.strip_prefix("rounds=") {
+		Some(rest) => {
+			let (rounds, rest) = rest
+				.split_once('$')
+				.ok_or("SHA-crypt hash is missing its salt")?;
+			let rounds = rounds
+				.parse()
+				.map_err(|_| format!("Invalid SHA-crypt rounds '{rounds}'"))?;
+			(Some(rounds), rest)
+		}
+		None => (None, rest),
+	};
+	let salt = rest.split('$').next().unwrap_or_default();
+	Ok((salt, rounds))
+}
+
+// ---- PostgreSQL SCRAM-SHA-256 ----
+
+/// StoredKey and ServerKey from RFC 5802. The password is used as given;
+/// PostgreSQL's SASLprep leaves ASCII passwords unchanged.
+fn scram_keys(password: &[u8], salt: &[u8], iterations: u32) -> ([u8; 32], [u8; 32]) {
+	let mut salted = Zeroizing::new([0u8; 32]);
+	pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, iterations, &mut *salted);
+	let hmac = |label: &[u8]| -> [u8; 32] {
+		let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&*salted).expect("HMAC takes any key");
+		mac.update(label);
+		mac.finalize().into_bytes().into()
+	};
+	let client_key = Zeroizing::new(hmac(b"Client Key"));
+	(Sha256::digest(*client_key).into(), hmac(b"Server Key"))
+}
+
+fn scram_hash(password: &[u8], salt: &[u8], iterations: u32) -> String {
+	let (stored_key, server_key) = scram_keys(password, salt, iterations);
+	format!(
+		"{SCRAM_PREFIX}{iterations}:{}${}:{}",
+		BASE64.encode(salt),
+		BASE64.encode(&stored_key),
+		BASE64.encode(&server_key)
+	)
+}
+
+fn check_scram_iterations(iterations: u32) -> Result<(), String> {
+	if iterations == 0 {
+		return Err("SCRAM needs at least one iteration".to_string());
+	}
+	check_iterations(iterations)
+}
+
+/// Iterations and salt from a SCRAM verifier.
+fn parse_scram(hash: &str) -> Result<(u32, Vec<u8>), String> {
+	let invalid = || "Invalid SCRAM-SHA-256 verifier".to_string();
+	let (settings, _) = hash
+		.strip_prefix(SCRAM_PREFIX)
+		.and_then(|rest| rest.split_once('$'))
+		.ok_or_else(invalid)?;
+	let (iterations, salt) = settings.split_once(':').ok_or_else(invalid)?;
+	let iterations = iterations.parse().map_err(|_| invalid())?;
+	check_scram_iterations(iterations)?;
+	let salt = BASE64.decode(salt.as_bytes()).map_err(|_| invalid())?;
+	Ok((iterations, salt))
+}
+
+// ---- unsalted and username-salted hashes ----
+
+fn mysql41(password: &[u8]) -> String {
+	format!(
+		"*{}",
+		HEXUPPER.encode(&Sha1::digest(Sha1::digest(password)))
+	)
+}
+
+fn postgres_md5(password: &[u8], username: Option<&str>) -> Result<String, String> {
+	let username = username
+		.filter(|u| !u.is_empty())
+		.ok_or("PostgreSQL md5 hashes need the role name")?;
+	let digest = Md5::new()
+		.chain_update(password)
+		.chain_update(username)
+		.finalize();
+	Ok(format!("md5{}", HEXLOWER.encode(&digest)))
+}
+
+fn ntlm(password: &str) -> String {
+	let utf16 = Zeroizing::new(
+		password
+			.encode_utf16()
+			.flat_map(u16::to_le_bytes)
+			.collect::<Vec<u8>>(),
+	);
+	HEXLOWER.encode(&Md4::digest(&*utf16))
+}
+
+fn htpasswd_sha(password: &[u8]) -> String {
+	format!("{{SHA}}{}", BASE64.encode(&Sha1::digest(password)))
+}
+
+fn is_hex(s: &str, len: usize) -> bool {
+	s.len() == len && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// The scheme a hash string is written in.
+pub(super) fn detect_scheme(hash: &str) -> Option<CryptScheme> {
+	let scheme = if hash.starts_with("$1$") {
+		CryptScheme::Md5Crypt
+	} else if hash.starts_with("$apr1$") {
+		CryptScheme::Apr1
+	} else if hash.starts_with("$5$") {
+		CryptScheme::Sha256Crypt
+	} else if hash.starts_with("$6$") {
+		CryptScheme::Sha512Crypt
+	} else if hash.starts_with(SCRAM_PREFIX) {
+		CryptScheme::ScramSha256
+	} else if hash.starts_with("{SHA}") {
+		CryptScheme::HtpasswdSha
+	} else if hash.strip_prefix('*').is_some_and(|h| is_hex(h, 40)) {
+		CryptScheme::Mysql41
+	} else if hash.strip_prefix("md5").is_some_and(|h| is_hex(h, 32)) {
+		CryptScheme::PostgresMd5
+	} else if is_hex(hash, 32) {
+		CryptScheme::Ntlm
+	} else if hash.len() == 13 && hash.bytes().all(is_crypt_char) {
+		CryptScheme::DesCrypt
+	} else {
+		return None;
+	};
+	Some(scheme)
+}
+
+/// Splits `user:hash[:...]` account lines; a bare hash has no user.
+fn split_account_line(line: &str) -> (Option<&str>, &str) {
+	let line = line.trim();
+	if detect_scheme(line).is_some() {
+		return (None, line);
+	}
+	match line.split_once(':') {
+		Some((user, rest)) if rest.starts_with(SCRAM_PREFIX) => (Some(user), rest),
+		Some((user, rest)) => (Some(user), rest.split(':').next().unwrap_or_default()),
+		None => (None, line),
+	}
+}
+
+/// Computes `scheme` over `password`; a missing salt is drawn at random.
+fn compute(
+	scheme: CryptScheme,
+	password: &str,
+	salt: Option<&str>,
+	rounds: Option<u32>,
+	username: Option<&str>,
+) -> Result<String, String> {
+	let bytes = password.as_bytes();
+	let crypt_salt = |len: usize| match salt {
+		Some(salt) if !salt.is_empty() => {
+			salt.bytes().try_for_each(|c| crypt_index(c).map(drop))?;
+			Ok(salt.to_string())
+		}
+		_ => random_crypt_salt(len),
+	};
+	match scheme {
+		CryptScheme::DesCrypt => des_crypt(bytes, &crypt_salt(2)?),
+		CryptScheme::Md5Crypt => Ok(md5_crypt(bytes, &crypt_salt(8)?, "$1$")),
+		CryptScheme::Apr1 => Ok(md5_crypt(bytes, &crypt_salt(8)?, "$apr1$")),
+		CryptScheme::Sha256Crypt | CryptScheme::Sha512Crypt => {
+			sha_crypt(scheme, bytes, &crypt_salt(16)?, rounds)
+		}
+		CryptScheme::ScramSha256 => {
+			let salt = match salt.filter(|s| !s.is_empty()) {
+				Some(salt) => BASE64
+					.decode(salt.trim().as_bytes())
+					.map_err(|e| format!("Invalid base64 SCRAM salt: {e}"))?,
+				None => {
+					let mut salt = vec![0u8; 16];
+					getrandom::fill(&mut salt)
+						.map_err(|e| format!("Random number generator failed: {e}"))?;
+					salt
+				}
+			};
+			let iterations = rounds.unwrap_or(SCRAM_DEFAULT_ITERATIONS);
+			check_scram_iterations(iterations)?;
+			Ok(scram_hash(bytes, &salt, iterations))
+		}
+		CryptScheme::HtpasswdSha => Ok(htpasswd_sha(bytes)),
+		CryptScheme::Mysql41 => Ok(mysql41(bytes)),
+		CryptScheme::PostgresMd5 => postgres_md5(bytes, username),
+		CryptScheme::Ntlm => Ok(ntlm(password)),
+	}
+}
+
+/// Recomputes `hash` over `password` with the salt and cost it carries.
+pub(super) fn verify_scheme(
+	scheme: CryptScheme,
+	password: &str,
+	hash: &str,
+	username: Option<&str>,
+) -> Result<bool, String> {
+	let recomputed = match scheme {
+		CryptScheme::DesCrypt => compute(scheme, password, hash.get(..2), None, None)?,
+		CryptScheme::Md5Crypt | CryptScheme::Apr1 => {
+			let magic = if scheme == CryptScheme::Apr1 {
+				"$apr1$"
+			} else {
+				"$1$"
+			};
+			let salt = hash
+				.strip_prefix(magic)
+				.and_then(|rest| rest.split('$').next())
+				.ok_or("Invalid md5crypt hash")?;
+			md5_crypt(password.as_bytes(), salt, magic)
+		}
+		CryptScheme::Sha256Crypt | CryptScheme::Sha512Crypt => {
+			let (salt, rounds) = parse_sha_crypt(scheme, hash)?;
+			sha_crypt(scheme, password.as_bytes(), salt, rounds)?
+		}
+		CryptScheme::ScramSha256 => {
+			let (iterations, salt) = parse_scram(hash)?;
+			scram_hash(password.as_bytes(), &salt, iterations)
+		}
+		CryptScheme::Mysql41 | CryptScheme::PostgresMd5 | CryptScheme::Ntlm => {
+			// Hex digests are compared without regard to case.
+			let recomputed = compute(scheme, password, None, None, username)?;
+			return Ok(bool::from(
+				recomputed
+					.to_ascii_lowercase()
+					.as_bytes()
+					.ct_eq(hash.to_ascii_lowercase().as_bytes()),
+			));
+		}
+		CryptScheme::HtpasswdSha => compute(scheme, password, None, None, None)?,
+	};
+	Ok(bool::from(recomputed.as_bytes().ct_eq(hash.as_bytes())))
+}
+
+#[tauri::command(async)]
+pub fn crypt_hash(request: CryptHashRequest) -> Result<String, String> {
+	compute(
+		request.scheme,
+		&request.password,
+		request.salt.as_deref().map(str::trim),
+		request.rounds,
+		request.username.as_deref(),
+	)
+}
+
+#[tauri::command(async)]
+pub fn crypt_verify(request: CryptVerifyRequest) -> Result<CryptVerifyResponse, String> {
+	let (line_user, hash) = split_account_line(&request.hash);
+	let scheme = request
+		.scheme
+		.or_else(|| detect_scheme(hash))
+		.ok_or("Unrecognised hash format")?;
+	let username = request.username.as_deref().or(line_user);
+	let matches = verify_scheme(scheme, &request.password, hash, username)?;
+	Ok(CryptVerifyResponse {
+		matches,
+		scheme,
+		username: line_user.map(str::to_string),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hash(
+		scheme: CryptScheme,
+		password: &str,
+		salt: Option<&str>,
+		rounds: Option<u32>,
+	) -> String {
+		crypt_hash(CryptHashRequest {
+			password: password.to_string().into(),
+			scheme,
+			salt: salt.map(str::to_string),
+			rounds,
+			username: Some("postgres".to_string()),
+		})
+		.unwrap()
+	}
+
+	fn verify(password: &str, hash: &str) -> CryptVerifyResponse {
+		crypt_verify(CryptVerifyRequest {
+			password: password.to_string().into(),
+			hash: hash.to_string(),
+			scheme: None,
+			username: None,
+		})
+		.unwrap()
+	}
+
+	#[test]
+	fn matches_reference_implementations() {
+		// glibc crypt(3), `openssl passwd -apr1` and Python's hashlib.
+		let cases = [
+			(CryptScheme::DesCrypt, "test", Some("ab"), None, "abgOeLfPimXQo"),
+			(CryptScheme::DesCrypt, "password12345", Some("xy"), None, "xyAjYtmfRYx/."),
+			(CryptScheme::Md5Crypt, "password", Some("saltsalt"), None, "$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/"),
+			(CryptScheme::Apr1, "password", Some("saltsalt"), None, "$apr1$saltsalt$yAAkm4libquA.ZWLHbSBq/"),
+			(CryptScheme::Sha256Crypt, "Hello world!", Some("saltstring"), None, "$5$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5"),
+			(CryptScheme::Sha256Crypt, "Hello world!", Some("saltstringsaltstring"), Some(10000), "$5$rounds=10000$saltstringsaltst$3xv.VbSHBb41AL9AvLeujZkZRBAwqFMz2.opqey6IcA"),
+			(CryptScheme::Sha512Crypt, "Hello world!", Some("saltstring"), None, "$6$saltstring$svn8UoSVapNtMuq1ukKS4tPQd8iKwSMHWjl/O817G3uBnIFNjnQJuesI68u4OTLiBFdcbYEdFCoEOfaS35inz1"),
+			(CryptScheme::Mysql41, "password", None, None, "*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19"),
+			(CryptScheme::PostgresMd5, "password", None, None, "md532e12f215ba27cb750c9e093ce4b5127"),
+			(CryptScheme::ScramSha256, "password", Some("c2FsdHNhbHRzYWx0c2FsdA=="), None, "SCRAM-SHA-256$4096:c2FsdHNhbHRzYWx0c2FsdA==$CozjiHjNmiMjBgH9gZ7qn0QWud6nrVP6E72IBh477bQ=:VKers2x8MllK1Rh7LZLqtj6KOTzoFWJpIaokMX3blS0="),
+			(CryptScheme::Ntlm, "password", None, None, "8846f7eaee8fb117ad06bdd830b7586c"),
+			(CryptScheme::HtpasswdSha, "password", None, None, "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g="),
+		];
+		for (scheme, password, salt, rounds, expected) in cases {
+			assert_eq!(hash(scheme, password, salt, rounds), expected, "{scheme:?}");
+		}
+	}
+
+	#[test]
+	fn verifies_hashes_and_account_lines() {
+		let shadow = "root:$6$rounds=1400$anotherlongsalts$5FGyu8c4BZDX4wJgs0Un26YOw2XibT5eTkHF1I1aP3QqStoJI9BHD2YPJYsAjEePVGUyBjdZxcNqMWlrrbIOC.:19000:0:99999:7:::";
+		let result = verify("Hello world!", shadow);
+		assert!(result.matches);
+		assert_eq!(result.scheme, CryptScheme::Sha512Crypt);
+		assert_eq!(result.username.as_deref(), Some("root"));
+		assert!(!verify("Hello world", shadow).matches);
+
+		assert!(verify("password", "admin:$apr1$saltsalt$yAAkm4libquA.ZWLHbSBq/").matches);
+		assert!(verify("password", "postgres:md532e12f215ba27cb750c9e093ce4b5127").matches);
+		assert!(verify("password", "8846F7EAEE8FB117AD06BDD830B7586C").matches);
+		assert!(verify("", "$1$$qRPK7m23GJusamGpoGLby/").matches);
+		// DES crypt ignores everything past the eighth character.
+		assert!(verify("password12345", "xyAjYtmfRYx/.").matches);
+		assert!(verify("password9", "xyAjYtmfRYx/.").matches);
+		assert!(!verify("passwor", "xyAjYtmfRYx/.").matches);
+
+		for scheme in [
+			CryptScheme::Md5Crypt,
+			CryptScheme::Sha256Crypt,
+			CryptScheme::ScramSha256,
+		] {
+			let stored = hash(scheme, "hunter2", None, None);
+			assert!(verify("hunter2", &stored).matches, "{stored}");
+			assert!(!verify("hunter3", &stored).matches, "{stored}");
+		}
+	}
+
+	#[test]
+	fn rejects_bad_input() {
+		let request = |scheme, salt: &str| CryptHashRequest {
+			password: "x".to_string().into(),
+			scheme,
+			salt: Some(salt.to_string()),
+			rounds: None,
+			username: None,
+		};
+		assert!(crypt_hash(request(CryptScheme::DesCrypt, "a")).is_err());
+		assert!(crypt_hash(request(CryptScheme::Md5Crypt, "sa$t")).is_err());
+		assert!(crypt_hash(request(CryptScheme::PostgresMd5, "")).is_err());
+		assert!(detect_scheme("not a hash").is_none());
+	}
+
+	#[test]
+	fn overridden_scheme_rejects_other_formats() {
+		let verify_as = |scheme, hash: &str| {
+			crypt_verify(CryptVerifyRequest {
+				password: "x".to_string().into(),
+				hash: hash.to_string(),
+				scheme: Some(scheme),
+				username: None,
+			})
+		};
+		assert!(verify_as(CryptScheme::Sha512Crypt, "ab").is_err());
+		assert!(verify_as(CryptScheme::Sha256Crypt, "$6$salt$abc").is_err());
+		assert!(verify_as(CryptScheme::ScramSha256, "x").is_err());
+		assert!(verify_as(CryptScheme::Md5Crypt, "ab").is_err());
+	}
+
+	#[test]
+	fn stored_costs_are_checked_like_requested_ones() {
+		let verify = |hash: &str| {
+			crypt_verify(CryptVerifyRequest {
+				password: "x".to_string().into(),
+				hash: hash.to_string(),
+				scheme: None,
+				username: None,
+			})
+		};
+		let err = verify("SCRAM-SHA-256$0:c2FsdA==$AAAA:AAAA").unwrap_err();
+		assert_eq!(err, "SCRAM needs at least one iteration");
+		assert!(verify("SCRAM-SHA-256$4000000000:c2FsdA==$AAAA:AAAA").is_err());
+		assert!(verify("$6$rounds=999999999$saltstring$abc").is_err());
+		assert!(crypt_hash(CryptHashRequest {
+			password: "x".to_string().into(),
+			scheme: CryptScheme::Sha256Crypt,
+			salt: Some("saltstring".to_string()),
+			rounds: Some(999_999_999),
+			username: None,
+		})
+		.is_err());
+	}
+}
@@ -0,0 +1,181 @@
+//! DES / 3DES key inspection: odd-parity check and repair, and detection of
+//! the weak and semi-weak keys from FIPS 74.
+
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+
+use super::encoding::{parse_sized, KeyEncoding};
+use super::secret::SecretString;
+
+/// The four keys whose 16 round keys are all identical (encryption equals decryption).
+const WEAK_KEYS: [u64; 4] = [
+	0x0101_0101_0101_0101,
+	0xfefe_fefe_fefe_fefe,
+	0xe0e0_e0e0_f1f1_f1f1,
+	0x1f1f_1f1f_0e0e_0e0e,
+];
+
+/// Six pairs of keys where encrypting with one decrypts with the other.
+const SEMI_WEAK_KEYS: [u64; 12] = [
+	0x011f_011f_010e_010e,
+	0x1f01_1f01_0e01_0e01,
+	0x01e0_01e0_01f1_01f1,
+	0xe001_e001_f101_f101,
+	0x01fe_01fe_01fe_01fe,
+	0xfe01_fe01_fe01_fe01,
+	0x1fe0_1fe0_0ef1_0ef1,
+	0xe01f_e01f_f10e_f10e,
+	0x1ffe_1ffe_0efe_0efe,
+	0xfe1f_fe1f_fe0e_fe0e,
+	0xe0fe_e0fe_f1fe_f1fe,
+	0xfee0_fee0_fef1_fef1,
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DesKeyRequest {
+	/// 8 bytes (DES), 16 bytes (2-key 3DES) or 24 bytes (3-key 3DES).
+	pub key: SecretString,
+	pub key_type: KeyEncoding,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DesKeyStrength {
+	Normal,
+	Weak,
+	SemiWeak,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DesKeyPart {
+	/// The 8-byte component as given, hex.
+	pub key: String,
+	pub parity_valid: bool,
+	pub strength: DesKeyStrength,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DesKeyReport {
+	/// One entry per 8-byte DES key (K1, K2, K3).
+	pub parts: Vec<DesKeyPart>,
+	pub parity_valid: bool,
+	/// Whole key with every byte set to odd parity, hex.
+	pub fixed_key: String,
+	/// 3DES whose components collapse it into single DES (K1 = K2 or K2 = K3).
+	pub degenerate: bool,
+}
+
+/// Sets the low bit of every byte so each byte has an odd number of 1 bits.
+pub(super) fn fix_parity(key: &[u8]) -> Vec<u8> {
+	key.iter()
+		.map(|&b| {
+			let high = b & 0xfe;
+			high | u8::from(high.count_ones() % 2 == 0)
+		})
+		.collect()
+}
+
+pub(super) fn classify(part: &[u8]) -> DesKeyStrength {
+	let mut bytes = [0u8; 8];
+	bytes.copy_from_slice(&fix_parity(part));
+	let value = u64::from_be_bytes(bytes);
+	if WEAK_KEYS.contains(&value) {
+		DesKeyStrength::Weak
+	} else if SEMI_WEAK_KEYS.contains(&value) {
+		DesKeyStrength::SemiWeak
+	} else {
+		DesKeyStrength::Normal
+	}
+}
+
+/// Parity bits are ignored by DES, so components are compared without them.
+pub(super) fn same_des_key(a: &[u8], b: &[u8]) -> bool {
+	a.iter().zip(b).all(|(x, y)| x & 0xfe == y & 0xfe)
+}
+
+#[tauri::command]
+pub fn des_key_check(request: DesKeyRequest) -> Result<DesKeyReport, String> {
+	let key = parse_sized(&request.key, request.key_type, "Key", &[8, 16, 24])?;
+	let parts: Vec<DesKeyPart> = key
+		.chunks(8)
+		.map(|part| DesKeyPart {
+			key: HEXLOWER.encode(part),
+			parity_valid: fix_parity(part) == part,
+			strength: classify(part),
+		})
+		.collect();
+
+	let components: Vec<&[u8]> = key.chunks(8).collect();
+	let degenerate = match components.as_slice() {
+		[k1, k2] => same_des_key(k1, k2),
+		[k1, k2, k3] => same_des_key(k1, k2) || same_des_key(k2, k3),
+		_ => false,
+	};
+
+	Ok(DesKeyReport {
+		parity_valid: parts.iter().all(|part| part.parity_valid),
+		parts,
+		fixed_key: HEXLOWER.encode(&fix_parity(&key)),
+		degenerate,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn check(key: &str) -> DesKeyReport {
+		des_key_check(DesKeyRequest {
+			key: key.to_string().into(),
+			key_type: KeyEncoding::Hex,
+		})
+		.unwrap()
+	}
+
+	#[test]
+	fn parity_is_fixed() {
+		let report = check("0000000000000000");
+		assert!(!report.parity_valid);
+		assert_eq!(report.fixed_key, "0101010101010101");
+		assert_eq!(report.parts[0].strength, DesKeyStrength::Weak);
+
+		let report = check("133457799bbcdff1");
+		assert!(report.parity_valid);
+		assert_eq!(report.fixed_key, "133457799bbcdff1");
+		assert_eq!(report.parts[0].strength, DesKeyStrength::Normal);
+	}
+
+	#[test]
+	fn weak_and_semi_weak_keys_are_reported() {
+		let report = check("fefefefefefefefe01fe01fe01fe01fe133457799bbcdff1");
+		let strengths: Vec<_> = report.parts.iter().map(|part| part.strength).collect();
+		assert_eq!(
+			strengths,
+			[
+				DesKeyStrength::Weak,
+				DesKeyStrength::SemiWeak,
+				DesKeyStrength::Normal
+			]
+		);
+		assert!(!report.degenerate);
+	}
+
+	#[test]
+	fn repeated_components_are_degenerate() {
+		assert!(check("133457799bbcdff1123457799bbcdff1").degenerate);
+		assert!(!check("133457799bbcdff10123456789abcdef").degenerate);
+	}
+
+	#[test]
+	fn rejects_other_lengths() {
+		let err = des_key_check(DesKeyRequest {
+			key: "0011223344".to_string().into(),
+			key_type: KeyEncoding::Hex,
+		})
+		.unwrap_err();
+		assert!(err.contains("8, 16, 24"), "{err}");
+	}
+}
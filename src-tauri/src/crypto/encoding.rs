@@ -0,0 +1,131 @@
+//! Text encodings shared by the crypto commands: how inputs, outputs and key
+//! material travel between the UI and the backend.
+
+use data_encoding::{BASE64, BASE64URL_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE};
+use serde::{Deserialize, Serialize};
+
+use super::secret::{SecretArray, SecretBytes, Zeroizing};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+	Hex,
+	Base64,
+	/// URL-safe alphabet; encoded without `=` padding, decoded with or without it.
+	Base64url,
+	/// Plaintext side only. Output that is not valid UTF-8 is an error.
+	Utf8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyEncoding {
+	#[default]
+	Text,
+	Hex,
+	Base64,
+}
+
+/// The copy may hold key material, so it is wiped once decoded.
+fn clean_whitespace(s: &str) -> Zeroizing<String> {
+	Zeroizing::new(s.chars().filter(|c| !c.is_whitespace()).collect())
+}
+
+fn decode_hex_to_bytes(input: &str) -> Result<Vec<u8>, String> {
+	let mut clean = clean_whitespace(input);
+	clean.make_ascii_lowercase();
+	HEXLOWER_PERMISSIVE
+		.decode(clean.as_bytes())
+		.map_err(|e| format!("Invalid hex: {e}"))
+}
+
+fn decode_base64_to_bytes(input: &str) -> Result<Vec<u8>, String> {
+	let clean = clean_whitespace(input);
+	BASE64
+		.decode(clean.as_bytes())
+		.map_err(|e| format!("Invalid base64: {e}"))
+}
+
+fn decode_base64url_to_bytes(input: &str) -> Result<Vec<u8>, String> {
+	let clean = clean_whitespace(input);
+	BASE64URL_NOPAD
+		.decode(clean.trim_end_matches('=').as_bytes())
+		.map_err(|e| format!("Invalid base64url: {e}"))
+}
+
+pub(super) fn decode_bytes(input: &str, format: DataFormat) -> Result<Vec<u8>, String> {
+	match format {
+		DataFormat::Hex => decode_hex_to_bytes(input),
+		DataFormat::Base64 => decode_base64_to_bytes(input),
+		DataFormat::Base64url => decode_base64url_to_bytes(input),
+		DataFormat::Utf8 => Ok(input.as_bytes().to_vec()),
+	}
+}
+
+pub(super) fn encode_bytes(bytes: &[u8], format: DataFormat) -> Result<String, String> {
+	Ok(match format {
+		DataFormat::Hex => HEXLOWER.encode(bytes),
+		DataFormat::Base64 => BASE64.encode(bytes),
+		DataFormat::Base64url => BASE64URL_NOPAD.encode(bytes),
+		DataFormat::Utf8 => std::str::from_utf8(bytes)
+			.map_err(|_| "Output is not valid UTF-8; use hex, base64 or base64url".to_string())?
+			.to_string(),
+	})
+}
+
+pub(super) fn require_binary_format(format: DataFormat) -> Result<(), String> {
+	match format {
+		DataFormat::Utf8 => {
+			Err("UTF-8 is only valid for plaintext; use hex, base64 or base64url".to_string())
+		}
+		_ => Ok(()),
+	}
+}
+
+pub(super) fn decode_key_bytes(value: &str, ty: KeyEncoding) -> Result<SecretBytes, String> {
+	match ty {
+		KeyEncoding::Text => Ok(value.as_bytes().to_vec()),
+		KeyEncoding::Hex => decode_hex_to_bytes(value),
+		KeyEncoding::Base64 => decode_base64_to_bytes(value),
+	}
+	.map(Zeroizing::new)
+}
+
+/// Decodes key material and checks it is one of the accepted lengths.
+pub(super) fn parse_sized(
+	value: &str,
+	ty: KeyEncoding,
+	name: &str,
+	lengths: &[usize],
+) -> Result<SecretBytes, String> {
+	let bytes = decode_key_bytes(value, ty)?;
+	if !lengths.contains(&bytes.len()) {
+		let sizes = lengths
+			.iter()
+			.map(|n| n.to_string())
+			.collect::<Vec<_>>()
+			.join(", ");
+		let expected = match (ty, lengths.len()) {
+			(KeyEncoding::Text, 1) => format!("exactly {sizes} bytes in UTF-8"),
+			(KeyEncoding::Hex, 1) => format!("{sizes} bytes ({} hex chars)", lengths[0] * 2),
+			(KeyEncoding::Base64, 1) => format!("{sizes} bytes after base64 decoding"),
+			_ => format!("one of {sizes} bytes"),
+		};
+		return Err(format!(
+			"{name} must be {expected}, got {} bytes",
+			bytes.len()
+		));
+	}
+	Ok(bytes)
+}
+
+pub(super) fn parse_fixed<const N: usize>(
+	value: &str,
+	ty: KeyEncoding,
+	name: &str,
+) -> Result<SecretArray<N>, String> {
+	let bytes = parse_sized(value, ty, name, &[N])?;
+	let mut out = Zeroizing::new([0u8; N]);
+	out.copy_from_slice(&bytes);
+	Ok(out)
+}
@@ -0,0 +1,551 @@
+//! Non-cryptographic hashes for sharding keys, bloom filters and cache keys:
+//! xxHash, MurmurHash3, FNV, SipHash, CityHash64 and Adler-32. Each value is
+//! shown as a number and as the bytes a big- or little-endian machine would
+//! store, since the two are easy to mix up when comparing against logs.
+
+use std::io::Cursor;
+
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+use siphasher::sip::{SipHasher13, SipHasher24};
+use siphasher::sip128;
+use xxhash_rust::{xxh3, xxh32, xxh64};
+
+use super::encoding::{decode_bytes, parse_fixed, DataFormat, KeyEncoding};
+use super::hash::HashInputType;
+
+const FNV32_OFFSET: u32 = 0x811c_9dc5;
+const FNV32_PRIME: u32 = 0x0100_0193;
+const FNV64_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV64_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FastHashAlgorithm {
+	Xxh32,
+	Xxh64,
+	Xxh3_64,
+	Xxh3_128,
+	/// MurmurHash3_x86_32.
+	Murmur3_32,
+	/// MurmurHash3_x86_128.
+	Murmur3X86_128,
+	/// MurmurHash3_x64_128.
+	Murmur3X64_128,
+	Fnv1_32,
+	#[serde(rename = "fnv1a_32")]
+	Fnv1a32,
+	Fnv1_64,
+	#[serde(rename = "fnv1a_64")]
+	Fnv1a64,
+	/// SipHash-2-4, 64-bit output; Rust's `std` hasher before 1.13.
+	Siphash24,
+	/// SipHash-1-3, 64-bit output; Rust's current `std` hasher.
+	Siphash13,
+	Siphash24_128,
+	Siphash13_128,
+	/// CityHash64 v1.1; CityHash64WithSeed when a seed is given.
+	Cityhash64,
+	/// zlib's checksum; takes no seed.
+	Adler32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FastHashRequest {
+	pub input: String,
+	/// Text or hex; these hashes are meant for keys, not files.
+	pub input_type: HashInputType,
+	pub algorithms: Vec<FastHashAlgorithm>,
+	/// Decimal or `0x` hex. 32 bits for xxh32 and MurmurHash3, 64 bits for
+	/// the rest; ignored by FNV, SipHash and Adler-32. Zero when absent.
+	pub seed: Option<String>,
+	/// SipHash key, 16 bytes of hex; all zeros when absent.
+	pub key: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FastHashDigest {
+	pub algorithm: FastHashAlgorithm,
+	pub bits: u32,
+	/// Hex bytes most significant first; also the value written as a number.
+	pub big_endian: String,
+	/// Hex bytes least significant first, as a little-endian machine stores
+	/// them. This is the canonical form of MurmurHash3-128 and SipHash-128.
+	pub little_endian: String,
+	pub decimal: String,
+}
+
+fn parse_seed(seed: Option<&str>) -> Result<Option<u64>, String> {
+	let Some(seed) = seed.map(str::trim).filter(|s| !s.is_empty()) else {
+		return Ok(None);
+	};
+	let parsed = match seed.strip_prefix("0x").or_else(|| seed.strip_prefix("0X")) {
+		Some(hex) => u64::from_str_radix(hex, 16),
+		None => seed.parse(),
+	};
+	parsed
+		.map(Some)
+		.map_err(|_| format!("Seed '{seed}' is not a 64-bit decimal or 0x hex number"))
+}
+
+fn seed32(algorithm: FastHashAlgorithm, seed: u64) -> Result<u32, String> {
+	u32::try_from(seed).map_err(|_| format!("{algorithm:?} takes a 32-bit seed"))
+}
+
+fn fnv1_32(data: &[u8]) -> u32 {
+	data.iter().fold(FNV32_OFFSET, |h, &b| {
+		h.wrapping_mul(FNV32_PRIME) ^ u32::from(b)
+	})
+}
+
+fn fnv1a_32(data: &[u8]) -> u32 {
+	data.iter().fold(FNV32_OFFSET, |h, &b| {
+		(h ^ u32::from(b)).wrapping_mul(FNV32_PRIME)
+	})
+}
+
+fn fnv1_64(data: &[u8]) -> u64 {
+	data.iter().fold(FNV64_OFFSET, |h, &b| {
+		h.wrapping_mul(FNV64_PRIME) ^ u64::from(b)
+	})
+}
+
+fn fnv1a_64(data: &[u8]) -> u64 {
+	data.iter().fold(FNV64_OFFSET, |h, &b| {
+		(h ^ u64::from(b)).wrapping_mul(FNV64_PRIME)
+	})
+}
+
+// ---- CityHash64 v1.1 ----
+
+const CITY_K0: u64 = 0xc3a5_c85c_97cb_3127;
+const CITY_K1: u64 = 0xb492_b66f_be98_f273;
+const CITY_K2: u64 = 0x9ae1_6a3b_2f90_404f;
+const CITY_KMUL: u64 = 0x9ddf_ea08_eb38_2d69;
+
+fn fetch64(s: &[u8], at: usize) -> u64 {
+	u64::from_le_bytes(s[at..at + 8].try_into().expect("8 bytes"))
+}
+
+fn fetch32(s: &[u8], at: usize) -> u64 {
+	u64::from(u32::from_le_bytes(
+		s[at..at + 4].try_into().expect("4 bytes"),
+	))
+}
+
+fn shift_mix(v: u64) -> u64 {
+	v ^ (v >> 47)
+}
+
+fn hash_len16(u: u64, v: u64, mul: u64) -> u64 {
+	let a = shift_mix((u ^ v).wrapping_mul(mul));
+	let b = shift_mix((v ^ a).wrapping_mul(mul));
+	b.wrapping_mul(mul)
+}
+
+fn hash_len0to16(s: &[u8]) -> u64 {
+	let len = s.len();
+	let mul = CITY_K2.wrapping_add(len as u64 * 2);
+	if len >= 8 {
+		let a = fetch64(s, 0).wrapping_add(CITY_K2);
+		let b = fetch64(s, len - 8);
+		let c = b.rotate_right(37).wrapping_mul(mul).wrapping_add(a);
+		let d = a.rotate_right(25).wrapping_add(b).wrapping_mul(mul);
+		hash_len16(c, d, mul)
+	} else if len >= 4 {
+		let a = fetch32(s, 0);
+		hash_len16((len as u64).wrapping_add(a << 3), fetch32(s, len - 4), mul)
+	} else if len > 0 {
+		let y = u32::from(s[0]) + (u32::from(s[len >> 1]) << 8);
+		let z = len as u32 + (u32::from(s[len - 1]) << 2);
+		shift_mix(u64::from(y).wrapping_mul(CITY_K2) ^ u64::from(z).wrapping_mul(CITY_K0))
+			.wrapping_mul(CITY_K2)
+	} else {
+		CITY_K2
+	}
+}
+
+fn hash_len17to32(s: &[u8]) -> u64 {
+	let len = s.len();
+	let mul = CITY_K2.wrapping_add(len as u64 * 2);
+	let a = fetch64(s, 0).wrapping_mul(CITY_K1);
+	let b = fetch64(s, 8);
+	let c = fetch64(s, len - 8).wrapping_mul(mul);
+	let d = fetch64(s, len - 16).wrapping_mul(CITY_K2);
+	hash_len16(
+		a.wrapping_add(b)
+			.rotate_right(43)
+			.wrapping_add(c.rotate_right(30))
+			.wrapping_add(d),
+		a.wrapping_add(b.wrapping_add(CITY_K2).rotate_right(18))
+			.wrapping_add(c),
+		mul,
+	)
+}
+
+fn hash_len33to64(s: &[u8]) -> u64 {
+	let len = s.len();
+	let mul = CITY_K2.wrapping_add(len as u64 * 2);
+	let a = fetch64(s, 0).wrapping_mul(CITY_K2);
+	let b = fetch64(s, 8);
+	let c = fetch64(s, len - 24);
+	let d = fetch64(s, len - 32);
+	let e = fetch64(s, 16).wrapping_mul(CITY_K2);
+	let f = fetch64(s, 24).wrapping_mul(9);
+	let g = fetch64(s, len - 8);
+	let h = fetch64(s, len - 16).wrapping_mul(mul);
+	let u = a
+		.wrapping_add(g)
+		.rotate_right(43)
+		.wrapping_add(b.rotate_right(30).wrapping_add(c).wrapping_mul(9));
+	let v = (a.wrapping_add(g) ^ d).wrapping_add(f).wrapping_add(1);
+	let w = u
+		.wrapping_add(v)
+		.wrapping_mul(mul)
+		.swap_bytes()
+		.wrapping_add(h);
+	let x = e.wrapping_add(f).rotate_right(42).wrapping_add(c);
+	let y = v
+		.wrapping_add(w)
+		.wrapping_mul(mul)
+		.swap_bytes()
+		.wrapping_add(g)
+		.wrapping_mul(mul);
+	let z = e.wrapping_add(f).wrapping_add(c);
+	let a = x
+		.wrapping_add(z)
+		.wrapping_mul(mul)
+		.wrapping_add(y)
+		.swap_bytes()
+		.wrapping_add(b);
+	let b = shift_mix(
+		z.wrapping_add(a)
+			.wrapping_mul(mul)
+			.wrapping_add(d)
+			.wrapping_add(h),
+	)
+	.wrapping_mul(mul);
+	b.wrapping_add(x)
+}
+
+fn weak_hash_len32_with_seeds(s: &[u8], at: usize, a: u64, b: u64) -> (u64, u64) {
+	let (w, x, y, z) = (
+		fetch64(s, at),
+		fetch64(s, at + 8),
+		fetch64(s, at + 16),
+		fetch64(s, at + 24),
+	);
+	let a = a.wrapping_add(w);
+	let b = b.wrapping_add(a).wrapping_add(z).rotate_right(21);
+	let c = a;
+	let a = a.wrapping_add(x).wrapping_add(y);
+	let b = b.wrapping_add(a.rotate_right(44));
+	(a.wrapping_add(z), b.wrapping_add(c))
+}
+
+fn city_hash64(s: &[u8]) -> u64 {
+	let len = s.len();
+	if len <= 16 {
+		return hash_len0to16(s);
+	} else if len <= 32 {
+		return hash_len17to32(s);
+	} else if len <= 64 {
+		return hash_len33to64(s);
+	}
+
+	let mut x = fetch64(s, len - 40);
+	let mut y = fetch64(s, len - 16).wrapping_add(fetch64(s, len - 56));
+	let mut z = hash_len16(
+		fetch64(s, len - 48).wrapping_add(len as u64),
+		fetch64(s, len - 24),
+		CITY_KMUL,
+	);
+	let mut v = weak_hash_len32_with_seeds(s, len - 64, len as u64, z);
+	let mut w = weak_hash_len32_with_seeds(s, len - 32, y.wrapping_add(CITY_K1), x);
+	x = x.wrapping_mul(CITY_K1).wrapping_add(fetch64(s, 0));
+
+	// Whole 64-byte blocks, the last of which may overlap the tail read above.
+	for at in (0..(len - 1) & !63).step_by(64) {
+		x = x
+			.wrapping_add(y)
+			.wrapping_add(v.0)
+			.wrapping_add(fetch64(s, at + 8))
+			.rotate_right(37)
+			.wrapping_mul(CITY_K1);
+		y = y
+			.wrapping_add(v.1)
+			.wrapping_add(fetch64(s, at + 48))
+			.rotate_right(42)
+			.wrapping_mul(CITY_K1);
+		x ^= w.1;
+		y = y.wrapping_add(v.0).wrapping_add(fetch64(s, at + 40));
+		z = z.wrapping_add(w.0).rotate_right(33).wrapping_mul(CITY_K1);
+		v = weak_hash_len32_with_seeds(s, at, v.1.wrapping_mul(CITY_K1), x.wrapping_add(w.0));
+		w = weak_hash_len32_with_seeds(
+			s,
+			at + 32,
+			z.wrapping_add(w.1),
+			y.wrapping_add(fetch64(s, at + 16)),
+		);
+		std::mem::swap(&mut z, &mut x);
+	}
+	hash_len16(
+		hash_len16(v.0, w.0, CITY_KMUL)
+			.wrapping_add(shift_mix(y).wrapping_mul(CITY_K1))
+			.wrapping_add(z),
+		hash_len16(v.1, w.1, CITY_KMUL).wrapping_add(x),
+		CITY_KMUL,
+	)
+}
+
+/// CityHash64WithSeed, i.e. CityHash64WithSeeds with k2 as the first seed.
+fn city_hash64_with_seed(s: &[u8], seed: u64) -> u64 {
+	hash_len16(city_hash64(s).wrapping_sub(CITY_K2), seed, CITY_KMUL)
+}
+
+/// One hash value and its width in bits.
+fn compute(
+	algorithm: FastHashAlgorithm,
+	data: &[u8],
+	seed: Option<u64>,
+	key: &[u8; 16],
+) -> Result<(u128, u32), String> {
+	let seed64 = seed.unwrap_or(0);
+	let murmur = |result: std::io::Result<u128>| result.map_err(|e| e.to_string());
+	let value = match algorithm {
+		FastHashAlgorithm::Xxh32 => (
+			u128::from(xxh32::xxh32(data, seed32(algorithm, seed64)?)),
+			32,
+		),
+		FastHashAlgorithm::Xxh64 => (u128::from(xxh64::xxh64(data, seed64)), 64),
+		FastHashAlgorithm::Xxh3_64 => (u128::from(xxh3::xxh3_64_with_seed(data, seed64)), 64),
+		FastHashAlgorithm::Xxh3_128 => (xxh3::xxh3_128_with_seed(data, seed64), 128),
+		FastHashAlgorithm::Murmur3_32 => {
+			let seed = seed32(algorithm, seed64)?;
+			let value =
+				murmur3::murmur3_32(&mut Cursor::new(data), seed).map_err(|e| e.to_string())?;
+			(u128::from(value), 32)
+		}
+		FastHashAlgorithm::Murmur3X86_128 => {
+			let seed = seed32(algorithm, seed64)?;
+			(
+				murmur(murmur3::murmur3_x86_128(&mut Cursor::new(data), seed))?,
+				128,
+			)
+		}
+		FastHashAlgorithm::Murmur3X64_128 => {
+			let seed = seed32(algorithm, seed64)?;
+			(
+				murmur(murmur3::murmur3_x64_128(&mut Cursor::new(data), seed))?,
+				128,
+			)
+		}
+		FastHashAlgorithm::Fnv1_32 => (u128::from(fnv1_32(data)), 32),
+		FastHashAlgorithm::Fnv1a32 => (u128::from(fnv1a_32(data)), 32),
+		FastHashAlgorithm::Fnv1_64 => (u128::from(fnv1_64(data)), 64),
+		FastHashAlgorithm::Fnv1a64 => (u128::from(fnv1a_64(data)), 64),
+		FastHashAlgorithm::Siphash24 => (u128::from(SipHasher24::new_with_key(key).hash(data)), 64),
+		FastHashAlgorithm::Siphash13 => (u128::from(SipHasher13::new_with_key(key).hash(data)), 64),
+		FastHashAlgorithm::Siphash24_128 => (
+			sip128::SipHasher24::new_with_key(key).hash(data).as_u128(),
+			128,
+		),
+		FastHashAlgorithm::Siphash13_128 => (
+			sip128::SipHasher13::new_with_key(key).hash(data).as_u128(),
+			128,
+		),
+		FastHashAlgorithm::Cityhash64 => {
+			let value = match seed {
+				Some(seed) => city_hash64_with_seed(data, seed),
+				None => city_hash64(data),
+			};
+			(u128::from(value), 64)
+		}
+		FastHashAlgorithm::Adler32 => (u128::from(adler2::adler32_slice(data)), 32),
+	};
+	Ok(value)
+}
+
+#[tauri::command]
+pub fn fast_hash(request: FastHashRequest) -> Result<Vec<FastHashDigest>, String> {
+	if request.algorithms.is_empty() {
+		return Err("Choose at least one hash algorithm".to_string());
+	}
+	let data = match request.input_type {
+		HashInputType::Text => request.input.as_bytes().to_vec(),
+		HashInputType::Hex => decode_bytes(&request.input, DataFormat::Hex)?,
+		HashInputType::File => {
+			return Err("Non-cryptographic hashes take text or hex input".to_string())
+		}
+	};
+	let seed = parse_seed(request.seed.as_deref())?;
+	let key = match request.key.as_deref().filter(|k| !k.trim().is_empty()) {
+		Some(key) => *parse_fixed::<16>(key, KeyEncoding::Hex, "SipHash key")?,
+		None => [0u8; 16],
+	};
+
+	request
+		.algorithms
+		.iter()
+		.map(|&algorithm| {
+			let (value, bits) = compute(algorithm, &data, seed, &key)?;
+			let bytes = &value.to_be_bytes()[16 - bits as usize / 8..];
+			let mut little_endian = bytes.to_vec();
+			little_endian.reverse();
+			Ok(FastHashDigest {
+				algorithm,
+				bits,
+				big_endian: HEXLOWER.encode(bytes),
+				little_endian: HEXLOWER.encode(&little_endian),
+				decimal: value.to_string(),
+			})
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hash(
+		algorithm: FastHashAlgorithm,
+		input: &str,
+		input_type: HashInputType,
+		seed: Option<&str>,
+		key: Option<&str>,
+	) -> FastHashDigest {
+		fast_hash(FastHashRequest {
+			input: input.to_string(),
+			input_type,
+			algorithms: vec![algorithm],
+			seed: seed.map(str::to_string),
+			key: key.map(str::to_string),
+		})
+		.unwrap()
+		.remove(0)
+	}
+
+	fn text(algorithm: FastHashAlgorithm, input: &str, seed: Option<&str>) -> String {
+		hash(algorithm, input, HashInputType::Text, seed, None).big_endian
+	}
+
+	const FOX: &str = "The quick brown fox jumps over the lazy dog";
+
+	#[test]
+	fn matches_reference_implementations() {
+		// libxxhash 0.8, SMHasher's MurmurHash3, the FNV and Adler-32 specs.
+		let cases = [
+			(FastHashAlgorithm::Xxh32, FOX, None, "e85ea4de"),
+			(FastHashAlgorithm::Xxh32, FOX, Some("42"), "4c29bb74"),
+			(FastHashAlgorithm::Xxh64, FOX, None, "0b242d361fda71bc"),
+			(
+				FastHashAlgorithm::Xxh64,
+				FOX,
+				Some("0x2a"),
+				"aa9f288a8baa3d3f",
+			),
+			(FastHashAlgorithm::Xxh3_64, FOX, None, "ce7d19a5418fb365"),
+			(
+				FastHashAlgorithm::Xxh3_64,
+				FOX,
+				Some("42"),
+				"b4a3f3c36b3c7d26",
+			),
+			(
+				FastHashAlgorithm::Xxh3_128,
+				FOX,
+				None,
+				"ddd650205ca3e7fa24a1cc2e3a8a7651",
+			),
+			(FastHashAlgorithm::Murmur3_32, FOX, None, "2e4ff723"),
+			(
+				FastHashAlgorithm::Murmur3X64_128,
+				FOX,
+				None,
+				"7a433ca9c49a9347e34bbc7bbc071b6c",
+			),
+			(FastHashAlgorithm::Fnv1_32, "a", None, "050c5d7e"),
+			(FastHashAlgorithm::Fnv1a32, "a", None, "e40c292c"),
+			(FastHashAlgorithm::Fnv1_64, "a", None, "af63bd4c8601b7be"),
+			(FastHashAlgorithm::Fnv1a64, "a", None, "af63dc4c8601ec8c"),
+			(FastHashAlgorithm::Adler32, "Wikipedia", None, "11e60398"),
+			(FastHashAlgorithm::Cityhash64, FOX, None, "c268724928feca7d"),
+		];
+		for (algorithm, input, seed, expected) in cases {
+			assert_eq!(text(algorithm, input, seed), expected, "{algorithm:?}");
+		}
+	}
+
+	#[test]
+	fn city_hash_covers_every_length_class() {
+		// Values from Abseil's copy of CityHash v1.1.
+		let data: Vec<u8> = (0..=255u8).cycle().take(300).collect();
+		let cases = [
+			(0, 0x9ae16a3b2f90404f, 0xa96ac8f555bccc29),
+			(3, 0x94a13d22e9eba49a, 0xf9c3c89ca029707d),
+			(5, 0xb4bfa9e87732c149, 0x4eb45d914902e080),
+			(12, 0x9fd5df33aefc3d7d, 0x4d1d7cfa596bda8c),
+			(20, 0x8dd7e7f1bf16a0e9, 0x0cb291a0694203b2),
+			(40, 0x36f7dd7a65e93d1c, 0x94f9ef2c9706ac37),
+			(64, 0xe99ab80f5ec7dca5, 0x3216138d63ba9464),
+			(65, 0xac589c990483dd2e, 0xcb573a679423ddab),
+			(100, 0xa47fe83e60b34cc6, 0xf7a7dd25ac36c95b),
+			(200, 0xf4d24e8c7493c3d8, 0xe353758aa630caf8),
+			(300, 0x3e4a4f6cb1a5cc62, 0x548ca29217455bdd),
+		];
+		for (len, unseeded, seeded) in cases {
+			assert_eq!(city_hash64(&data[..len]), unseeded, "length {len}");
+			assert_eq!(
+				city_hash64_with_seed(&data[..len], 42),
+				seeded,
+				"length {len}"
+			);
+		}
+	}
+
+	#[test]
+	fn reports_both_byte_orders() {
+		// SipHash paper, appendix A: key 00..0f, message 00..0e.
+		let sip = hash(
+			FastHashAlgorithm::Siphash24,
+			"000102030405060708090a0b0c0d0e",
+			HashInputType::Hex,
+			None,
+			Some("000102030405060708090a0b0c0d0e0f"),
+		);
+		assert_eq!(sip.big_endian, "a129ca6149be45e5");
+		assert_eq!(sip.little_endian, "e545be4961ca29a1");
+		assert_eq!(
+			sip.decimal,
+			u64::from_str_radix("a129ca6149be45e5", 16)
+				.unwrap()
+				.to_string()
+		);
+
+		let murmur = hash(
+			FastHashAlgorithm::Murmur3X64_128,
+			FOX,
+			HashInputType::Text,
+			None,
+			None,
+		);
+		assert_eq!(murmur.little_endian, "6c1b07bc7bbc4be347939ac4a93c437a");
+		assert_eq!(murmur.bits, 128);
+	}
+
+	#[test]
+	fn rejects_bad_parameters() {
+		let request = |algorithm, seed: &str| FastHashRequest {
+			input: "x".to_string(),
+			input_type: HashInputType::Text,
+			algorithms: vec![algorithm],
+			seed: Some(seed.to_string()),
+			key: None,
+		};
+		assert!(fast_hash(request(FastHashAlgorithm::Xxh32, "4294967296")).is_err());
+		assert!(fast_hash(request(FastHashAlgorithm::Xxh64, "-1")).is_err());
+		assert!(fast_hash(request(FastHashAlgorithm::Xxh64, "18446744073709551615")).is_ok());
+	}
+}
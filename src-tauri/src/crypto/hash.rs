@@ -0,0 +1,564 @@
+//! Message digests for the hash tools. Text and hex inputs are hashed in
+//! memory; files are streamed in chunks so their size is not limited by RAM,
+//! with progress reported over a Tauri channel. Several algorithms can share
+//! one pass over the input.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use blake2::{Blake2bVar, Blake2sVar};
+use serde::{Deserialize, Serialize};
+use sha2::digest::{DynDigest, ExtendableOutput, Update, VariableOutput};
+use sha3::{Shake128, Shake256};
+use tauri::ipc::Channel;
+
+use super::crc::{Crc, CRC_32_ISO_HDLC};
+use super::encoding::{decode_bytes, encode_bytes, require_binary_format, DataFormat};
+
+/// Files are read in chunks of this size.
+const READ_CHUNK_SIZE: usize = 1024 * 1024;
+/// Progress is reported after at least this many bytes, and once at the end.
+const PROGRESS_INTERVAL: u64 = 16 * 1024 * 1024;
+/// Upper bound for SHAKE and BLAKE3 output, which is otherwise unlimited.
+pub(super) const MAX_XOF_LENGTH: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+	Md2,
+	Md4,
+	Md5,
+	Sha1,
+	Sha224,
+	Sha256,
+	Sha384,
+	Sha512,
+	Sha512_224,
+	Sha512_256,
+	Sha3_224,
+	Sha3_256,
+	Sha3_384,
+	Sha3_512,
+	/// Original Keccak padding, as used by Ethereum.
+	Keccak224,
+	Keccak256,
+	Keccak384,
+	Keccak512,
+	/// Variable output; 32 bytes by default.
+	Shake128,
+	/// Variable output; 64 bytes by default.
+	Shake256,
+	/// 1-64 bytes of output; 64 by default.
+	Blake2b,
+	/// 1-32 bytes of output; 32 by default.
+	Blake2s,
+	/// Variable output; 32 bytes by default.
+	Blake3,
+	Ripemd160,
+	Whirlpool,
+	/// Tiger/192 with the original padding byte 0x01.
+	Tiger,
+	/// GOST R 34.11-2012, 256-bit output.
+	Streebog256,
+	/// GOST R 34.11-2012, 512-bit output.
+	Streebog512,
+	Sm3,
+	/// CRC-32 as used by zip and SFV files; not a cryptographic hash.
+	Crc32,
+}
+
+impl std::fmt::Display for HashAlgorithm {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			HashAlgorithm::Md2 => "MD2",
+			HashAlgorithm::Md4 => "MD4",
+			HashAlgorithm::Md5 => "MD5",
+			HashAlgorithm::Sha1 => "SHA-1",
+			HashAlgorithm::Sha224 => "SHA-224",
+			HashAlgorithm::Sha256 => "SHA-256",
+			HashAlgorithm::Sha384 => "SHA-384",
+			HashAlgorithm::Sha512 => "SHA-512",
+			HashAlgorithm::Sha512_224 => "SHA-512/224",
+			HashAlgorithm::Sha512_256 => "SHA-512/256",
+			HashAlgorithm::Sha3_224 => "SHA3-224",
+			HashAlgorithm::Sha3_256 => "SHA3-256",
+			HashAlgorithm::Sha3_384 => "SHA3-384",
+			HashAlgorithm::Sha3_512 => "SHA3-512",
+			HashAlgorithm::Keccak224 => "Keccak-224",
+			HashAlgorithm::Keccak256 => "Keccak-256",
+			HashAlgorithm::Keccak384 => "Keccak-384",
+			HashAlgorithm::Keccak512 => "Keccak-512",
+			HashAlgorithm::Shake128 => "SHAKE128",
+			HashAlgorithm::Shake256 => "SHAKE256",
+			HashAlgorithm::Blake2b => "BLAKE2b",
+			HashAlgorithm::Blake2s => "BLAKE2s",
+			HashAlgorithm::Blake3 => "BLAKE3",
+			HashAlgorithm::Ripemd160 => "RIPEMD-160",
+			HashAlgorithm::Whirlpool => "Whirlpool",
+			HashAlgorithm::Tiger => "Tiger",
+			HashAlgorithm::Streebog256 => "Streebog-256",
+			HashAlgorithm::Streebog512 => "Streebog-512",
+			HashAlgorithm::Sm3 => "SM3",
+			HashAlgorithm::Crc32 => "CRC-32",
+		};
+		f.write_str(name)
+	}
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashInputType {
+	Text,
+	Hex,
+	/// `input` is a path to a file on disk.
+	File,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashRequest {
+	pub input: String,
+	pub input_type: HashInputType,
+	/// Computed together in one pass over the input.
+	pub algorithms: Vec<HashAlgorithm>,
+	/// Output bytes for SHAKE, BLAKE2 and BLAKE3; each algorithm's default when absent.
+	pub output_length: Option<usize>,
+	/// Format of the digests; hex when absent.
+	pub format: Option<DataFormat>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashDigest {
+	pub algorithm: HashAlgorithm,
+	pub digest: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashProgress {
+	pub processed: u64,
+	pub total: u64,
+}
+
+/// One running digest, whatever the shape of its output.
+pub(super) enum Hasher {
+	Fixed(Box<dyn DynDigest + Send>),
+	Blake2b(Blake2bVar),
+	Blake2s(Blake2sVar),
+	Shake128(Shake128, usize),
+	Shake256(Shake256, usize),
+	Blake3(Box<blake3::Hasher>, usize),
+	Crc32(Crc),
+}
+
+impl Hasher {
+	pub(super) fn new(
+		algorithm: HashAlgorithm,
+		output_length: Option<usize>,
+	) -> Result<Self, String> {
+		let variable = |default: usize, max: usize| match output_length {
+			None => Ok(default),
+			Some(len) if (1..=max).contains(&len) => Ok(len),
+			Some(len) => Err(format!(
+				"{algorithm} output length must be 1-{max} bytes, got {len}"
+			)),
+		};
+		let fixed: Box<dyn DynDigest + Send> = match algorithm {
+			HashAlgorithm::Blake2b => {
+				let len = variable(64, 64)?;
+				return Ok(Hasher::Blake2b(
+					Blake2bVar::new(len).map_err(|e| e.to_string())?,
+				));
+			}
+			HashAlgorithm::Blake2s => {
+				let len = variable(32, 32)?;
+				return Ok(Hasher::Blake2s(
+					Blake2sVar::new(len).map_err(|e| e.to_string())?,
+				));
+			}
+			HashAlgorithm::Shake128 => {
+				return Ok(Hasher::Shake128(
+					Shake128::default(),
+					variable(32, MAX_XOF_LENGTH)?,
+				));
+			}
+			HashAlgorithm::Shake256 => {
+				return Ok(Hasher::Shake256(
+					Shake256::default(),
+					variable(64, MAX_XOF_LENGTH)?,
+				));
+			}
+			HashAlgorithm::Blake3 => {
+				return Ok(Hasher::Blake3(
+					Box::default(),
+					variable(32, MAX_XOF_LENGTH)?,
+				));
+			}
+			_ if output_length.is_some() => {
+				return Err(format!("{algorithm} has a fixed output length"));
+			}
+			HashAlgorithm::Md2 => Box::<md2::Md2>::default(),
+			HashAlgorithm::Md4 => Box::<md4::Md4>::default(),
+			HashAlgorithm::Md5 => Box::<md5::Md5>::default(),
+			HashAlgorithm::Sha1 => Box::<sha1::Sha1>::default(),
+			HashAlgorithm::Sha224 => Box::<sha2::Sha224>::default(),
+			HashAlgorithm::Sha256 => Box::<sha2::Sha256>::default(),
+			HashAlgorithm::Sha384 => Box::<sha2::Sha384>::default(),
+			HashAlgorithm::Sha512 => Box::<sha2::Sha512>::default(),
+			HashAlgorithm::Sha512_224 => Box::<sha2::Sha512_224>::default(),
+			HashAlgorithm::Sha512_256 => Box::<sha2::Sha512_256>::default(),
+			HashAlgorithm::Sha3_224 => Box::<sha3::Sha3_224>::default(),
+			HashAlgorithm::Sha3_256 => Box::<sha3::Sha3_256>::default(),
+			HashAlgorithm::Sha3_384 => Box::<sha3::Sha3_384>::default(),
+			HashAlgorithm::Sha3_512 => Box::<sha3::Sha3_512>::default(),
+			HashAlgorithm::Keccak224 => Box::<sha3::Keccak224>::default(),
+			HashAlgorithm::Keccak256 => Box::<sha3::Keccak256>::default(),
+			HashAlgorithm::Keccak384 => Box::<sha3::Keccak384>::default(),
+			HashAlgorithm::Keccak512 => Box::<sha3::Keccak512>::default(),
+			HashAlgorithm::Ripemd160 => Box::<ripemd::Ripemd160>::default(),
+			HashAlgorithm::Whirlpool => Box::<whirlpool::Whirlpool>::default(),
+			HashAlgorithm::Tiger => Box::<tiger::Tiger>::default(),
+			HashAlgorithm::Streebog256 => Box::<streebog::Streebog256>::default(),
+			HashAlgorithm::Streebog512 => Box::<streebog::Streebog512>::default(),
+			HashAlgorithm::Sm3 => Box::<sm3::Sm3>::default(),
+			HashAlgorithm::Crc32 => return Ok(Hasher::Crc32(Crc::new(CRC_32_ISO_HDLC))),
+		};
+		Ok(Hasher::Fixed(fixed))
+	}
+
+	pub(super) fn update(&mut self, data: &[u8]) {
+		match self {
+			Hasher::Fixed(hasher) => hasher.update(data),
+			Hasher::Blake2b(hasher) => Update::update(hasher, data),
+			Hasher::Blake2s(hasher) => Update::update(hasher, data),
+			Hasher::Shake128(hasher, _) => Update::update(hasher, data),
+			Hasher::Shake256(hasher, _) => Update::update(hasher, data),
+			Hasher::Blake3(hasher, _) => {
+				hasher.update(data);
+			}
+			Hasher::Crc32(crc) => crc.update(data),
+		}
+	}
+
+	pub(super) fn finalize(self) -> Vec<u8> {
+		match self {
+			Hasher::Fixed(hasher) => hasher.finalize().into_vec(),
+			Hasher::Blake2b(hasher) => hasher.finalize_boxed().into_vec(),
+			Hasher::Blake2s(hasher) => hasher.finalize_boxed().into_vec(),
+			Hasher::Shake128(hasher, len) => hasher.finalize_boxed(len).into_vec(),
+			Hasher::Shake256(hasher, len) => hasher.finalize_boxed(len).into_vec(),
+			Hasher::Blake3(hasher, len) => {
+				let mut out = vec![0u8; len];
+				hasher.finalize_xof().fill(&mut out);
+				out
+			}
+			Hasher::Crc32(crc) => crc.to_be_bytes(),
+		}
+	}
+}
+
+/// Reads a file chunk by chunk into `sink`. `progress` gets the bytes read
+/// so far and the file size.
+pub(super) fn stream_file(
+	path: &Path,
+	sink: &mut dyn FnMut(&[u8]),
+	progress: &mut dyn FnMut(HashProgress),
+) -> Result<(), String> {
+	let mut file = File::open(path).map_err(|e| format!("Failed to open file: {e}"))?;
+	let total = file
+		.metadata()
+		.map_err(|e| format!("Failed to read file: {e}"))?
+		.len();
+	let mut buf = vec![0u8; READ_CHUNK_SIZE];
+	let mut processed = 0u64;
+	let mut reported = 0u64;
+	loop {
+		let n = file
+			.read(&mut buf)
+			.map_err(|e| format!("Failed to read file: {e}"))?;
+		if n == 0 {
+			break;
+		}
+		sink(&buf[..n]);
+		processed += n as u64;
+		if processed - reported >= PROGRESS_INTERVAL {
+			reported = processed;
+			progress(HashProgress { processed, total });
+		}
+	}
+	progress(HashProgress {
+		processed,
+		total: total.max(processed),
+	});
+	Ok(())
+}
+
+pub(super) fn digest_input(
+	request: &HashRequest,
+	progress: &mut dyn FnMut(HashProgress),
+) -> Result<Vec<HashDigest>, String> {
+	if request.algorithms.is_empty() {
+		return Err("Choose at least one hash algorithm".to_string());
+	}
+	let format = request.format.unwrap_or(DataFormat::Hex);
+	require_binary_format(format)?;
+	let mut hashers = request
+		.algorithms
+		.iter()
+		.map(|&algorithm| Hasher::new(algorithm, request.output_length))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	match request.input_type {
+		HashInputType::Text => hashers
+			.iter_mut()
+			.for_each(|h| h.update(request.input.as_bytes())),
+		HashInputType::Hex => {
+			let data = decode_bytes(&request.input, DataFormat::Hex)?;
+			hashers.iter_mut().for_each(|h| h.update(&data));
+		}
+		HashInputType::File => {
+			let path = Path::new(request.input.trim());
+			stream_file(
+				path,
+				&mut |chunk| hashers.iter_mut().for_each(|h| h.update(chunk)),
+				progress,
+			)?
+		}
+	}
+
+	request
+		.algorithms
+		.iter()
+		.zip(hashers)
+		.map(|(&algorithm, hasher)| {
+			Ok(HashDigest {
+				algorithm,
+				digest: encode_bytes(&hasher.finalize(), format)?,
+			})
+		})
+		.collect()
+}
+
+#[tauri::command(async)]
+pub fn hash_digest(
+	request: HashRequest,
+	on_progress: Channel<HashProgress>,
+) -> Result<Vec<HashDigest>, String> {
+	digest_input(&request, &mut |progress| {
+		// A closed channel only means the window stopped listening.
+		let _ = on_progress.send(progress);
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn digest(algorithm: HashAlgorithm, input: &str, output_length: Option<usize>) -> String {
+		let request = HashRequest {
+			input: input.to_string(),
+			input_type: HashInputType::Text,
+			algorithms: vec![algorithm],
+			output_length,
+			format: None,
+		};
+		digest_input(&request, &mut |_| {})
+			.unwrap()
+			.remove(0)
+			.digest
+	}
+
+	#[test]
+	fn every_algorithm_matches_reference_vectors() {
+		let vectors = [
+			(
+				HashAlgorithm::Md2,
+				"abc",
+				"da853b0d3f88d99b30283a69e6ded6bb",
+			),
+			(
+				HashAlgorithm::Md4,
+				"abc",
+				"a448017aaf21d8525fc10ae87aa6729d",
+			),
+			(
+				HashAlgorithm::Md5,
+				"abc",
+				"900150983cd24fb0d6963f7d28e17f72",
+			),
+			(
+				HashAlgorithm::Sha1,
+				"abc",
+				"a9993e364706816aba3e25717850c26c9cd0d89d",
+			),
+			(
+				HashAlgorithm::Sha224,
+				"abc",
+				"23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7",
+			),
+			(
+				HashAlgorithm::Sha256,
+				"abc",
+				"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+			),
+			(
+				HashAlgorithm::Sha384,
+				"abc",
+				"cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed\
+				 8086072ba1e7cc2358baeca134c825a7",
+			),
+			(
+				HashAlgorithm::Sha512,
+				"abc",
+				"ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+				 2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+			),
+			(
+				HashAlgorithm::Sha512_224,
+				"abc",
+				"4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa",
+			),
+			(
+				HashAlgorithm::Sha512_256,
+				"abc",
+				"53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23",
+			),
+			(
+				HashAlgorithm::Sha3_224,
+				"abc",
+				"e642824c3f8cf24ad09234ee7d3c766fc9a3a5168d0c94ad73b46fdf",
+			),
+			(
+				HashAlgorithm::Sha3_256,
+				"abc",
+				"3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+			),
+			(
+				HashAlgorithm::Sha3_384,
+				"abc",
+				"ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b2\
+				 98d88cea927ac7f539f1edf228376d25",
+			),
+			(
+				HashAlgorithm::Sha3_512,
+				"abc",
+				"b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+				 10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+			),
+			(
+				HashAlgorithm::Keccak256,
+				"",
+				"c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+			),
+			(
+				HashAlgorithm::Shake128,
+				"abc",
+				"5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8",
+			),
+			(
+				HashAlgorithm::Shake256,
+				"abc",
+				"483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739\
+				 d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e4",
+			),
+			(
+				HashAlgorithm::Blake2b,
+				"abc",
+				"ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+				 7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+			),
+			(
+				HashAlgorithm::Blake2s,
+				"abc",
+				"508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982",
+			),
+			(
+				HashAlgorithm::Blake3,
+				"",
+				"af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+			),
+			(
+				HashAlgorithm::Ripemd160,
+				"abc",
+				"8eb208f7e05d987a9b044a8e98c6b087f15a0bfc",
+			),
+			(
+				HashAlgorithm::Whirlpool,
+				"abc",
+				"4e2448a4c6f486bb16b6562c73b4020bf3043e3a731bce721ae1b303d97e6d4c\
+				 7181eebdb6c57e277d0e34957114cbd6c797fc9d95d8b582d225292076d4eef5",
+			),
+			(
+				HashAlgorithm::Tiger,
+				"",
+				"3293ac630c13f0245f92bbb1766e16167a4e58492dde73f3",
+			),
+			(
+				HashAlgorithm::Streebog256,
+				"",
+				"3f539a213e97c802cc229d474c6aa32a825a360b2a933a949fd925208d9ce1bb",
+			),
+			(
+				HashAlgorithm::Streebog512,
+				"",
+				"8e945da209aa869f0455928529bcae4679e9873ab707b55315f56ceb98bef0a7\
+				 362f715528356ee83cda5f2aac4c6ad2ba3a715c1bcd81cb8e9f90bf4c1c1a8a",
+			),
+			(
+				HashAlgorithm::Sm3,
+				"abc",
+				"66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0",
+			),
+			(HashAlgorithm::Crc32, "123456789", "cbf43926"),
+		];
+		for (algorithm, input, expected) in vectors {
+			assert_eq!(digest(algorithm, input, None), expected, "{algorithm}");
+		}
+	}
+
+	#[test]
+	fn variable_output_lengths() {
+		assert_eq!(
+			digest(HashAlgorithm::Shake128, "abc", Some(10)),
+			"5881092dd818bf5cf8a3"
+		);
+		assert_eq!(
+			digest(HashAlgorithm::Blake2b, "abc", Some(32)),
+			"bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319"
+		);
+		assert_eq!(digest(HashAlgorithm::Blake3, "", Some(4)), "af1349b9");
+
+		let request = HashRequest {
+			input: "abc".to_string(),
+			input_type: HashInputType::Text,
+			algorithms: vec![HashAlgorithm::Sha256],
+			output_length: Some(16),
+			format: None,
+		};
+		assert!(digest_input(&request, &mut |_| {}).is_err());
+	}
+
+	#[test]
+	fn file_input_is_streamed_with_progress() {
+		let path = std::env::temp_dir().join("trovekit-hash-test.bin");
+		let data = "abc".repeat(READ_CHUNK_SIZE);
+		std::fs::write(&path, &data).unwrap();
+		let request = HashRequest {
+			input: path.to_str().unwrap().to_string(),
+			input_type: HashInputType::File,
+			algorithms: vec![HashAlgorithm::Md5, HashAlgorithm::Sha256],
+			output_length: None,
+			format: None,
+		};
+		let mut updates = Vec::new();
+		let got = digest_input(&request, &mut |p| updates.push(p.processed));
+		std::fs::remove_file(&path).unwrap();
+		let got = got.unwrap();
+
+		assert_eq!(got[0].digest, digest(HashAlgorithm::Md5, &data, None));
+		assert_eq!(got[1].digest, digest(HashAlgorithm::Sha256, &data, None));
+		assert_eq!(updates.last(), Some(&(data.len() as u64)));
+	}
+}
@@ -0,0 +1,793 @@
+//! Hash type identification: ranks the algorithms an unknown hash string
+//! could have come from, by prefix, length, alphabet and structure, with
+//! the matching hashcat mode and John the Ripper format. Candidates backed
+//! by the hash, crypt or password modules can be checked against a known
+//! plaintext in one call.
+
+use data_encoding::{BASE64, HEXLOWER};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::digest::Digest;
+
+use super::crypt::{is_crypt_char, verify_scheme, CryptScheme};
+use super::hash::{HashAlgorithm, Hasher};
+use super::password::{password_verify, PasswordVerifyRequest};
+use super::secret::SecretString;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+	/// A prefix or structure that only this scheme uses.
+	High,
+	/// Plausible from length and alphabet, and commonly seen.
+	Medium,
+	/// Same shape, but rarely the answer.
+	Low,
+}
+
+/// How a candidate is checked against a plaintext.
+#[derive(Debug, Clone, Copy)]
+enum Check {
+	/// Hex digest of the plaintext, optionally after a `$...$` prefix.
+	Digest(HashAlgorithm),
+	Crypt(CryptScheme),
+	/// bcrypt and PHC strings, via the password module.
+	Password,
+	/// LDAP `{SSHA}`: base64 of SHA1(password || salt) || salt.
+	Ssha,
+	Unsupported,
+}
+
+struct Signature {
+	name: &'static str,
+	hashcat: Option<u32>,
+	john: Option<&'static str>,
+	check: Check,
+}
+
+const fn sig(
+	name: &'static str,
+	hashcat: Option<u32>,
+	john: Option<&'static str>,
+	check: Check,
+) -> Signature {
+	Signature {
+		name,
+		hashcat,
+		john,
+		check,
+	}
+}
+
+/// Self-describing formats, matched by prefix.
+const PREFIXED: &[(&str, Signature)] = &[
+	(
+		"$2a$",
+		sig("bcrypt", Some(3200), Some("bcrypt"), Check::Password),
+	),
+	(
+		"$2b$",
+		sig("bcrypt", Some(3200), Some("bcrypt"), Check::Password),
+	),
+	(
+		"$2y$",
+		sig("bcrypt", Some(3200), Some("bcrypt"), Check::Password),
+	),
+	(
+		"$2x$",
+		sig("bcrypt", Some(3200), Some("bcrypt"), Check::Password),
+	),
+	(
+		"$argon2id$",
+		sig("Argon2id", Some(34000), Some("argon2"), Check::Password),
+	),
+	(
+		"$argon2i$",
+		sig("Argon2i", Some(34000), Some("argon2"), Check::Password),
+	),
+	(
+		"$argon2d$",
+		sig("Argon2d", Some(34000), Some("argon2"), Check::Password),
+	),
+	("$scrypt$", sig("scrypt (PHC)", None, None, Check::Password)),
+	(
+		"$pbkdf2-sha256$",
+		sig(
+			"PBKDF2-HMAC-SHA256 (PHC)",
+			Some(10900),
+			Some("PBKDF2-HMAC-SHA256"),
+			Check::Password,
+		),
+	),
+	(
+		"$pbkdf2-sha512$",
+		sig(
+			"PBKDF2-HMAC-SHA512 (PHC)",
+			Some(12100),
+			Some("PBKDF2-HMAC-SHA512"),
+			Check::Password,
+		),
+	),
+	(
+		"$1$",
+		sig(
+			"md5crypt",
+			Some(500),
+			Some("md5crypt"),
+			Check::Crypt(CryptScheme::Md5Crypt),
+		),
+	),
+	(
+		"$apr1$",
+		sig(
+			"Apache apr1",
+			Some(1600),
+			Some("md5crypt"),
+			Check::Crypt(CryptScheme::Apr1),
+		),
+	),
+	(
+		"$5$",
+		sig(
+			"sha256crypt",
+			Some(7400),
+			Some("sha256crypt"),
+			Check::Crypt(CryptScheme::Sha256Crypt),
+		),
+	),
+	(
+		"$6$",
+		sig(
+			"sha512crypt",
+			Some(1800),
+			Some("sha512crypt"),
+			Check::Crypt(CryptScheme::Sha512Crypt),
+		),
+	),
+	(
+		"$y$",
+		sig("yescrypt", None, Some("crypt"), Check::Unsupported),
+	),
+	(
+		"$7$",
+		sig("scrypt (crypt)", None, Some("scrypt"), Check::Unsupported),
+	),
+	(
+		"$sha1$",
+		sig(
+			"sha1crypt",
+			Some(15100),
+			Some("sha1crypt"),
+			Check::Unsupported,
+		),
+	),
+	(
+		"$P$",
+		sig("phpass", Some(400), Some("phpass"), Check::Unsupported),
+	),
+	(
+		"$H$",
+		sig("phpass", Some(400), Some("phpass"), Check::Unsupported),
+	),
+	(
+		"$BLAKE2$",
+		sig(
+			"BLAKE2b-512",
+			Some(600),
+			Some("raw-blake2"),
+			Check::Digest(HashAlgorithm::Blake2b),
+		),
+	),
+	(
+		"pbkdf2_sha256$",
+		sig(
+			"Django PBKDF2-SHA256",
+			Some(10000),
+			Some("django"),
+			Check::Unsupported,
+		),
+	),
+	(
+		"SCRAM-SHA-256$",
+		sig(
+			"PostgreSQL SCRAM-SHA-256",
+			Some(28600),
+			None,
+			Check::Crypt(CryptScheme::ScramSha256),
+		),
+	),
+	(
+		"{SHA}",
+		sig(
+			"LDAP {SHA}",
+			Some(101),
+			Some("nsldap"),
+			Check::Crypt(CryptScheme::HtpasswdSha),
+		),
+	),
+	(
+		"{SSHA}",
+		sig("LDAP {SSHA}", Some(111), Some("salted-sha1"), Check::Ssha),
+	),
+];
+
+/// Bare hex digests by length in characters, most likely first.
+const HEX: &[(usize, Confidence, Signature)] = &[
+	(
+		8,
+		Confidence::Medium,
+		sig(
+			"CRC-32",
+			Some(11500),
+			Some("crc32"),
+			Check::Digest(HashAlgorithm::Crc32),
+		),
+	),
+	(
+		16,
+		Confidence::Medium,
+		sig("MySQL 3.23", Some(200), Some("mysql"), Check::Unsupported),
+	),
+	(
+		32,
+		Confidence::High,
+		sig(
+			"MD5",
+			Some(0),
+			Some("raw-md5"),
+			Check::Digest(HashAlgorithm::Md5),
+		),
+	),
+	(
+		32,
+		Confidence::Medium,
+		sig(
+			"NTLM",
+			Some(1000),
+			Some("nt"),
+			Check::Crypt(CryptScheme::Ntlm),
+		),
+	),
+	(
+		32,
+		Confidence::Low,
+		sig(
+			"MD4",
+			Some(900),
+			Some("raw-md4"),
+			Check::Digest(HashAlgorithm::Md4),
+		),
+	),
+	(
+		32,
+		Confidence::Low,
+		sig("LM", Some(3000), Some("lm"), Check::Unsupported),
+	),
+	(
+		32,
+		Confidence::Low,
+		sig("MD2", None, Some("md2"), Check::Digest(HashAlgorithm::Md2)),
+	),
+	(
+		40,
+		Confidence::High,
+		sig(
+			"SHA-1",
+			Some(100),
+			Some("raw-sha1"),
+			Check::Digest(HashAlgorithm::Sha1),
+		),
+	),
+	(
+		40,
+		Confidence::Medium,
+		sig(
+			"MySQL 4.1+",
+			Some(300),
+			Some("mysql-sha1"),
+			Check::Crypt(CryptScheme::Mysql41),
+		),
+	),
+	(
+		40,
+		Confidence::Low,
+		sig(
+			"RIPEMD-160",
+			Some(6000),
+			Some("ripemd-160"),
+			Check::Digest(HashAlgorithm::Ripemd160),
+		),
+	),
+	(
+		48,
+		Confidence::Medium,
+		sig(
+			"Tiger-192",
+			None,
+			Some("tiger"),
+			Check::Digest(HashAlgorithm::Tiger),
+		),
+	),
+	(
+		56,
+		Confidence::High,
+		sig(
+			"SHA-224",
+			Some(1300),
+			Some("raw-sha224"),
+			Check::Digest(HashAlgorithm::Sha224),
+		),
+	),
+	(
+		56,
+		Confidence::Medium,
+		sig(
+			"SHA3-224",
+			Some(17300),
+			None,
+			Check::Digest(HashAlgorithm::Sha3_224),
+		),
+	),
+	(
+		56,
+		Confidence::Low,
+		sig(
+			"Keccak-224",
+			Some(17700),
+			None,
+			Check::Digest(HashAlgorithm::Keccak224),
+		),
+	),
+	(
+		56,
+		Confidence::Low,
+		sig(
+			"SHA-512/224",
+			None,
+			None,
+			Check::Digest(HashAlgorithm::Sha512_224),
+		),
+	),
+	(
+		64,
+		Confidence::High,
+		sig(
+			"SHA-256",
+			Some(1400),
+			Some("raw-sha256"),
+			Check::Digest(HashAlgorithm::Sha256),
+		),
+	),
+	(
+		64,
+		Confidence::Medium,
+		sig(
+			"SHA3-256",
+			Some(17400),
+			None,
+			Check::Digest(HashAlgorithm::Sha3_256),
+		),
+	),
+	(
+		64,
+		Confidence::Medium,
+		sig(
+			"Keccak-256",
+			Some(17800),
+			Some("raw-keccak-256"),
+			Check::Digest(HashAlgorithm::Keccak256),
+		),
+	),
+	(
+		64,
+		Confidence::Low,
+		sig(
+			"BLAKE2s-256",
+			None,
+			None,
+			Check::Digest(HashAlgorithm::Blake2s),
+		),
+	),
+	(
+		64,
+		Confidence::Low,
+		sig("BLAKE3", None, None, Check::Digest(HashAlgorithm::Blake3)),
+	),
+	(
+		64,
+		Confidence::Low,
+		sig("SM3", Some(31100), None, Check::Digest(HashAlgorithm::Sm3)),
+	),
+	(
+		64,
+		Confidence::Low,
+		sig(
+			"Streebog-256",
+			Some(11700),
+			None,
+			Check::Digest(HashAlgorithm::Streebog256),
+		),
+	),
+	(
+		64,
+		Confidence::Low,
+		sig(
+			"SHA-512/256",
+			None,
+			None,
+			Check::Digest(HashAlgorithm::Sha512_256),
+		),
+	),
+	(
+		96,
+		Confidence::High,
+		sig(
+			"SHA-384",
+			Some(10800),
+			Some("raw-sha384"),
+			Check::Digest(HashAlgorithm::Sha384),
+		),
+	),
+	(
+		96,
+		Confidence::Medium,
+		sig(
+			"SHA3-384",
+			Some(17500),
+			None,
+			Check::Digest(HashAlgorithm::Sha3_384),
+		),
+	),
+	(
+		96,
+		Confidence::Low,
+		sig(
+			"Keccak-384",
+			Some(17900),
+			None,
+			Check::Digest(HashAlgorithm::Keccak384),
+		),
+	),
+	(
+		128,
+		Confidence::High,
+		sig(
+			"SHA-512",
+			Some(1700),
+			Some("raw-sha512"),
+			Check::Digest(HashAlgorithm::Sha512),
+		),
+	),
+	(
+		128,
+		Confidence::Medium,
+		sig(
+			"SHA3-512",
+			Some(17600),
+			Some("raw-sha3"),
+			Check::Digest(HashAlgorithm::Sha3_512),
+		),
+	),
+	(
+		128,
+		Confidence::Medium,
+		sig(
+			"BLAKE2b-512",
+			Some(600),
+			Some("raw-blake2"),
+			Check::Digest(HashAlgorithm::Blake2b),
+		),
+	),
+	(
+		128,
+		Confidence::Medium,
+		sig(
+			"Whirlpool",
+			Some(6100),
+			Some("whirlpool"),
+			Check::Digest(HashAlgorithm::Whirlpool),
+		),
+	),
+	(
+		128,
+		Confidence::Low,
+		sig(
+			"Keccak-512",
+			Some(18000),
+			Some("raw-keccak"),
+			Check::Digest(HashAlgorithm::Keccak512),
+		),
+	),
+	(
+		128,
+		Confidence::Low,
+		sig(
+			"Streebog-512",
+			Some(11800),
+			None,
+			Check::Digest(HashAlgorithm::Streebog512),
+		),
+	),
+];
+
+const MYSQL41: Signature = sig(
+	"MySQL 4.1+",
+	Some(300),
+	Some("mysql-sha1"),
+	Check::Crypt(CryptScheme::Mysql41),
+);
+const POSTGRES_MD5: Signature = sig(
+	"PostgreSQL md5",
+	Some(12),
+	None,
+	Check::Crypt(CryptScheme::PostgresMd5),
+);
+const DES_CRYPT: Signature = sig(
+	"DES crypt",
+	Some(1500),
+	Some("descrypt"),
+	Check::Crypt(CryptScheme::DesCrypt),
+);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashCandidate {
+	pub name: String,
+	pub hashcat: Option<u32>,
+	pub john: Option<String>,
+	pub confidence: Confidence,
+	/// Whether `hash_identify_verify` can check this candidate.
+	pub verifiable: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashIdentifyVerifyRequest {
+	pub hash: String,
+	pub plaintext: SecretString,
+	/// Name of one candidate from `hash_identify`; every verifiable
+	/// candidate when absent.
+	pub candidate: Option<String>,
+	/// Role name for PostgreSQL md5.
+	pub username: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashCandidateCheck {
+	pub name: String,
+	pub matches: bool,
+	/// Why the candidate could not be checked; `matches` is false then.
+	pub error: Option<String>,
+}
+
+fn is_hex(s: &str) -> bool {
+	!s.is_empty() && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Candidate signatures for `hash`, best first.
+fn identify(hash: &str) -> Vec<(Confidence, &'static Signature)> {
+	let mut found: Vec<(Confidence, &'static Signature)> = PREFIXED
+		.iter()
+		.filter(|(prefix, _)| hash.starts_with(prefix))
+		.map(|(_, signature)| (Confidence::High, signature))
+		.collect();
+	if !found.is_empty() {
+		return found;
+	}
+	if hash
+		.strip_prefix('*')
+		.is_some_and(|h| h.len() == 40 && is_hex(h))
+	{
+		found.push((Confidence::High, &MYSQL41));
+	}
+	if hash
+		.strip_prefix("md5")
+		.is_some_and(|h| h.len() == 32 && is_hex(h))
+	{
+		found.push((Confidence::High, &POSTGRES_MD5));
+	}
+	if is_hex(hash) {
+		found.extend(
+			HEX.iter()
+				.filter(|(len, _, _)| *len == hash.len())
+				.map(|(_, confidence, signature)| (*confidence, signature)),
+		);
+	}
+	if hash.len() == 13 && hash.bytes().all(is_crypt_char) {
+		found.push((Confidence::Medium, &DES_CRYPT));
+	}
+	found.sort_by_key(|(confidence, _)| *confidence);
+	found
+}
+
+fn check_ssha(plaintext: &[u8], hash: &str) -> Result<bool, String> {
+	let decoded = BASE64
+		.decode(hash["{SSHA}".len()..].trim().as_bytes())
+		.map_err(|e| format!("Invalid {{SSHA}} base64: {e}"))?;
+	if decoded.len() <= 20 {
+		return Err("{SSHA} hash has no salt".to_string());
+	}
+	let (digest, salt) = decoded.split_at(20);
+	Ok(Sha1::new()
+		.chain_update(plaintext)
+		.chain_update(salt)
+		.finalize()
+		.as_slice()
+		== digest)
+}
+
+fn check(
+	signature: &Signature,
+	hash: &str,
+	plaintext: &str,
+	username: Option<&str>,
+) -> Result<bool, String> {
+	match signature.check {
+		Check::Digest(algorithm) => {
+			let expected = hash.rsplit('$').next().unwrap_or(hash);
+			let mut hasher = Hasher::new(algorithm, Some(expected.len() / 2))
+				.or_else(|_| Hasher::new(algorithm, None))?;
+			hasher.update(plaintext.as_bytes());
+			Ok(HEXLOWER
+				.encode(&hasher.finalize())
+				.eq_ignore_ascii_case(expected))
+		}
+		Check::Crypt(CryptScheme::Mysql41) if !hash.starts_with('*') => verify_scheme(
+			CryptScheme::Mysql41,
+			plaintext,
+			&format!("*{hash}"),
+			username,
+		),
+		Check::Crypt(scheme) => verify_scheme(scheme, plaintext, hash, username),
+		Check::Password => password_verify(PasswordVerifyRequest {
+			password: plaintext.to_string().into(),
+			hash: hash.to_string(),
+		})
+		.map(|response| response.matches),
+		Check::Ssha => check_ssha(plaintext.as_bytes(), hash),
+		Check::Unsupported => Err(format!("{} cannot be checked here", signature.name)),
+	}
+}
+
+#[tauri::command]
+pub fn hash_identify(hash: String) -> Vec<HashCandidate> {
+	identify(hash.trim())
+		.into_iter()
+		.map(|(confidence, signature)| HashCandidate {
+			name: signature.name.to_string(),
+			hashcat: signature.hashcat,
+			john: signature.john.map(str::to_string),
+			confidence,
+			verifiable: !matches!(signature.check, Check::Unsupported),
+		})
+		.collect()
+}
+
+#[tauri::command(async)]
+pub fn hash_identify_verify(
+	request: HashIdentifyVerifyRequest,
+) -> Result<Vec<HashCandidateCheck>, String> {
+	let hash = request.hash.trim();
+	let candidates = identify(hash);
+	if candidates.is_empty() {
+		return Err("Unrecognised hash format".to_string());
+	}
+	let selected: Vec<_> = match request.candidate.as_deref() {
+		Some(name) => {
+			let (_, signature) = candidates
+				.iter()
+				.find(|(_, signature)| signature.name == name)
+				.ok_or_else(|| format!("{name} is not a candidate for this hash"))?;
+			if matches!(signature.check, Check::Unsupported) {
+				return Err(format!("{name} cannot be checked here"));
+			}
+			vec![*signature]
+		}
+		None => candidates
+			.iter()
+			.map(|(_, signature)| *signature)
+			.filter(|signature| !matches!(signature.check, Check::Unsupported))
+			.collect(),
+	};
+	Ok(selected
+		.into_iter()
+		.map(|signature| {
+			let result = check(
+				signature,
+				hash,
+				&request.plaintext,
+				request.username.as_deref(),
+			);
+			HashCandidateCheck {
+				name: signature.name.to_string(),
+				matches: result.as_ref().is_ok_and(|&matches| matches),
+				error: result.err(),
+			}
+		})
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn names(hash: &str) -> Vec<String> {
+		hash_identify(hash.to_string())
+			.into_iter()
+			.map(|candidate| candidate.name)
+			.collect()
+	}
+
+	fn verify(hash: &str, plaintext: &str, candidate: Option<&str>) -> Vec<HashCandidateCheck> {
+		hash_identify_verify(HashIdentifyVerifyRequest {
+			hash: hash.to_string(),
+			plaintext: plaintext.to_string().into(),
+			candidate: candidate.map(str::to_string),
+			username: None,
+		})
+		.unwrap()
+	}
+
+	#[test]
+	fn ranks_candidates() {
+		let md5 = hash_identify("5f4dcc3b5aa765d61d8327deb882cf99".to_string());
+		assert_eq!(md5[0].name, "MD5");
+		assert_eq!(md5[0].hashcat, Some(0));
+		assert_eq!(md5[1].name, "NTLM");
+		assert!(md5.iter().all(|c| c.name != "SHA-1"));
+
+		let bcrypt = hash_identify(
+			"$2b$10$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW".to_string(),
+		);
+		assert_eq!(bcrypt.len(), 1);
+		assert_eq!(bcrypt[0].hashcat, Some(3200));
+		assert_eq!(bcrypt[0].confidence, Confidence::High);
+
+		assert_eq!(
+			names("*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19"),
+			["MySQL 4.1+"]
+		);
+		assert_eq!(
+			names("{SSHA}yI6cZwQadOA1e+/f+T+H3eCQQhRzYWx0"),
+			["LDAP {SSHA}"]
+		);
+		assert_eq!(names("abgOeLfPimXQo"), ["DES crypt"]);
+		assert_eq!(names("$y$j9T$abc$def")[0], "yescrypt");
+		assert!(names("not a hash").is_empty());
+	}
+
+	#[test]
+	fn verifies_candidates_against_plaintext() {
+		let checks = verify("5f4dcc3b5aa765d61d8327deb882cf99", "password", None);
+		let matched: Vec<_> = checks
+			.iter()
+			.filter(|c| c.matches)
+			.map(|c| c.name.as_str())
+			.collect();
+		assert_eq!(matched, ["MD5"]);
+		assert!(checks.iter().all(|c| c.name != "LM"));
+
+		let ntlm = verify("8846F7EAEE8FB117AD06BDD830B7586C", "password", Some("NTLM"));
+		assert!(ntlm[0].matches);
+
+		let sha1 = verify("5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8", "password", None);
+		assert_eq!(sha1[0].name, "SHA-1");
+		assert!(sha1[0].matches);
+		assert!(!sha1[1].matches);
+
+		assert!(verify("{SSHA}yI6cZwQadOA1e+/f+T+H3eCQQhRzYWx0", "password", None)[0].matches);
+		assert!(verify("$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/", "password", None)[0].matches);
+
+		let postgres = verify("md532e12f215ba27cb750c9e093ce4b5127", "password", None);
+		assert!(postgres[0].error.is_some());
+
+		let err = hash_identify_verify(HashIdentifyVerifyRequest {
+			hash: "5f4dcc3b5aa765d61d8327deb882cf99".to_string(),
+			plaintext: "password".to_string().into(),
+			candidate: Some("LM".to_string()),
+			username: None,
+		});
+		assert!(err.is_err());
+	}
+}
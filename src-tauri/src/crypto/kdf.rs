@@ -0,0 +1,452 @@
+//! Password-based keys for the cipher requests: PBKDF2, scrypt, Argon2id and
+//! OpenSSL's `EVP_BytesToKey`, plus the `Salted__` header written by
+//! `openssl enc` and CryptoJS passphrase encryption.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use hmac::Hmac;
+use md5::Md5;
+use serde::Deserialize;
+use sha2::digest::Digest;
+use sha2::Sha256;
+use sm3::Sm3;
+
+use super::encoding::{decode_key_bytes, KeyEncoding};
+use super::secret::{SecretBytes, SecretString, Zeroizing};
+
+/// `openssl enc` output starts with this magic followed by an 8-byte salt.
+pub(super) const SALTED_MAGIC: &[u8; 8] = b"Salted__";
+const SALT_LEN: usize = 8;
+/// Ceilings on caller-chosen costs; beyond them a derivation would exhaust
+/// memory or keep the app busy for minutes.
+pub(super) const MAX_MEMORY_KIB: u64 = 1024 * 1024;
+pub(super) const MAX_ITERATIONS: u32 = 10_000_000;
+pub(super) const MAX_ARGON2_ITERATIONS: u32 = 100;
+pub(super) const MAX_SCRYPT_PARALLELISM: u32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KdfHash {
+	Md5,
+	Sha256,
+	Sm3,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "kdf", rename_all = "camelCase")]
+pub enum KdfSettings {
+	/// PBKDF2-HMAC; `openssl enc -pbkdf2` uses SHA-256 with 10000 iterations.
+	Pbkdf2 { hash: KdfHash, iterations: u32 },
+	/// N = 2^logN.
+	#[serde(rename_all = "camelCase")]
+	Scrypt { log_n: u8, r: u32, p: u32 },
+	#[serde(rename_all = "camelCase")]
+	Argon2id {
+		memory_kib: u32,
+		iterations: u32,
+		parallelism: u32,
+	},
+	/// OpenSSL's legacy derivation: MD5 for CryptoJS and OpenSSL < 1.1, SHA-256 since.
+	EvpBytesToKey {
+		hash: KdfHash,
+		#[serde(default = "one")]
+		iterations: u32,
+	},
+}
+
+fn one() -> u32 {
+	1
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordKey {
+	pub password: SecretString,
+	#[serde(flatten)]
+	pub kdf: KdfSettings,
+	/// Hex. Encrypt draws a random 8-byte salt when absent; decrypt takes the
+	/// salt from a `Salted__` header first.
+	pub salt: Option<String>,
+	/// Encrypt: prefix the ciphertext with `Salted__` and the salt, as `openssl enc` does.
+	/// Always on when the salt is drawn at random, which could not be recovered otherwise.
+	#[serde(default)]
+	pub salted_header: bool,
+}
+
+/// Key and IV cut from one derivation output, the way OpenSSL splits it.
+pub(super) struct DerivedKey {
+	pub key: SecretBytes,
+	pub iv: SecretBytes,
+	/// Bytes to put in front of the ciphertext; empty unless `salted_header` is set.
+	pub header: Vec<u8>,
+}
+
+/// `EVP_BytesToKey`: D_i = H^count(D_{i-1} || password || salt), concatenated.
+fn evp_bytes_to_key<D: Digest>(
+	password: &[u8],
+	salt: &[u8],
+	iterations: u32,
+	len: usize,
+) -> SecretBytes {
+	let mut out = Zeroizing::new(Vec::with_capacity(len));
+	let mut block = Zeroizing::new(Vec::new());
+	while out.len() < len {
+		let mut hasher = D::new();
+		hasher.update(&block);
+		hasher.update(password);
+		hasher.update(salt);
+		block = Zeroizing::new(hasher.finalize().to_vec());
+		for _ in 1..iterations {
+			block = Zeroizing::new(D::digest(&block).to_vec());
+		}
+		out.extend_from_slice(&block);
+	}
+	out.truncate(len);
+	out
+}
+
+/// PBKDF2, EVP_BytesToKey and SHA-crypt iteration counts.
+pub(super) fn check_iterations(iterations: u32) -> Result<(), String> {
+	if iterations > MAX_ITERATIONS {
+		return Err(format!(
+			"{iterations} iterations is over the limit of {MAX_ITERATIONS}"
+		));
+	}
+	Ok(())
+}
+
+/// scrypt needs 128·r·2^logN bytes; `p` only repeats the work.
+pub(super) fn check_scrypt_cost(log_n: u8, r: u32, p: u32) -> Result<(), String> {
+	if log_n > 32 || (u64::from(r) << log_n) / 8 > MAX_MEMORY_KIB {
+		return Err(format!(
+			"scrypt with logN {log_n} and r {r} is over the limit of {} MiB",
+			MAX_MEMORY_KIB / 1024
+		));
+	}
+	if p > MAX_SCRYPT_PARALLELISM {
+		return Err(format!(
+			"scrypt p {p} is over the limit of {MAX_SCRYPT_PARALLELISM}"
+		));
+	}
+	Ok(())
+}
+
+pub(super) fn check_argon2_cost(memory_kib: u32, iterations: u32) -> Result<(), String> {
+	if u64::from(memory_kib) > MAX_MEMORY_KIB {
+		return Err(format!(
+			"Argon2 memory of {memory_kib} KiB is over the limit of {MAX_MEMORY_KIB} KiB"
+		));
+	}
+	if iterations > MAX_ARGON2_ITERATIONS {
+		return Err(format!(
+			"Argon2 iterations {iterations} is over the limit of {MAX_ARGON2_ITERATIONS}"
+		));
+	}
+	Ok(())
+}
+
+pub(super) fn derive_bytes(
+	password: &[u8],
+	kdf: KdfSettings,
+	salt: &[u8],
+	len: usize,
+) -> Result<SecretBytes, String> {
+	let mut out = Zeroizing::new(vec![0u8; len]);
+	match kdf {
+		KdfSettings::Pbkdf2 { iterations: 0, .. }
+		| KdfSettings::EvpBytesToKey { iterations: 0, .. } => {
+			return Err("Iterations must be at least 1".to_string());
+		}
+		KdfSettings::Pbkdf2 { hash, iterations } => {
+			check_iterations(iterations)?;
+			match hash {
+				KdfHash::Md5 => pbkdf2::pbkdf2::<Hmac<Md5>>(password, salt, iterations, &mut out),
+				KdfHash::Sha256 => {
+					pbkdf2::pbkdf2::<Hmac<Sha256>>(password, salt, iterations, &mut out)
+				}
+				KdfHash::Sm3 => pbkdf2::pbkdf2::<Hmac<Sm3>>(password, salt, iterations, &mut out),
+			}
+			.map_err(|e| e.to_string())?;
+		}
+		KdfSettings::Scrypt { log_n, r, p } => {
+			require_salt(salt, "scrypt")?;
+			check_scrypt_cost(log_n, r, p)?;
+			let params =
+				scrypt::Params::new(log_n, r, p, len).map_err(|e| format!("scrypt: {e}"))?;
+			scrypt::scrypt(password, salt, &params, &mut out)
+				.map_err(|e| format!("scrypt: {e}"))?;
+		}
+		KdfSettings::Argon2id {
+			memory_kib,
+			iterations,
+			parallelism,
+		} => {
+			require_salt(salt, "Argon2id")?;
+			check_argon2_cost(memory_kib, iterations)?;
+			let params = Params::new(memory_kib, iterations, parallelism, Some(len))
+				.map_err(|e| format!("Argon2id: {e}"))?;
+			Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+				.hash_password_into(password, salt, &mut out)
+				.map_err(|e| format!("Argon2id: {e}"))?;
+		}
+		KdfSettings::EvpBytesToKey { hash, iterations } => {
+			check_iterations(iterations)?;
+			if !salt.is_empty() && salt.len() != SALT_LEN {
+				return Err(format!(
+					"EVP_BytesToKey salt must be {SALT_LEN} bytes, got {} bytes",
+					salt.len()
+				));
+			}
+			out = match hash {
+				KdfHash::Md5 => evp_bytes_to_key::<Md5>(password, salt, iterations, len),
+				KdfHash::Sha256 => evp_bytes_to_key::<Sha256>(password, salt, iterations, len),
+				KdfHash::Sm3 => evp_bytes_to_key::<Sm3>(password, salt, iterations, len),
+			};
+		}
+	}
+	Ok(out)
+}
+
+fn require_salt(salt: &[u8], kdf: &str) -> Result<(), String> {
+	if salt.is_empty() {
+		return Err(format!("{kdf} needs a salt"));
+	}
+	Ok(())
+}
+
+fn split(
+	password: &PasswordKey,
+	salt: &[u8],
+	key_len: usize,
+	iv_len: usize,
+) -> Result<(SecretBytes, SecretBytes), String> {
+	let mut key = derive_bytes(
+		password.password.as_bytes(),
+		password.kdf,
+		salt,
+		key_len + iv_len,
+	)?;
+	let iv = Zeroizing::new(key.split_off(key_len));
+	Ok((key, iv))
+}
+
+fn request_salt(password: &PasswordKey) -> Result<Option<Vec<u8>>, String> {
+	match password.salt.as_deref().filter(|s| !s.trim().is_empty()) {
+		Some(salt) => decode_key_bytes(salt, KeyEncoding::Hex).map(|salt| Some(salt.to_vec())),
+		None => Ok(None),
+	}
+}
+
+pub(super) fn derive_for_encrypt(
+	password: &PasswordKey,
+	key_len: usize,
+	iv_len: usize,
+) -> Result<DerivedKey, String> {
+	let (salt, salted_header) = match request_salt(password)? {
+		Some(salt) => (salt, password.salted_header),
+		None => {
+			let mut salt = vec![0u8; SALT_LEN];
+			getrandom::fill(&mut salt).map_err(|e| e.to_string())?;
+			(salt, true)
+		}
+	};
+	if salted_header && salt.len() != SALT_LEN {
+		return Err(format!(
+			"The Salted__ header holds a {SALT_LEN}-byte salt, got {} bytes",
+			salt.len()
+		));
+	}
+	let (key, iv) = split(password, &salt, key_len, iv_len)?;
+	let header = if salted_header {
+		[&SALTED_MAGIC[..], &salt].concat()
+	} else {
+		Vec::new()
+	};
+	Ok(DerivedKey { key, iv, header })
+}
+
+/// Returns the derived key and the ciphertext with any `Salted__` header removed.
+pub(super) fn derive_for_decrypt<'a>(
+	password: &PasswordKey,
+	data: &'a [u8],
+	key_len: usize,
+	iv_len: usize,
+) -> Result<(DerivedKey, &'a [u8]), String> {
+	let (salt, body) = match data.strip_prefix(&SALTED_MAGIC[..]) {
+		Some(rest) if rest.len() >= SALT_LEN => (rest[..SALT_LEN].to_vec(), &rest[SALT_LEN..]),
+		Some(_) => return Err("Salted__ header is truncated".to_string()),
+		None => (request_salt(password)?.unwrap_or_default(), data),
+	};
+	let (key, iv) = split(password, &salt, key_len, iv_len)?;
+	Ok((
+		DerivedKey {
+			key,
+			iv,
+			header: Vec::new(),
+		},
+		body,
+	))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use data_encoding::HEXLOWER;
+
+	#[test]
+	fn pbkdf2_sha256_matches_rfc_7914() {
+		let out = derive_bytes(
+			b"passwd",
+			KdfSettings::Pbkdf2 {
+				hash: KdfHash::Sha256,
+				iterations: 1,
+			},
+			b"salt",
+			64,
+		)
+		.unwrap();
+		assert_eq!(
+			HEXLOWER.encode(&out),
+			"55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+			 49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+		);
+	}
+
+	#[test]
+	fn scrypt_matches_rfc_7914() {
+		let out = derive_bytes(
+			b"password",
+			KdfSettings::Scrypt {
+				log_n: 10,
+				r: 8,
+				p: 16,
+			},
+			b"NaCl",
+			64,
+		)
+		.unwrap();
+		assert_eq!(
+			HEXLOWER.encode(&out),
+			"fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+			 2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"
+		);
+	}
+
+	#[test]
+	fn evp_bytes_to_key_matches_openssl() {
+		// openssl enc -aes-256-cbc -md md5 -pass pass:secret -S 0001020304050607 -P
+		let out = derive_bytes(
+			b"secret",
+			KdfSettings::EvpBytesToKey {
+				hash: KdfHash::Md5,
+				iterations: 1,
+			},
+			&[0, 1, 2, 3, 4, 5, 6, 7],
+			48,
+		)
+		.unwrap();
+		assert_eq!(
+			HEXLOWER.encode(&out),
+			"035fb8145b73cf111570dc936112be9c375a5d3d8b915bc213bdbef9dbfb7851\
+			 1d112c3c48b1d30dbceeaff080816be4"
+		);
+	}
+
+	#[test]
+	fn salted_header_round_trips() {
+		let password = PasswordKey {
+			password: "secret".to_string().into(),
+			kdf: KdfSettings::Argon2id {
+				memory_kib: 64,
+				iterations: 1,
+				parallelism: 1,
+			},
+			salt: None,
+			salted_header: true,
+		};
+		let derived = derive_for_encrypt(&password, 16, 16).unwrap();
+		assert_eq!(&derived.header[..8], SALTED_MAGIC);
+		let data = [&derived.header[..], b"body"].concat();
+		let (again, body) = derive_for_decrypt(&password, &data, 16, 16).unwrap();
+		assert_eq!(again.key, derived.key);
+		assert_eq!(again.iv, derived.iv);
+		assert_eq!(body, b"body");
+	}
+
+	#[test]
+	fn random_salt_is_always_written_to_the_header() {
+		let password = PasswordKey {
+			password: "secret".to_string().into(),
+			kdf: KdfSettings::Pbkdf2 {
+				hash: KdfHash::Sha256,
+				iterations: 1,
+			},
+			salt: None,
+			salted_header: false,
+		};
+		let derived = derive_for_encrypt(&password, 16, 16).unwrap();
+		assert_eq!(derived.header.len(), 16);
+		let data = [&derived.header[..], b"body"].concat();
+		let (again, body) = derive_for_decrypt(&password, &data, 16, 16).unwrap();
+		assert_eq!(again.key, derived.key);
+		assert_eq!(body, b"body");
+
+		let fixed = PasswordKey {
+			salt: Some("0001020304050607".to_string()),
+			..password
+		};
+		assert!(derive_for_encrypt(&fixed, 16, 16)
+			.unwrap()
+			.header
+			.is_empty());
+	}
+
+	#[test]
+	fn rejects_costs_over_the_limits() {
+		let derive = |kdf| derive_bytes(b"secret", kdf, b"saltsalt", 16);
+		assert!(derive(KdfSettings::Scrypt {
+			log_n: 40,
+			r: 8,
+			p: 1
+		})
+		.is_err());
+		assert!(derive(KdfSettings::Scrypt {
+			log_n: 20,
+			r: 16,
+			p: 1
+		})
+		.is_err());
+		assert!(derive(KdfSettings::Argon2id {
+			memory_kib: 4 * 1024 * 1024,
+			iterations: 1,
+			parallelism: 1
+		})
+		.is_err());
+		assert!(derive(KdfSettings::Argon2id {
+			memory_kib: 64,
+			iterations: 1000,
+			parallelism: 1
+		})
+		.is_err());
+		assert!(derive(KdfSettings::Pbkdf2 {
+			hash: KdfHash::Sha256,
+			iterations: u32::MAX
+		})
+		.is_err());
+	}
+
+	#[test]
+	fn salt_free_kdfs_need_a_salt() {
+		let err = derive_bytes(
+			b"secret",
+			KdfSettings::Scrypt {
+				log_n: 4,
+				r: 1,
+				p: 1,
+			},
+			&[],
+			16,
+		)
+		.unwrap_err();
+		assert!(err.contains("salt"), "{err}");
+	}
+}
@@ -0,0 +1,257 @@
+//! Random key / IV generation and key check values (KCV) for the backend
+//! ciphers.
+
+use data_encoding::{BASE64, HEXLOWER};
+use serde::{Deserialize, Serialize};
+
+use super::block::{parse_cipher_key, BlockAlgorithm};
+use super::des::{classify, fix_parity, same_des_key, DesKeyStrength};
+use super::encoding::KeyEncoding;
+use super::secret::{SecretBytes, SecretString, Zeroizing};
+
+/// Trivium takes an 80-bit key and an 80-bit IV.
+const TRIVIUM_KEY_LENGTH: usize = 10;
+
+/// Key length used for the variable-length ciphers when none is requested.
+const DEFAULT_VARIABLE_KEY_LENGTH: usize = 16;
+
+/// Bytes of the encrypted zero block that make up the KCV.
+const KCV_LENGTH: usize = 3;
+
+/// Characters used for text keys: letters and digits without the look-alikes
+/// 0/O and 1/l/I, so a key read off the screen types back unambiguously.
+const TEXT_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz23456789";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamAlgorithm {
+	Trivium,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum KeyAlgorithm {
+	Block(BlockAlgorithm),
+	Stream(StreamAlgorithm),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyGenRequest {
+	pub algorithm: KeyAlgorithm,
+	/// Text keys use letters and digits only, so they survive copy and paste.
+	pub key_type: KeyEncoding,
+	/// Only for Blowfish, RC2 and CAST5; 16 bytes when absent.
+	pub key_length: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedKey {
+	/// Key in the requested encoding.
+	pub key: SecretString,
+	pub key_hex: SecretString,
+	pub key_base64: SecretString,
+	/// Fresh IV of the cipher's block size (Trivium: 10 bytes), hex.
+	pub iv: String,
+	/// Absent for Trivium, which has no block function.
+	pub kcv: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KcvRequest {
+	pub algorithm: BlockAlgorithm,
+	pub key: SecretString,
+	pub key_type: KeyEncoding,
+}
+
+fn random_bytes(length: usize) -> Result<SecretBytes, String> {
+	let mut bytes = Zeroizing::new(vec![0u8; length]);
+	getrandom::fill(&mut bytes).map_err(|e| format!("Random number generator failed: {e}"))?;
+	Ok(bytes)
+}
+
+fn random_text(length: usize) -> Result<SecretBytes, String> {
+	// Rejection sampling keeps every character equally likely.
+	let limit = 256 - 256 % TEXT_ALPHABET.len();
+	let mut out = Zeroizing::new(Vec::with_capacity(length));
+	while out.len() < length {
+		for &byte in random_bytes(length)?.iter() {
+			if (byte as usize) < limit && out.len() < length {
+				out.push(TEXT_ALPHABET[byte as usize % TEXT_ALPHABET.len()]);
+			}
+		}
+	}
+	Ok(out)
+}
+
+fn key_length(algorithm: KeyAlgorithm, requested: Option<usize>) -> Result<usize, String> {
+	let algorithm = match algorithm {
+		KeyAlgorithm::Stream(StreamAlgorithm::Trivium) => return Ok(TRIVIUM_KEY_LENGTH),
+		KeyAlgorithm::Block(algorithm) => algorithm,
+	};
+	let lengths = algorithm.key_lengths();
+	if lengths.start() == lengths.end() {
+		return Ok(*lengths.start());
+	}
+	let length = requested.unwrap_or(DEFAULT_VARIABLE_KEY_LENGTH);
+	if !lengths.contains(&length) {
+		return Err(format!(
+			"{algorithm} key must be {}-{} bytes, got {length}",
+			lengths.start(),
+			lengths.end()
+		));
+	}
+	Ok(length)
+}
+
+fn is_des_family(algorithm: BlockAlgorithm) -> bool {
+	matches!(
+		algorithm,
+		BlockAlgorithm::Des | BlockAlgorithm::Tdes2 | BlockAlgorithm::Tdes3
+	)
+}
+
+/// DES keys get odd parity; weak, semi-weak and repeated components are redrawn.
+fn acceptable_des_key(key: &[u8]) -> bool {
+	let parts: Vec<&[u8]> = key.chunks(8).collect();
+	let distinct = parts.windows(2).all(|pair| !same_des_key(pair[0], pair[1]));
+	distinct
+		&& parts
+			.iter()
+			.all(|part| classify(part) == DesKeyStrength::Normal)
+}
+
+pub(super) fn compute_kcv(algorithm: BlockAlgorithm, key: &[u8]) -> Result<String, String> {
+	let cipher = algorithm.cipher(key)?;
+	let mut block = vec![0u8; algorithm.block_size()];
+	cipher.encrypt_block(&mut block);
+	Ok(HEXLOWER.encode(&block[..KCV_LENGTH]))
+}
+
+#[tauri::command]
+pub fn generate_key(request: KeyGenRequest) -> Result<GeneratedKey, String> {
+	let length = key_length(request.algorithm, request.key_length)?;
+	let key = loop {
+		let key = match request.key_type {
+			KeyEncoding::Text => random_text(length)?,
+			KeyEncoding::Hex | KeyEncoding::Base64 => random_bytes(length)?,
+		};
+		match request.algorithm {
+			KeyAlgorithm::Block(algorithm) if is_des_family(algorithm) => {
+				let key = if request.key_type == KeyEncoding::Text {
+					key
+				} else {
+					Zeroizing::new(fix_parity(&key))
+				};
+				if acceptable_des_key(&key) {
+					break key;
+				}
+			}
+			_ => break key,
+		}
+	};
+
+	let (iv_length, kcv) = match request.algorithm {
+		KeyAlgorithm::Block(algorithm) => {
+			(algorithm.block_size(), Some(compute_kcv(algorithm, &key)?))
+		}
+		KeyAlgorithm::Stream(StreamAlgorithm::Trivium) => (TRIVIUM_KEY_LENGTH, None),
+	};
+
+	Ok(GeneratedKey {
+		key: Zeroizing::new(match request.key_type {
+			KeyEncoding::Text => String::from_utf8(key.to_vec()).expect("text keys are ASCII"),
+			KeyEncoding::Hex => HEXLOWER.encode(&key),
+			KeyEncoding::Base64 => BASE64.encode(&key),
+		}),
+		key_hex: Zeroizing::new(HEXLOWER.encode(&key)),
+		key_base64: Zeroizing::new(BASE64.encode(&key)),
+		iv: HEXLOWER.encode(&random_bytes(iv_length)?),
+		kcv,
+	})
+}
+
+/// Encrypts a zero block under the key and returns its first 3 bytes as hex.
+#[tauri::command]
+pub fn key_check_value(request: KcvRequest) -> Result<String, String> {
+	let key = parse_cipher_key(request.algorithm, &request.key, request.key_type, 1)?;
+	compute_kcv(request.algorithm, &key)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn generate(algorithm: KeyAlgorithm, key_type: KeyEncoding) -> GeneratedKey {
+		generate_key(KeyGenRequest {
+			algorithm,
+			key_type,
+			key_length: None,
+		})
+		.unwrap()
+	}
+
+	#[test]
+	fn generated_lengths_match_the_algorithm() {
+		let key = generate(KeyAlgorithm::Block(BlockAlgorithm::Sm4), KeyEncoding::Hex);
+		assert_eq!(key.key.len(), 32);
+		assert_eq!(key.iv.len(), 32);
+		assert_eq!(key.kcv.unwrap().len(), 6);
+
+		let key = generate(
+			KeyAlgorithm::Block(BlockAlgorithm::Aes256),
+			KeyEncoding::Base64,
+		);
+		assert_eq!(key.key_hex.len(), 64);
+
+		let key = generate(
+			KeyAlgorithm::Stream(StreamAlgorithm::Trivium),
+			KeyEncoding::Hex,
+		);
+		assert_eq!(key.key.len(), 20);
+		assert_eq!(key.iv.len(), 20);
+		assert!(key.kcv.is_none());
+	}
+
+	#[test]
+	fn text_keys_are_alphanumeric() {
+		let key = generate(KeyAlgorithm::Block(BlockAlgorithm::Sm4), KeyEncoding::Text);
+		assert_eq!(key.key.len(), 16);
+		assert!(key.key.bytes().all(|b| b.is_ascii_alphanumeric()));
+		assert!(!key.key.contains(['0', 'O', '1', 'l', 'I']));
+	}
+
+	#[test]
+	fn des_keys_have_odd_parity() {
+		let key = generate(KeyAlgorithm::Block(BlockAlgorithm::Tdes3), KeyEncoding::Hex);
+		let bytes = HEXLOWER.decode(key.key.as_bytes()).unwrap();
+		assert_eq!(bytes.len(), 24);
+		assert_eq!(fix_parity(&bytes), bytes);
+	}
+
+	#[test]
+	fn kcv_of_known_keys() {
+		let kcv = |algorithm, key: &str| {
+			key_check_value(KcvRequest {
+				algorithm,
+				key: key.to_string().into(),
+				key_type: KeyEncoding::Hex,
+			})
+			.unwrap()
+		};
+		// AES-128 encryption of the zero block under the zero key is 66e94bd4...
+		assert_eq!(kcv(BlockAlgorithm::Aes128, &"00".repeat(16)), "66e94b");
+		// DES of the zero block under 0123456789abcdef is d5d44ff720683d0d.
+		assert_eq!(kcv(BlockAlgorithm::Des, "0123456789abcdef"), "d5d44f");
+	}
+
+	#[test]
+	fn deserializes_block_and_stream_algorithms() {
+		let block: KeyAlgorithm = serde_json::from_str("\"aes128\"").unwrap();
+		assert_eq!(block, KeyAlgorithm::Block(BlockAlgorithm::Aes128));
+		let stream: KeyAlgorithm = serde_json::from_str("\"trivium\"").unwrap();
+		assert_eq!(stream, KeyAlgorithm::Stream(StreamAlgorithm::Trivium));
+	}
+}
@@ -0,0 +1,489 @@
+//! Keyed message authentication: HMAC over any fixed-length digest of the
+//! hash module, KMAC128/256 (NIST SP 800-185) and BLAKE2's built-in keyed
+//! mode. A given MAC is checked in constant time.
+
+use std::path::Path;
+
+use blake2::digest::block_buffer::Lazy;
+use blake2::digest::consts::{True, U256};
+use blake2::digest::core_api::{Block, Buffer, BufferKindUser, UpdateCore, VariableOutputCore};
+use blake2::digest::typenum::IsLess;
+use blake2::{Blake2bVarCore, Blake2sVarCore};
+use hmac::digest::KeyInit;
+use hmac::{Mac, SimpleHmac};
+use serde::{Deserialize, Serialize};
+use sha2::digest::core_api::BlockSizeUser;
+use sha2::digest::{Digest, ExtendableOutput, Update, XofReader};
+use sha3::{CShake128, CShake128Core, CShake256, CShake256Core};
+use subtle::ConstantTimeEq;
+
+use super::encoding::{
+	decode_bytes, decode_key_bytes, encode_bytes, require_binary_format, DataFormat, KeyEncoding,
+};
+use super::hash::{stream_file, HashAlgorithm, HashInputType, MAX_XOF_LENGTH};
+use super::secret::SecretString;
+
+/// cSHAKE128 and cSHAKE256 absorb this many bytes per block.
+const KMAC128_RATE: usize = 168;
+const KMAC256_RATE: usize = 136;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MacAlgorithm {
+	/// HMAC with the digest named in `hash`.
+	Hmac,
+	/// 32 bytes of output by default.
+	Kmac128,
+	/// 64 bytes of output by default.
+	Kmac256,
+	/// Keyed BLAKE2b, 64 bytes of output by default; keys up to 64 bytes.
+	Blake2b,
+	/// Keyed BLAKE2s, 32 bytes of output by default; keys up to 32 bytes.
+	Blake2s,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacRequest {
+	pub input: String,
+	pub input_type: HashInputType,
+	pub algorithm: MacAlgorithm,
+	/// Digest for HMAC; ignored otherwise.
+	pub hash: Option<HashAlgorithm>,
+	pub key: SecretString,
+	pub key_type: KeyEncoding,
+	/// KMAC customization string S, as UTF-8.
+	pub customization: Option<String>,
+	/// KMAC output length L and BLAKE2 digest length in bytes. For HMAC, keep
+	/// only the leftmost bytes; full length when absent.
+	pub output_length: Option<usize>,
+	/// Format of the MAC and of `expected`; hex when absent.
+	pub format: Option<DataFormat>,
+	/// MAC to compare against.
+	pub expected: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacResponse {
+	pub mac: String,
+	/// Set when `expected` was given.
+	pub matches: Option<bool>,
+}
+
+/// A running MAC, whatever the construction.
+trait MacState: Send {
+	fn update(&mut self, data: &[u8]);
+	fn finalize(self: Box<Self>) -> Vec<u8>;
+}
+
+impl<M: Mac + Send> MacState for M {
+	fn update(&mut self, data: &[u8]) {
+		Mac::update(self, data);
+	}
+
+	fn finalize(self: Box<Self>) -> Vec<u8> {
+		Mac::finalize(*self).into_bytes().to_vec()
+	}
+}
+
+/// KMAC(K, X, L, S) = cSHAKE(bytepad(encode_string(K)) || X || right_encode(L), L, "KMAC", S).
+struct Kmac<X> {
+	xof: X,
+	length: usize,
+}
+
+impl<X: Update + ExtendableOutput + Send> MacState for Kmac<X> {
+	fn update(&mut self, data: &[u8]) {
+		Update::update(&mut self.xof, data);
+	}
+
+	fn finalize(mut self: Box<Self>) -> Vec<u8> {
+		Update::update(&mut self.xof, &right_encode(self.length as u64 * 8));
+		let mut out = vec![0u8; self.length];
+		self.xof.finalize_xof().read(&mut out);
+		out
+	}
+}
+
+impl<X: Update> Kmac<X> {
+	fn new(mut xof: X, rate: usize, key: &[u8], length: usize) -> Self {
+		let encoded_key = [left_encode(key.len() as u64 * 8).as_slice(), key].concat();
+		Update::update(&mut xof, &bytepad(&encoded_key, rate));
+		Kmac { xof, length }
+	}
+}
+
+/// SP 800-185 left_encode: the byte count, then x big-endian without leading zeros.
+fn left_encode(x: u64) -> Vec<u8> {
+	let bytes = x.to_be_bytes();
+	let skip = bytes[..7].iter().take_while(|&&b| b == 0).count();
+	let mut out = vec![(8 - skip) as u8];
+	out.extend_from_slice(&bytes[skip..]);
+	out
+}
+
+/// SP 800-185 right_encode: like `left_encode` with the byte count last.
+fn right_encode(x: u64) -> Vec<u8> {
+	let mut out = left_encode(x);
+	out.rotate_left(1);
+	out
+}
+
+fn bytepad(data: &[u8], rate: usize) -> Vec<u8> {
+	let mut out = left_encode(rate as u64);
+	out.extend_from_slice(data);
+	out.resize(out.len().div_ceil(rate) * rate, 0);
+	out
+}
+
+/// Keyed BLAKE2 with the digest length in its parameter block, so a shorter
+/// MAC is not a prefix of the full-length one.
+struct KeyedBlake2<C>
+where
+	C: BufferKindUser<BufferKind = Lazy>,
+	C::BlockSize: IsLess<U256, Output = True>,
+{
+	core: C,
+	buffer: Buffer<C>,
+	length: usize,
+}
+
+impl<C> KeyedBlake2<C>
+where
+	C: UpdateCore + BufferKindUser<BufferKind = Lazy>,
+	C::BlockSize: IsLess<U256, Output = True>,
+{
+	/// `core` must already carry the key and digest lengths.
+	fn new(core: C, key: &[u8], length: usize) -> Self {
+		// A non-empty key is padded to a full block and hashed first.
+		let buffer = if key.is_empty() {
+			Buffer::<C>::default()
+		} else {
+			let mut padded = Block::<C>::default();
+			padded[..key.len()].copy_from_slice(key);
+			Buffer::<C>::new(&padded)
+		};
+		KeyedBlake2 {
+			core,
+			buffer,
+			length,
+		}
+	}
+}
+
+impl<C> MacState for KeyedBlake2<C>
+where
+	C: UpdateCore + VariableOutputCore + BufferKindUser<BufferKind = Lazy> + Send,
+	C::BlockSize: IsLess<U256, Output = True>,
+{
+	fn update(&mut self, data: &[u8]) {
+		let Self { core, buffer, .. } = self;
+		buffer.digest_blocks(data, |blocks| core.update_blocks(blocks));
+	}
+
+	fn finalize(mut self: Box<Self>) -> Vec<u8> {
+		let Self {
+			core,
+			buffer,
+			length,
+		} = &mut *self;
+		let mut out = Default::default();
+		core.finalize_variable_core(buffer, &mut out);
+		out[..*length].to_vec()
+	}
+}
+
+fn hmac<D>(key: &[u8]) -> Box<dyn MacState>
+where
+	D: Digest + BlockSizeUser + Clone + Send + 'static,
+{
+	// HMAC accepts keys of any length, so this cannot fail.
+	Box::new(<SimpleHmac<D> as KeyInit>::new_from_slice(key).expect("HMAC takes any key length"))
+}
+
+fn hmac_for(hash: HashAlgorithm, key: &[u8]) -> Result<Box<dyn MacState>, String> {
+	Ok(match hash {
+		HashAlgorithm::Md2 => hmac::<md2::Md2>(key),
+		HashAlgorithm::Md4 => hmac::<md4::Md4>(key),
+		HashAlgorithm::Md5 => hmac::<md5::Md5>(key),
+		HashAlgorithm::Sha1 => hmac::<sha1::Sha1>(key),
+		HashAlgorithm::Sha224 => hmac::<sha2::Sha224>(key),
+		HashAlgorithm::Sha256 => hmac::<sha2::Sha256>(key),
+		HashAlgorithm::Sha384 => hmac::<sha2::Sha384>(key),
+		HashAlgorithm::Sha512 => hmac::<sha2::Sha512>(key),
+		HashAlgorithm::Sha512_224 => hmac::<sha2::Sha512_224>(key),
+		HashAlgorithm::Sha512_256 => hmac::<sha2::Sha512_256>(key),
+		HashAlgorithm::Sha3_224 => hmac::<sha3::Sha3_224>(key),
+		HashAlgorithm::Sha3_256 => hmac::<sha3::Sha3_256>(key),
+		HashAlgorithm::Sha3_384 => hmac::<sha3::Sha3_384>(key),
+		HashAlgorithm::Sha3_512 => hmac::<sha3::Sha3_512>(key),
+		HashAlgorithm::Keccak224 => hmac::<sha3::Keccak224>(key),
+		HashAlgorithm::Keccak256 => hmac::<sha3::Keccak256>(key),
+		HashAlgorithm::Keccak384 => hmac::<sha3::Keccak384>(key),
+		HashAlgorithm::Keccak512 => hmac::<sha3::Keccak512>(key),
+		HashAlgorithm::Blake2b => hmac::<blake2::Blake2b512>(key),
+		HashAlgorithm::Blake2s => hmac::<blake2::Blake2s256>(key),
+		HashAlgorithm::Ripemd160 => hmac::<ripemd::Ripemd160>(key),
+		HashAlgorithm::Whirlpool => hmac::<whirlpool::Whirlpool>(key),
+		HashAlgorithm::Tiger => hmac::<tiger::Tiger>(key),
+		HashAlgorithm::Streebog256 => hmac::<streebog::Streebog256>(key),
+		HashAlgorithm::Streebog512 => hmac::<streebog::Streebog512>(key),
+		HashAlgorithm::Sm3 => hmac::<sm3::Sm3>(key),
+		HashAlgorithm::Shake128
+		| HashAlgorithm::Shake256
+		| HashAlgorithm::Blake3
+		| HashAlgorithm::Crc32 => {
+			return Err(format!("HMAC needs a fixed-length hash, not {hash}"));
+		}
+	})
+}
+
+fn new_mac(request: &MacRequest, key: &[u8]) -> Result<Box<dyn MacState>, String> {
+	let customization = request.customization.as_deref().unwrap_or("").as_bytes();
+	let kmac_length = |default: usize| match request.output_length {
+		None => Ok(default),
+		Some(len) if (1..=MAX_XOF_LENGTH).contains(&len) => Ok(len),
+		Some(len) => Err(format!(
+			"KMAC output length must be 1-{MAX_XOF_LENGTH} bytes, got {len}"
+		)),
+	};
+	let blake2_length = |name: &str, max: usize| match request.output_length {
+		None => Ok(max),
+		Some(len) if (1..=max).contains(&len) => Ok(len),
+		Some(len) => Err(format!(
+			"{name} output length must be 1-{max} bytes, got {len}"
+		)),
+	};
+	Ok(match request.algorithm {
+		MacAlgorithm::Hmac => {
+			let hash = request.hash.ok_or("HMAC needs a hash algorithm")?;
+			hmac_for(hash, key)?
+		}
+		MacAlgorithm::Kmac128 => Box::new(Kmac::new(
+			CShake128::from_core(CShake128Core::new_with_function_name(
+				b"KMAC",
+				customization,
+			)),
+			KMAC128_RATE,
+			key,
+			kmac_length(32)?,
+		)),
+		MacAlgorithm::Kmac256 => Box::new(Kmac::new(
+			CShake256::from_core(CShake256Core::new_with_function_name(
+				b"KMAC",
+				customization,
+			)),
+			KMAC256_RATE,
+			key,
+			kmac_length(64)?,
+		)),
+		MacAlgorithm::Blake2b => {
+			if key.len() > 64 {
+				return Err(format!(
+					"BLAKE2b keys are at most 64 bytes, got {}",
+					key.len()
+				));
+			}
+			let length = blake2_length("BLAKE2b", 64)?;
+			let core = Blake2bVarCore::new_with_params(&[], &[], key.len(), length);
+			Box::new(KeyedBlake2::new(core, key, length))
+		}
+		MacAlgorithm::Blake2s => {
+			if key.len() > 32 {
+				return Err(format!(
+					"BLAKE2s keys are at most 32 bytes, got {}",
+					key.len()
+				));
+			}
+			let length = blake2_length("BLAKE2s", 32)?;
+			let core = Blake2sVarCore::new_with_params(&[], &[], key.len(), length);
+			Box::new(KeyedBlake2::new(core, key, length))
+		}
+	})
+}
+
+#[tauri::command(async)]
+pub fn mac_compute(request: MacRequest) -> Result<MacResponse, String> {
+	let format = request.format.unwrap_or(DataFormat::Hex);
+	require_binary_format(format)?;
+	let key = decode_key_bytes(&request.key, request.key_type)?;
+	let mut mac = new_mac(&request, &key)?;
+
+	match request.input_type {
+		HashInputType::Text => mac.update(request.input.as_bytes()),
+		HashInputType::Hex => mac.update(&decode_bytes(&request.input, DataFormat::Hex)?),
+		HashInputType::File => stream_file(
+			Path::new(request.input.trim()),
+			&mut |chunk| mac.update(chunk),
+			&mut |_| {},
+		)?,
+	}
+
+	let mut tag = mac.finalize();
+	if let (MacAlgorithm::Hmac, Some(len)) = (request.algorithm, request.output_length) {
+		if len == 0 || len > tag.len() {
+			return Err(format!(
+				"Output length must be 1-{} bytes, got {len}",
+				tag.len()
+			));
+		}
+		tag.truncate(len);
+	}
+
+	let matches = match &request.expected {
+		Some(expected) => {
+			let expected = decode_bytes(expected, format)?;
+			Some(bool::from(tag.ct_eq(&expected)))
+		}
+		None => None,
+	};
+	Ok(MacResponse {
+		mac: encode_bytes(&tag, format)?,
+		matches,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const JEFE_MESSAGE: &str = "what do ya want for nothing?";
+
+	fn request(algorithm: MacAlgorithm, hash: Option<HashAlgorithm>) -> MacRequest {
+		MacRequest {
+			input: JEFE_MESSAGE.to_string(),
+			input_type: HashInputType::Text,
+			algorithm,
+			hash,
+			key: "Jefe".to_string().into(),
+			key_type: KeyEncoding::Text,
+			customization: None,
+			output_length: None,
+			format: None,
+			expected: None,
+		}
+	}
+
+	fn mac(request: MacRequest) -> String {
+		mac_compute(request).unwrap().mac
+	}
+
+	#[test]
+	fn hmac_matches_reference_values() {
+		// RFC 2104 / RFC 4231 test case 2; the rest from Python's hmac module.
+		let vectors = [
+			(HashAlgorithm::Md5, "750c783e6ab0b503eaa86e310a5db738"),
+			(
+				HashAlgorithm::Sha1,
+				"effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+			),
+			(
+				HashAlgorithm::Sha256,
+				"5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+			),
+			(
+				HashAlgorithm::Sha512,
+				"164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+				 9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+			),
+			(
+				HashAlgorithm::Sha3_256,
+				"c7d4072e788877ae3596bbb0da73b887c9171f93095b294ae857fbe2645e1ba5",
+			),
+			(
+				HashAlgorithm::Sm3,
+				"2e87f1d16862e6d964b50a5200bf2b10b764faa9680a296a2405f24bec39f882",
+			),
+			(
+				HashAlgorithm::Blake2s,
+				"90b6281e2f3038c9056af0b4a7e763cae6fe5d9eb4386a0ec95237890c104ff0",
+			),
+		];
+		for (hash, expected) in vectors {
+			assert_eq!(
+				mac(request(MacAlgorithm::Hmac, Some(hash))),
+				expected,
+				"{hash}"
+			);
+		}
+		assert!(mac_compute(request(MacAlgorithm::Hmac, Some(HashAlgorithm::Shake128))).is_err());
+	}
+
+	#[test]
+	fn kmac_matches_sp_800_185_samples() {
+		let sample = |algorithm, customization: &str| MacRequest {
+			input: "00010203".to_string(),
+			input_type: HashInputType::Hex,
+			key: "404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f"
+				.to_string()
+				.into(),
+			key_type: KeyEncoding::Hex,
+			customization: Some(customization.to_string()),
+			..request(algorithm, None)
+		};
+		assert_eq!(
+			mac(sample(MacAlgorithm::Kmac128, "")),
+			"e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e"
+		);
+		assert_eq!(
+			mac(sample(MacAlgorithm::Kmac128, "My Tagged Application")),
+			"3b1fba963cd8b0b59e8c1a6d71888b7143651af8ba0a7070c0979e2811324aa5"
+		);
+		assert_eq!(
+			mac(sample(MacAlgorithm::Kmac256, "My Tagged Application")),
+			"20c570c31346f703c9ac36c61c03cb64c3970d0cfc787e9b79599d273a68d2f7\
+			 f69d4cc3de9d104a351689f27cf6f5951f0103f33f4f24871024d9c27773a8dd"
+		);
+	}
+
+	#[test]
+	fn blake2_keyed_mode_matches_reference_values() {
+		assert_eq!(
+			mac(request(MacAlgorithm::Blake2b, None)),
+			"380246f80263db862b00d41ebb70e6d26fa97c4b42ae7985991deb963b4317aa\
+			 33735ff9dc76bd294455731365ab3a9eb67d33f83f98360f2bae5f7a4356e6b1"
+		);
+		assert_eq!(
+			mac(request(MacAlgorithm::Blake2s, None)),
+			"d7fe099d889ba98178a934de6bd36da084600d7831ff16b8deaeefa8f6c00af4"
+		);
+	}
+
+	#[test]
+	fn blake2_output_length_is_a_parameter_not_a_truncation() {
+		// Python's hashlib.blake2b(b"abc", key=b"key", digest_size=32), and blake2s with 16.
+		let abc = |algorithm, output_length| MacRequest {
+			input: "abc".to_string(),
+			key: "key".to_string().into(),
+			output_length: Some(output_length),
+			..request(algorithm, None)
+		};
+		assert_eq!(
+			mac(abc(MacAlgorithm::Blake2b, 32)),
+			"0330531d097355a3f72e80d55c1245ccf79f1704431c6e3887938320442c23c0"
+		);
+		assert_eq!(
+			mac(abc(MacAlgorithm::Blake2s, 16)),
+			"94fdf6f35b9999920dcdcaee361ad435"
+		);
+		assert!(mac_compute(abc(MacAlgorithm::Blake2s, 33)).is_err());
+	}
+
+	#[test]
+	fn verifies_expected_mac() {
+		let check = |expected: &str, output_length| {
+			mac_compute(MacRequest {
+				output_length,
+				expected: Some(expected.to_string()),
+				..request(MacAlgorithm::Hmac, Some(HashAlgorithm::Md5))
+			})
+			.unwrap()
+			.matches
+		};
+		assert_eq!(check("750c783e6ab0b503eaa86e310a5db738", None), Some(true));
+		assert_eq!(check("750c783e6ab0b503eaa86e310a5db739", None), Some(false));
+		assert_eq!(check("750c783e6ab0b503", None), Some(false));
+		assert_eq!(check("750c783e6ab0b503", Some(8)), Some(true));
+	}
+}
@@ -1 +1,23 @@
+pub mod block;
+pub mod checksum;
+pub mod crc;
+pub mod crypt;
+pub mod des;
+pub mod encoding;
+pub mod fast_hash;
+pub mod hash;
+pub mod identify;
+pub mod kdf;
+pub mod keygen;
+pub mod keywrap;
+pub mod mac;
+pub mod password;
+pub mod secret;
+pub mod seed;
+pub mod self_test;
+pub mod sm2;
+pub mod sm2_exchange;
+pub mod sm3;
 pub mod sm4;
+pub mod sm4_trace;
+pub mod sm9;
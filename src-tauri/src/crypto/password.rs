@@ -0,0 +1,456 @@
+//! Password storage hashes: bcrypt in its modular crypt form (`$2b$`), and
+//! scrypt, Argon2 and PBKDF2 as PHC strings (`$argon2id$v=19$...`). Stored
+//! hashes can be verified against a password and taken apart to show their
+//! parameters.
+
+use argon2::password_hash::{self, PasswordHash, PasswordHasher, SaltString};
+use argon2::{Argon2, Version};
+use data_encoding::{Encoding, Specification, HEXLOWER};
+use pbkdf2::Pbkdf2;
+use scrypt::Scrypt;
+use serde::{Deserialize, Serialize};
+
+use super::encoding::{decode_key_bytes, KeyEncoding};
+use super::kdf::{check_argon2_cost, check_iterations, check_scrypt_cost};
+use super::secret::SecretString;
+
+/// Salt drawn when none is given, for every algorithm.
+const SALT_LEN: usize = 16;
+/// bcrypt's salt and its 23-byte digest are written in this base64 alphabet.
+const BCRYPT_ALPHABET: &str = "./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Argon2Variant {
+	Argon2i,
+	Argon2d,
+	Argon2id,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pbkdf2Hash {
+	Sha256,
+	Sha512,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "algorithm", rename_all = "camelCase")]
+pub enum PasswordHashSettings {
+	/// Cost 4-31; 2^cost rounds. Only the first 72 bytes of the password count.
+	Bcrypt {
+		cost: u32,
+	},
+	/// N = 2^logN.
+	#[serde(rename_all = "camelCase")]
+	Scrypt {
+		log_n: u8,
+		r: u32,
+		p: u32,
+	},
+	#[serde(rename_all = "camelCase")]
+	Argon2 {
+		variant: Argon2Variant,
+		memory_kib: u32,
+		iterations: u32,
+		parallelism: u32,
+	},
+	Pbkdf2 {
+		hash: Pbkdf2Hash,
+		iterations: u32,
+	},
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordHashRequest {
+	pub password: SecretString,
+	#[serde(flatten)]
+	pub settings: PasswordHashSettings,
+	/// Hex; 16 random bytes when absent. bcrypt needs exactly 16 bytes.
+	pub salt: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordVerifyRequest {
+	pub password: SecretString,
+	/// Stored hash, bcrypt or PHC.
+	pub hash: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordHashParam {
+	pub name: String,
+	pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordHashInfo {
+	/// PHC identifier (`argon2id`, `scrypt`, `pbkdf2-sha256`, ...) or `bcrypt`.
+	pub algorithm: String,
+	/// Argon2 `v=19`, or the bcrypt revision (`2a`, `2b`, `2y`).
+	pub version: Option<String>,
+	/// In the order they appear in the hash.
+	pub params: Vec<PasswordHashParam>,
+	/// Hex.
+	pub salt: String,
+	/// Hex.
+	pub hash: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordVerifyResponse {
+	pub matches: bool,
+	pub info: PasswordHashInfo,
+}
+
+fn param(name: &str, value: impl ToString) -> PasswordHashParam {
+	PasswordHashParam {
+		name: name.to_string(),
+		value: value.to_string(),
+	}
+}
+
+fn bcrypt_base64() -> Encoding {
+	let mut spec = Specification::new();
+	spec.symbols.push_str(BCRYPT_ALPHABET);
+	spec.check_trailing_bits = false;
+	spec.encoding().expect("valid bcrypt alphabet")
+}
+
+/// The parts of `$2b$12$<22-char salt><31-char digest>`.
+struct BcryptHash<'a> {
+	revision: &'a str,
+	cost: u32,
+	salt: Vec<u8>,
+	digest: Vec<u8>,
+}
+
+fn parse_bcrypt(hash: &str) -> Option<BcryptHash<'_>> {
+	let mut parts = hash.strip_prefix('$')?.split('$');
+	let revision = parts.next()?;
+	let cost = parts.next()?;
+	let rest = parts.next()?;
+	if parts.next().is_some()
+		|| !matches!(revision, "2" | "2a" | "2b" | "2x" | "2y")
+		|| cost.len() != 2
+		|| rest.len() != 53
+		|| !rest.is_ascii()
+	{
+		return None;
+	}
+	let base64 = bcrypt_base64();
+	// 22 characters carry 16 bytes and 31 carry 23; the spare bits are dropped.
+	let salt = base64
+		.decode(format!("{}..", &rest[..22]).as_bytes())
+		.ok()?;
+	let digest = base64.decode(format!("{}.", &rest[22..]).as_bytes()).ok()?;
+	Some(BcryptHash {
+		revision,
+		cost: cost.parse().ok()?,
+		salt: salt[..16].to_vec(),
+		digest: digest[..23].to_vec(),
+	})
+}
+
+fn phc_info(hash: &PasswordHash<'_>) -> PasswordHashInfo {
+	let salt = hash.salt.map(|salt| {
+		let mut buf = [0u8; 64];
+		match salt.decode_b64(&mut buf) {
+			Ok(bytes) => HEXLOWER.encode(bytes),
+			Err(_) => HEXLOWER.encode(salt.as_str().as_bytes()),
+		}
+	});
+	PasswordHashInfo {
+		algorithm: hash.algorithm.to_string(),
+		version: hash.version.map(|v| v.to_string()),
+		params: hash
+			.params
+			.iter()
+			.map(|(name, value)| param(name.as_str(), value.as_str()))
+			.collect(),
+		salt: salt.unwrap_or_default(),
+		hash: hash
+			.hash
+			.map(|h| HEXLOWER.encode(h.as_bytes()))
+			.unwrap_or_default(),
+	}
+}
+
+fn describe(hash: &str) -> Result<PasswordHashInfo, String> {
+	let hash = hash.trim();
+	if let Some(bcrypt) = parse_bcrypt(hash) {
+		return Ok(PasswordHashInfo {
+			algorithm: "bcrypt".to_string(),
+			version: Some(bcrypt.revision.to_string()),
+			params: vec![param("cost", bcrypt.cost)],
+			salt: HEXLOWER.encode(&bcrypt.salt),
+			hash: HEXLOWER.encode(&bcrypt.digest),
+		});
+	}
+	let parsed =
+		PasswordHash::new(hash).map_err(|e| format!("Not a bcrypt or PHC hash string: {e}"))?;
+	Ok(phc_info(&parsed))
+}
+
+fn salt_bytes(salt: Option<&str>) -> Result<Vec<u8>, String> {
+	match salt.filter(|s| !s.trim().is_empty()) {
+		Some(salt) => decode_key_bytes(salt, KeyEncoding::Hex).map(|salt| salt.to_vec()),
+		None => {
+			let mut salt = vec![0u8; SALT_LEN];
+			getrandom::fill(&mut salt)
+				.map_err(|e| format!("Random number generator failed: {e}"))?;
+			Ok(salt)
+		}
+	}
+}
+
+/// Costs taken from a stored hash are held to the same limits as `password_hash`,
+/// so a crafted string cannot ask for gigabytes or hours of work. Missing
+/// parameters fall back to the hashers' own small defaults.
+fn check_phc_cost(hash: &PasswordHash<'_>) -> Result<(), String> {
+	let decimal = |name: &str| hash.params.get_decimal(name).unwrap_or(0);
+	match hash.algorithm.as_str() {
+		"scrypt" => {
+			let log_n = u8::try_from(decimal("ln")).unwrap_or(u8::MAX);
+			check_scrypt_cost(log_n, decimal("r"), decimal("p"))
+		}
+		"argon2i" | "argon2d" | "argon2id" => check_argon2_cost(decimal("m"), decimal("t")),
+		"pbkdf2-sha256" | "pbkdf2-sha512" | "pbkdf2-sha1" => check_iterations(decimal("i")),
+		_ => Ok(()),
+	}
+}
+
+fn phc_error(e: password_hash::Error) -> String {
+	e.to_string()
+}
+
+#[tauri::command(async)]
+pub fn password_hash(request: PasswordHashRequest) -> Result<String, String> {
+	let password = request.password.as_bytes();
+	let salt = salt_bytes(request.salt.as_deref())?;
+
+	if let PasswordHashSettings::Bcrypt { cost } = request.settings {
+		let salt: [u8; 16] = salt
+			.as_slice()
+			.try_into()
+			.map_err(|_| format!("bcrypt salt must be 16 bytes, got {} bytes", salt.len()))?;
+		return bcrypt::hash_with_salt(password, cost, salt)
+			.map(|parts| parts.format_for_version(bcrypt::Version::TwoB))
+			.map_err(|e| format!("bcrypt: {e}"));
+	}
+
+	let salt = SaltString::encode_b64(&salt).map_err(phc_error)?;
+	let hash = match request.settings {
+		PasswordHashSettings::Bcrypt { .. } => unreachable!("handled above"),
+		PasswordHashSettings::Scrypt { log_n, r, p } => {
+			check_scrypt_cost(log_n, r, p)?;
+			let params = scrypt::Params::new(log_n, r, p, scrypt::Params::RECOMMENDED_LEN)
+				.map_err(|e| format!("scrypt: {e}"))?;
+			Scrypt.hash_password_customized(password, None, None, params, &salt)
+		}
+		PasswordHashSettings::Argon2 {
+			variant,
+			memory_kib,
+			iterations,
+			parallelism,
+		} => {
+			let algorithm = match variant {
+				Argon2Variant::Argon2i => argon2::Algorithm::Argon2i,
+				Argon2Variant::Argon2d => argon2::Algorithm::Argon2d,
+				Argon2Variant::Argon2id => argon2::Algorithm::Argon2id,
+			};
+			check_argon2_cost(memory_kib, iterations)?;
+			let params = argon2::Params::new(memory_kib, iterations, parallelism, None)
+				.map_err(|e| format!("Argon2: {e}"))?;
+			Argon2::new(algorithm, Version::V0x13, params).hash_password(password, &salt)
+		}
+		PasswordHashSettings::Pbkdf2 { hash, iterations } => {
+			check_iterations(iterations)?;
+			let (algorithm, output_length) = match hash {
+				Pbkdf2Hash::Sha256 => (pbkdf2::Algorithm::Pbkdf2Sha256, 32),
+				Pbkdf2Hash::Sha512 => (pbkdf2::Algorithm::Pbkdf2Sha512, 64),
+			};
+			let params = pbkdf2::Params {
+				rounds: iterations,
+				output_length,
+			};
+			Pbkdf2.hash_password_customized(password, Some(algorithm.ident()), None, params, &salt)
+		}
+	};
+	hash.map(|hash| hash.to_string()).map_err(phc_error)
+}
+
+#[tauri::command(async)]
+pub fn password_verify(request: PasswordVerifyRequest) -> Result<PasswordVerifyResponse, String> {
+	let info = describe(&request.hash)?;
+	let hash = request.hash.trim();
+	let password = request.password.as_bytes();
+	let matches = if info.algorithm == "bcrypt" {
+		bcrypt::verify(password, hash).map_err(|e| format!("bcrypt: {e}"))?
+	} else {
+		let parsed = PasswordHash::new(hash).map_err(phc_error)?;
+		check_phc_cost(&parsed)?;
+		match parsed.verify_password(&[&Argon2::default(), &Scrypt, &Pbkdf2], password) {
+			Ok(()) => true,
+			Err(password_hash::Error::Password) => false,
+			Err(e) => return Err(phc_error(e)),
+		}
+	};
+	Ok(PasswordVerifyResponse { matches, info })
+}
+
+#[tauri::command]
+pub fn password_hash_info(hash: String) -> Result<PasswordHashInfo, String> {
+	describe(&hash)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn verify(password: &str, hash: &str) -> PasswordVerifyResponse {
+		password_verify(PasswordVerifyRequest {
+			password: password.to_string().into(),
+			hash: hash.to_string(),
+		})
+		.unwrap()
+	}
+
+	fn hash(settings: PasswordHashSettings, salt: Option<&str>) -> String {
+		password_hash(PasswordHashRequest {
+			password: "hunter2".to_string().into(),
+			settings,
+			salt: salt.map(str::to_string),
+		})
+		.unwrap()
+	}
+
+	#[test]
+	fn verifies_reference_hashes() {
+		// OpenBSD bcrypt test vector; scrypt and PBKDF2 from Python's hashlib.
+		let bcrypt = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+		assert!(verify("U*U", bcrypt).matches);
+		assert!(!verify("U*V", bcrypt).matches);
+
+		let scrypt = "$scrypt$ln=4,r=8,p=1$c2FsdHNhbHRzYWx0c2FsdA$7rY1ZUtQrNs0gR2ZzLDipSKkJ6K4ghvSvyxCeIMYcao";
+		assert!(verify("hunter2", scrypt).matches);
+		assert!(!verify("hunter3", scrypt).matches);
+
+		let pbkdf2 = "$pbkdf2-sha256$i=1000,l=32$c2FsdHNhbHRzYWx0c2FsdA$RilxBxnvGa3JIyaXwlUUKmvuPzxjHerJeqIuhiIvKNU";
+		assert!(verify("hunter2", pbkdf2).matches);
+	}
+
+	#[test]
+	fn hashes_round_trip() {
+		let salt = Some("73616c7473616c7473616c7473616c74");
+		let bcrypt = hash(PasswordHashSettings::Bcrypt { cost: 4 }, salt);
+		assert!(bcrypt.starts_with("$2b$04$a0Dqb"), "{bcrypt}");
+
+		let argon2 = hash(
+			PasswordHashSettings::Argon2 {
+				variant: Argon2Variant::Argon2id,
+				memory_kib: 64,
+				iterations: 1,
+				parallelism: 1,
+			},
+			None,
+		);
+		assert!(
+			argon2.starts_with("$argon2id$v=19$m=64,t=1,p=1$"),
+			"{argon2}"
+		);
+
+		let scrypt = hash(
+			PasswordHashSettings::Scrypt {
+				log_n: 4,
+				r: 8,
+				p: 1,
+			},
+			salt,
+		);
+		let pbkdf2 = hash(
+			PasswordHashSettings::Pbkdf2 {
+				hash: Pbkdf2Hash::Sha512,
+				iterations: 1000,
+			},
+			None,
+		);
+		for stored in [&bcrypt, &argon2, &scrypt, &pbkdf2] {
+			assert!(verify("hunter2", stored).matches, "{stored}");
+			assert!(!verify("hunter3", stored).matches, "{stored}");
+		}
+	}
+
+	#[test]
+	fn describes_hash_parameters() {
+		let info =
+			describe("$2y$10$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW").unwrap();
+		assert_eq!(info.algorithm, "bcrypt");
+		assert_eq!(info.version.as_deref(), Some("2y"));
+		assert_eq!(info.params[0].value, "10");
+		assert_eq!(info.salt.len(), 32);
+		assert_eq!(info.hash.len(), 46);
+
+		let info = describe(
+			"$scrypt$ln=4,r=8,p=1$c2FsdHNhbHRzYWx0c2FsdA$7rY1ZUtQrNs0gR2ZzLDipSKkJ6K4ghvSvyxCeIMYcao",
+		)
+		.unwrap();
+		assert_eq!(info.algorithm, "scrypt");
+		let params: Vec<_> = info
+			.params
+			.iter()
+			.map(|p| format!("{}={}", p.name, p.value))
+			.collect();
+		assert_eq!(params, ["ln=4", "r=8", "p=1"]);
+		assert_eq!(info.salt, HEXLOWER.encode(b"saltsaltsaltsalt"));
+
+		assert!(describe("5f4dcc3b5aa765d61d8327deb882cf99").is_err());
+	}
+
+	#[test]
+	fn rejects_non_ascii_bcrypt_without_panicking() {
+		// 53 bytes, but the salt/digest boundary falls inside the `é`.
+		let hash = format!("$2b$10${}é{}", "x".repeat(21), "y".repeat(30));
+		assert!(password_hash_info(hash.clone()).is_err());
+		assert!(password_verify(PasswordVerifyRequest {
+			password: "hunter2".to_string().into(),
+			hash,
+		})
+		.is_err());
+	}
+
+	#[test]
+	fn rejects_costs_over_the_limits() {
+		let salt = "c2FsdHNhbHQ";
+		let digest = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+		for hash in [
+			format!("$argon2id$v=19$m=4194304,t=1,p=1${salt}${digest}"),
+			format!("$argon2id$v=19$m=65536,t=4000,p=1${salt}${digest}"),
+			format!("$scrypt$ln=40,r=8,p=1${salt}${digest}"),
+			format!("$pbkdf2-sha256$i=4000000000${salt}${digest}"),
+		] {
+			assert!(password_verify(PasswordVerifyRequest {
+				password: "hunter2".to_string().into(),
+				hash,
+			})
+			.unwrap_err()
+			.contains("limit"));
+		}
+		let settings = PasswordHashSettings::Scrypt {
+			log_n: 40,
+			r: 8,
+			p: 1,
+		};
+		assert!(password_hash(PasswordHashRequest {
+			password: "hunter2".to_string().into(),
+			settings,
+			salt: None,
+		})
+		.is_err());
+	}
+}
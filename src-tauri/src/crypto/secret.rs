@@ -0,0 +1,16 @@
+//! Containers for key material that wipe their contents on drop: keys, IVs,
+//! passwords and decrypted plaintext never outlive the command that used them.
+//! Responses carrying generated or private keys use them too, so the backend's
+//! copy is wiped once serialised.
+
+pub use zeroize::Zeroizing;
+
+/// Key, IV, password-derived or decrypted bytes.
+pub type SecretBytes = Zeroizing<Vec<u8>>;
+
+/// Fixed-size key or IV.
+pub type SecretArray<const N: usize> = Zeroizing<[u8; N]>;
+
+/// Key, IV or password text as received from the frontend, or a private key
+/// returned to it.
+pub type SecretString = Zeroizing<String>;
@@ -0,0 +1,851 @@
+//! Known-answer tests run inside the shipped binary, so a build can be checked
+//! against published vectors without a Rust toolchain.
+
+use data_encoding::HEXLOWER;
+use serde::Serialize;
+
+use super::block::{
+	block_decrypt, block_encrypt, BlockAlgorithm, BlockMode, BlockRequest, Padding,
+};
+use super::encoding::{DataFormat, KeyEncoding};
+use super::keywrap::{key_unwrap, key_wrap, KeyWrapRequest, KeyWrapVariant};
+use super::sm2::{
+	sm2_decrypt, sm2_verify, Sm2CipherMode, Sm2DecryptRequest, Sm2Encoding, Sm2VerifyRequest,
+};
+use super::sm3::{hmac_sm3, sm3_hash, HmacSm3Request, Sm3InputType, Sm3Request};
+use super::sm4::{sm4_decrypt_bytes, sm4_encrypt_bytes, sm4_mac, Sm4MacAlgorithm, Sm4MacRequest};
+use super::sm9::{
+	sm9_decrypt, sm9_extract_user_key, sm9_generate_master_key, sm9_verify, Sm9Cipher,
+	Sm9DecryptRequest, Sm9KeyUsage, Sm9MasterKeyRequest, Sm9UserKeyRequest, Sm9VerifyRequest,
+};
+use crate::command::trivium_xor;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestResult {
+	pub algorithm: String,
+	pub passed: bool,
+	/// Number of vectors run; each checks both directions where there are two.
+	pub vectors: usize,
+	/// One message per failed vector.
+	pub failures: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+	pub passed: bool,
+	pub results: Vec<SelfTestResult>,
+}
+
+struct BlockVector {
+	source: &'static str,
+	algorithm: BlockAlgorithm,
+	mode: BlockMode,
+	key: &'static str,
+	iv: Option<&'static str>,
+	aad: Option<&'static str>,
+	plaintext: &'static str,
+	ciphertext: &'static str,
+}
+
+const fn ecb(
+	source: &'static str,
+	algorithm: BlockAlgorithm,
+	key: &'static str,
+	plaintext: &'static str,
+	ciphertext: &'static str,
+) -> BlockVector {
+	BlockVector {
+		source,
+		algorithm,
+		mode: BlockMode::Ecb,
+		key,
+		iv: None,
+		aad: None,
+		plaintext,
+		ciphertext,
+	}
+}
+
+const SP800_38A_KEY: &str = "2b7e151628aed2a6abf7158809cf4f3c";
+const SP800_38A_IV: &str = "000102030405060708090a0b0c0d0e0f";
+const SP800_38A_BLOCK: &str = "6bc1bee22e409f96e93d7e117393172a";
+
+const BLOCK_VECTORS: &[BlockVector] = &[
+	ecb(
+		"GB/T 32907 A.1",
+		BlockAlgorithm::Sm4,
+		"0123456789abcdeffedcba9876543210",
+		"0123456789abcdeffedcba9876543210",
+		"681edf34d206965e86b3e94f536e4246",
+	),
+	ecb(
+		"SP 800-38A F.1.1",
+		BlockAlgorithm::Aes128,
+		SP800_38A_KEY,
+		SP800_38A_BLOCK,
+		"3ad77bb40d7a3660a89ecaf32466ef97",
+	),
+	BlockVector {
+		source: "SP 800-38A F.2.1",
+		mode: BlockMode::Cbc,
+		iv: Some(SP800_38A_IV),
+		ciphertext: "7649abac8119b246cee98e9b12e9197d",
+		..ecb(
+			"",
+			BlockAlgorithm::Aes128,
+			SP800_38A_KEY,
+			SP800_38A_BLOCK,
+			"",
+		)
+	},
+	BlockVector {
+		source: "SP 800-38A F.3.13",
+		mode: BlockMode::Cfb,
+		iv: Some(SP800_38A_IV),
+		ciphertext: "3b3fd92eb72dad20333449f8e83cfb4a",
+		..ecb(
+			"",
+			BlockAlgorithm::Aes128,
+			SP800_38A_KEY,
+			SP800_38A_BLOCK,
+			"",
+		)
+	},
+	BlockVector {
+		source: "SP 800-38A F.4.1",
+		mode: BlockMode::Ofb,
+		iv: Some(SP800_38A_IV),
+		ciphertext: "3b3fd92eb72dad20333449f8e83cfb4a",
+		..ecb(
+			"",
+			BlockAlgorithm::Aes128,
+			SP800_38A_KEY,
+			SP800_38A_BLOCK,
+			"",
+		)
+	},
+	BlockVector {
+		source: "SP 800-38A F.5.1",
+		mode: BlockMode::Ctr,
+		iv: Some("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff"),
+		ciphertext: "874d6191b620e3261bef6864990db6ce",
+		..ecb(
+			"",
+			BlockAlgorithm::Aes128,
+			SP800_38A_KEY,
+			SP800_38A_BLOCK,
+			"",
+		)
+	},
+	BlockVector {
+		source: "GCM spec test case 2",
+		mode: BlockMode::Gcm,
+		iv: Some("000000000000000000000000"),
+		ciphertext: "0388dace60b6a392f328c2b971b2fe78ab6e47d42cec13bdf53a67b21257bddf",
+		..ecb(
+			"",
+			BlockAlgorithm::Aes128,
+			"00000000000000000000000000000000",
+			"00000000000000000000000000000000",
+			"",
+		)
+	},
+	BlockVector {
+		source: "SP 800-38C C.3 inputs, 128-bit tag",
+		mode: BlockMode::Ccm,
+		iv: Some("101112131415161718191a1b"),
+		aad: Some("000102030405060708090a0b0c0d0e0f"),
+		ciphertext: "e3b201a9f5b71a7a9b1ceaeccd97e70b44655ab3e6ff4732f67dac7d978ad063",
+		..ecb(
+			"",
+			BlockAlgorithm::Aes128,
+			"404142434445464748494a4b4c4d4e4f",
+			"202122232425262728292a2b2c2d2e2f",
+			"",
+		)
+	},
+	BlockVector {
+		source: "IEEE 1619 vector 15",
+		mode: BlockMode::Xts,
+		iv: Some("9a785634120000000000000000000000"),
+		ciphertext: "6c1625db4671522d3d7599601de7ca09ed",
+		..ecb(
+			"",
+			BlockAlgorithm::Aes128,
+			"fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+			"000102030405060708090a0b0c0d0e0f10",
+			"",
+		)
+	},
+	BlockVector {
+		source: "RFC 5297 A.1",
+		mode: BlockMode::Siv,
+		aad: Some("101112131415161718191a1b1c1d1e1f2021222324252627"),
+		ciphertext: "85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c",
+		..ecb(
+			"",
+			BlockAlgorithm::Aes128,
+			"fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+			"112233445566778899aabbccddee",
+			"",
+		)
+	},
+	ecb(
+		"SP 800-38A F.1.3",
+		BlockAlgorithm::Aes192,
+		"8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b",
+		SP800_38A_BLOCK,
+		"bd334f1d6e45f25ff712a214571fa5cc",
+	),
+	ecb(
+		"SP 800-38A F.1.5",
+		BlockAlgorithm::Aes256,
+		"603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4",
+		SP800_38A_BLOCK,
+		"f3eed1bdb5d2a03c064b5a7e3db181f8",
+	),
+	ecb(
+		"FIPS 46 worked example",
+		BlockAlgorithm::Des,
+		"133457799bbcdff1",
+		"0123456789abcdef",
+		"85e813540f0ab405",
+	),
+	// With K1 = K2 (= K3) both 3DES keying options reduce to single DES.
+	ecb(
+		"FIPS 46 worked example, K1 = K2",
+		BlockAlgorithm::Tdes2,
+		"133457799bbcdff1133457799bbcdff1",
+		"0123456789abcdef",
+		"85e813540f0ab405",
+	),
+	ecb(
+		"FIPS 46 worked example, K1 = K2 = K3",
+		BlockAlgorithm::Tdes3,
+		"133457799bbcdff1133457799bbcdff1133457799bbcdff1",
+		"0123456789abcdef",
+		"85e813540f0ab405",
+	),
+	ecb(
+		"Schneier test vector 1",
+		BlockAlgorithm::Blowfish,
+		"0000000000000000",
+		"0000000000000000",
+		"4ef997456198dd78",
+	),
+	ecb(
+		"RFC 2268 section 5",
+		BlockAlgorithm::Rc2,
+		"3000000000000000",
+		"1000000000000001",
+		"30649edf9be7d2c2",
+	),
+	ecb(
+		"IDEA reference vector",
+		BlockAlgorithm::Idea,
+		"00010002000300040005000600070008",
+		"0000000100020003",
+		"11fbed2b01986de5",
+	),
+	ecb(
+		"RFC 2144 B.1",
+		BlockAlgorithm::Cast5,
+		"0123456712345678234567893456789a",
+		"0123456789abcdef",
+		"238b4fe5847e44b2",
+	),
+	ecb(
+		"RFC 5794 A.1",
+		BlockAlgorithm::Aria128,
+		"000102030405060708090a0b0c0d0e0f",
+		"00112233445566778899aabbccddeeff",
+		"d718fbd6ab644c739da95f3be6451778",
+	),
+	ecb(
+		"RFC 5794 A.2",
+		BlockAlgorithm::Aria192,
+		"000102030405060708090a0b0c0d0e0f1011121314151617",
+		"00112233445566778899aabbccddeeff",
+		"26449c1805dbe7aa25a468ce263a9e79",
+	),
+	ecb(
+		"RFC 5794 A.3",
+		BlockAlgorithm::Aria256,
+		"000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+		"00112233445566778899aabbccddeeff",
+		"f92bd7c79fb72e2f2b8f80c1972d24fc",
+	),
+	ecb(
+		"RFC 4269 B.1",
+		BlockAlgorithm::Seed,
+		"00000000000000000000000000000000",
+		"000102030405060708090a0b0c0d0e0f",
+		"5ebac6e0054e166819aff1cc6d346cdb",
+	),
+	ecb(
+		"RFC 3713 A, 128-bit key",
+		BlockAlgorithm::Camellia128,
+		"0123456789abcdeffedcba9876543210",
+		"0123456789abcdeffedcba9876543210",
+		"67673138549669730857065648eabe43",
+	),
+	ecb(
+		"RFC 3713 A, 192-bit key",
+		BlockAlgorithm::Camellia192,
+		"0123456789abcdeffedcba98765432100011223344556677",
+		"0123456789abcdeffedcba9876543210",
+		"b4993401b3e996f84ee5cee7d79b09b9",
+	),
+	ecb(
+		"RFC 3713 A, 256-bit key",
+		BlockAlgorithm::Camellia256,
+		"0123456789abcdeffedcba987654321000112233445566778899aabbccddeeff",
+		"0123456789abcdeffedcba9876543210",
+		"9acc237dff16d76c20ef7c919e3a7509",
+	),
+	ecb(
+		"GOST R 34.12-2015 A.1",
+		BlockAlgorithm::Kuznyechik,
+		"8899aabbccddeeff0011223344556677fedcba98765432100123456789abcdef",
+		"1122334455667700ffeeddccbbaa9988",
+		"7f679d90bebc24305a468d42b9d4edcd",
+	),
+	ecb(
+		"GOST R 34.12-2015 A.2",
+		BlockAlgorithm::Magma,
+		"ffeeddccbbaa99887766554433221100f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+		"fedcba9876543210",
+		"4ee901e5c2d8ca3d",
+	),
+];
+
+/// Text key, IV and plaintext of the SM4 tool's reference vectors, PKCS#7 padded.
+const SM4_TEXT_KEY: &[u8; 16] = b"1234567890123456";
+const SM4_TEXT_PLAINTEXT: &str = "0123456789abcdeffedcba9876543210";
+const SM4_MODE_VECTORS: &[(BlockMode, &str)] = &[
+	(
+		BlockMode::Ecb,
+		"ba611296fd1f3f27299eff1c29b18d63de48b88f34351e6d653b381ce1355ca66c88f739af2a29a735381f5677badef7",
+	),
+	(
+		BlockMode::Cbc,
+		"50aaf1ea1040e2b564a39f88d79f140119d813b078261d344cb6eff909384015265d7cc8adfe8d99477442fb5912539d",
+	),
+	(
+		BlockMode::Cfb,
+		"80b2eef39dc37deec2db9beef00a58111ca93628ee96ad2c29c91379c729c05f649c4bd0852ddde13384464c912e6c22",
+	),
+	(
+		BlockMode::Ofb,
+		"80b2eef39dc37deec2db9beef00a5811052c7433380b380f300571626d4498fecad5947e958e9f1823260b144963213a",
+	),
+	(
+		BlockMode::Ctr,
+		"80b2eef39dc37deec2db9beef00a581128692ce012c17cfd95986ded8b1c824fa629037b38944cc69e23312e2f94dbf6",
+	),
+];
+
+/// (source, algorithm, variant, KEK, key data, wrapped), all hex.
+const KEY_WRAP_VECTORS: &[(&str, BlockAlgorithm, KeyWrapVariant, &str, &str, &str)] = &[
+	(
+		"RFC 3394 4.1",
+		BlockAlgorithm::Aes128,
+		KeyWrapVariant::Kw,
+		"000102030405060708090a0b0c0d0e0f",
+		"00112233445566778899aabbccddeeff",
+		"1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5",
+	),
+	(
+		"RFC 5649 6",
+		BlockAlgorithm::Aes192,
+		KeyWrapVariant::Kwp,
+		"5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8",
+		"c37b7e6492584340bed12207808941155068f738",
+		"138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a",
+	),
+];
+
+/// (source, message, digest).
+const SM3_VECTORS: &[(&str, &str, &str)] = &[
+	(
+		"GB/T 32905 A.1",
+		"abc",
+		"66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0",
+	),
+	(
+		"GB/T 32905 A.2",
+		"abcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcd",
+		"debe9ff92275b8a138604889c18e5a4d6fdb70e5387e5765293dcba39c0c5732",
+	),
+];
+
+/// (source, text key, message, MAC).
+const HMAC_SM3_VECTORS: &[(&str, &str, &str, &str)] = &[(
+	"HMAC-SM3 reference vector",
+	"key",
+	"abc",
+	"28e63256e7c5a087b1f073265dc53092163f7b82729735d06f28f10af9d52393",
+)];
+
+/// (source, algorithm, padding, hex key, hex IV, MAC) over the SM4 tool's
+/// text plaintext.
+type Sm4MacVector = (
+	&'static str,
+	Sm4MacAlgorithm,
+	Padding,
+	&'static str,
+	Option<&'static str>,
+	&'static str,
+);
+
+const SM4_MAC_VECTORS: &[Sm4MacVector] = &[
+	(
+		"last block of the CBC reference vector",
+		Sm4MacAlgorithm::CbcMac,
+		Padding::Pkcs7,
+		"31323334353637383930313233343536",
+		Some("31323334353637383930313233343536"),
+		"265d7cc8adfe8d99477442fb5912539d",
+	),
+	(
+		"ISO/IEC 9797-1 padding method 2 reference vector",
+		Sm4MacAlgorithm::CbcMac,
+		Padding::Iso7816,
+		"31323334353637383930313233343536",
+		None,
+		"68724db8dcfc5ba9a56d01e03e1ba1b3",
+	),
+	(
+		"CMAC reference vector",
+		Sm4MacAlgorithm::Cmac,
+		Padding::None,
+		"31323334353637383930313233343536",
+		None,
+		"5b3bfb90d37fb0acd4edfca6ac78c63d",
+	),
+	(
+		"retail MAC reference vector",
+		Sm4MacAlgorithm::Retail,
+		Padding::Iso7816,
+		"313233343536373839303132333435360123456789abcdeffedcba9876543210",
+		None,
+		"e6e41cfcd08d7df42d7983e9ea47232e",
+	),
+];
+
+/// OpenSSL `pkeyutl -sign -rawin -digest sm3` over "message digest" with the
+/// default user ID.
+const SM2_SIGN_PUBLIC_KEY: &str = "047bf6ce8584088ce8dc036aa44eedda14b6d6be2cc558b31915f81f7a9d4d7323e1f4210c2efbd59bb0e8da4f65be32d22085cd3ebbafe490f5d52239af55c870";
+const SM2_SIGNATURE: &str = "3046022100acbaa10f58d706b65dc5dc5f03876780a91da89538d10a5deb8c137d80d2dde8022100d14bba58e1e15a22b838c9d3bbae22cb0871f1cc242c617db295d957045a8024";
+/// RustCrypto `sm2` encryption key; the DER ciphertext is OpenSSL `pkeyutl -encrypt`.
+const SM2_PRIVATE_KEY: &str = "3ddd2a3679bf6f1dfc3b49d3e99114718e48ec170eb4e4d3a82052dab19e8b50";
+const SM2_CIPHERTEXTS: &[(&str, Sm2Encoding, &str)] = &[
+	(
+		"RustCrypto sm2 C1C3C2 vector",
+		Sm2Encoding::Raw,
+		"041ed68db303f5bc6bce516d5a62e1cd16781d3007df6864d970a56d46a6cecca0e0d33bfc71e78c440ae6afeef1a18cce473b3e27002189a058ddadc9182c80a3f13be66476ba6ef66d95a7fb11f30de441b3b66d566e48348bd830e584e7ec37f9b704ef32eba9055c",
+	),
+	(
+		"OpenSSL DER ciphertext",
+		Sm2Encoding::Der,
+		"307202206ba17ad462a75beeb2caf8a1282687ab7e2f248b776a481612d89425a519ce6002210083e1de8c57dae995137227839d3880eaf9fe82a885a750be29ebe58193c8e31a0420d513a555087c2b17a88dd62749435133d325a4afca675284c85d754ba35670f80409bd3a294a6d50184b37",
+	),
+];
+
+/// GM/T 0044.5 annex A: signature master key, Alice's key and signature.
+const SM9_KS: &str = "0130e78459d78545cb54c587e02cf480ce0b66340f319f348a1d5b1f2dc5f4";
+const SM9_PPUB_S: &str = "049f64080b3084f733e48aff4b41b565011ce0711c5e392cfb0ab1b6791b94c40829dba116152d1f786ce843ed24a3b573414d2177386a92dd8f14d65696ea5e3269850938abea0112b57329f447e3a0cbad3e2fdb1a77f335e89e1408d0ef1c2541e00a53dda532da1a7ce027b7a46f741006e85f5cdff0730e75c05fb4e3216d";
+const SM9_DS_ALICE: &str = "04a5702f05cf1315305e2d6eb64b0deb923db1a0bcf0caff90523ac8754aa6982078559a844411f9825c109f5ee3f52d720dd01785392a727bb1556952b2b013d3";
+const SM9_SIGNATURE: &str = "823c4b21e4bd2dfe1ed92c606653e996668563152fc33f55d7bfbb9bd9705adb0473bf96923ce58b6ad0e13e9643a406d8eb98417c50ef1b29cef9adb48b6d598c856712f1c2e0968ab7769f42a99586aed139d5b8b3e15891827cc2aced9baa05";
+/// GM/T 0044.5 annex D: encryption master key, Bob's key and ciphertext.
+const SM9_KE: &str = "01edee3778f441f8dea3d9fa0acc4e07ee36c93f9a08618af4ad85cede1c22";
+const SM9_PPUB_E: &str = "04787ed7b8a51f3ab84e0a66003f32da5c720b17eca7137d39abc66e3c80a892ff769de61791e5adc4b9ff85a31354900b202871279a8c49dc3f220f644c57a7b1";
+const SM9_DE_BOB: &str = "0494736acd2c8c8796cc4785e938301a139a059d3537b6414140b2d31eecf41683115bae85f5d8bc6c3dbd9e5342979acccf3c2f4f28420b1cb4f8c0b59a19b1587aa5e47570da7600cd760a0cf7beaf71c447f3844753fe74fa7ba92ca7d3b55f27538a62e7f7bfb51dce08704796d94c9d56734f119ea44732b50e31cdeb75c1";
+const SM9_CIPHERTEXT: &str = "2445471164490618e1ee20528ff1d545b0f14c8bcaa44544f03dab5dac07d8ff42ffca97d57cddc05ea405f2e586feb3a6930715532b8000759f13059ed59ac0ba672387bcd6de5016a158a52bb2e7fc429197bcab70b25afee37a2b9db9f3671b5f5b0e951489682f3e64e1378cdd5da9513b1c";
+
+/// eSTREAM set 2, vector 0: all-zero key and IV, first 32 keystream bytes.
+const TRIVIUM_KEYSTREAM: &str = "fbe0bf265859051b517a2e4e239fc97f563203161907cf2de7a8790fa1b2e9cd";
+
+fn expect(
+	source: &str,
+	step: &str,
+	got: Result<String, String>,
+	expected: &str,
+) -> Result<(), String> {
+	match got {
+		Ok(got) if got.eq_ignore_ascii_case(expected) => Ok(()),
+		Ok(got) => Err(format!("{source}: {step} gave {got}, expected {expected}")),
+		Err(e) => Err(format!("{source}: {step} failed: {e}")),
+	}
+}
+
+fn run_block_vector(vector: &BlockVector) -> Result<(), String> {
+	let request = |input: &str| BlockRequest {
+		input: input.to_string(),
+		algorithm: vector.algorithm,
+		mode: vector.mode,
+		padding: Padding::None,
+		format: DataFormat::Hex,
+		key: vector.key.to_string().into(),
+		key_type: KeyEncoding::Hex,
+		iv: vector.iv.map(|iv| iv.to_string().into()),
+		iv_type: Some(KeyEncoding::Hex),
+		plain_format: Some(DataFormat::Hex),
+		aad: vector.aad.map(str::to_string),
+		aad_format: Some(DataFormat::Hex),
+		password: None,
+	};
+	let label = format!("{} {}", vector.mode, vector.source);
+	expect(
+		&label,
+		"encrypt",
+		block_encrypt(request(vector.plaintext)),
+		vector.ciphertext,
+	)?;
+	expect(
+		&label,
+		"decrypt",
+		block_decrypt(request(vector.ciphertext)),
+		vector.plaintext,
+	)
+}
+
+fn run_sm4_mode_vector(mode: BlockMode, expected: &str) -> Result<(), String> {
+	let iv = (mode != BlockMode::Ecb).then_some(SM4_TEXT_KEY);
+	let label = format!("{mode} reference vector");
+	let ciphertext = sm4_encrypt_bytes(
+		SM4_TEXT_PLAINTEXT.as_bytes(),
+		SM4_TEXT_KEY,
+		iv,
+		mode,
+		Padding::Pkcs7,
+	)
+	.map(|c| HEXLOWER.encode(&c));
+	expect(&label, "encrypt", ciphertext, expected)?;
+
+	let ciphertext = HEXLOWER
+		.decode(expected.as_bytes())
+		.map_err(|e| e.to_string())?;
+	let plaintext = sm4_decrypt_bytes(&ciphertext, SM4_TEXT_KEY, iv, mode, Padding::Pkcs7)
+		.map(|p| String::from_utf8_lossy(&p).into_owned());
+	expect(&label, "decrypt", plaintext, SM4_TEXT_PLAINTEXT)
+}
+
+fn run_key_wrap_vector(
+	(source, algorithm, variant, kek, key, wrapped): (
+		&str,
+		BlockAlgorithm,
+		KeyWrapVariant,
+		&str,
+		&str,
+		&str,
+	),
+) -> Result<(), String> {
+	let request = |input: &str| KeyWrapRequest {
+		input: input.to_string().into(),
+		format: DataFormat::Hex,
+		algorithm,
+		variant,
+		key: kek.to_string().into(),
+		key_type: KeyEncoding::Hex,
+		iv: None,
+	};
+	expect(source, "wrap", key_wrap(request(key)), wrapped)?;
+	expect(source, "unwrap", key_unwrap(request(wrapped)), key)
+}
+
+fn run_trivium_vector() -> Result<(), String> {
+	let keystream = trivium_xor(
+		vec![0; 10].into(),
+		vec![0; 10].into(),
+		vec![0; 32].into(),
+		None,
+	)
+	.map(|k| HEXLOWER.encode(&k));
+	expect(
+		"eSTREAM set 2 vector 0",
+		"keystream",
+		keystream,
+		TRIVIUM_KEYSTREAM,
+	)
+}
+
+fn run_sm3_vector((source, message, digest): (&str, &str, &str)) -> Result<(), String> {
+	let request = Sm3Request {
+		input: message.to_string(),
+		input_type: Sm3InputType::Text,
+	};
+	expect(source, "hash", sm3_hash(request), digest)
+}
+
+fn run_hmac_sm3_vector(
+	(source, key, message, mac): (&str, &str, &str, &str),
+) -> Result<(), String> {
+	let request = HmacSm3Request {
+		input: message.to_string(),
+		input_type: Sm3InputType::Text,
+		key: key.to_string().into(),
+		key_type: KeyEncoding::Text,
+	};
+	expect(source, "MAC", hmac_sm3(request), mac)
+}
+
+fn run_sm4_mac_vector(
+	(source, algorithm, padding, key, iv, mac): Sm4MacVector,
+) -> Result<(), String> {
+	let request = Sm4MacRequest {
+		input: SM4_TEXT_PLAINTEXT.to_string(),
+		input_format: None,
+		algorithm,
+		padding,
+		format: DataFormat::Hex,
+		key: key.to_string().into(),
+		key_type: KeyEncoding::Hex,
+		iv: iv.map(|iv| iv.to_string().into()),
+		iv_type: Some(KeyEncoding::Hex),
+		mac_length: None,
+	};
+	expect(source, "MAC", sm4_mac(request), mac)
+}
+
+/// Deterministic SM2 operations: verification (accept and reject) and decryption.
+fn run_sm2_vectors(results: &mut Vec<SelfTestResult>) {
+	let verify = |message: &str| {
+		sm2_verify(Sm2VerifyRequest {
+			input: message.to_string(),
+			input_format: None,
+			public_key: SM2_SIGN_PUBLIC_KEY.to_string(),
+			user_id: None,
+			signature: SM2_SIGNATURE.to_string(),
+			encoding: None,
+		})
+		.map(|valid| valid.to_string())
+	};
+	let source = "OpenSSL SM2 signature";
+	let outcome = expect(source, "verify", verify("message digest"), "true")
+		.and_then(|()| expect(source, "reject", verify("message digesT"), "false"));
+	record(results, "SM2".to_string(), outcome);
+
+	for &(source, encoding, ciphertext) in SM2_CIPHERTEXTS {
+		let plaintext = sm2_decrypt(Sm2DecryptRequest {
+			input: ciphertext.to_string(),
+			format: DataFormat::Hex,
+			private_key: SM2_PRIVATE_KEY.to_string().into(),
+			mode: Sm2CipherMode::C1C3C2,
+			encoding,
+			plain_format: None,
+		});
+		record(
+			results,
+			"SM2".to_string(),
+			expect(source, "decrypt", plaintext, "plaintext"),
+		);
+	}
+}
+
+/// Deterministic SM9 operations: master and user key derivation, verification
+/// and decryption.
+fn run_sm9_vectors(results: &mut Vec<SelfTestResult>) {
+	let keys = |usage, master: &str, user_id: &str, ppub: &str, user_key: &str| {
+		let source = match usage {
+			Sm9KeyUsage::Sign => "GM/T 0044.5 A",
+			Sm9KeyUsage::Encrypt => "GM/T 0044.5 D",
+		};
+		let master_public_key = sm9_generate_master_key(Sm9MasterKeyRequest {
+			usage,
+			master_private_key: Some(master.to_string().into()),
+		})
+		.map(|pair| pair.master_public_key);
+		expect(source, "master public key", master_public_key, ppub)?;
+		let extracted = sm9_extract_user_key(Sm9UserKeyRequest {
+			usage,
+			master_private_key: master.to_string().into(),
+			user_id: user_id.to_string(),
+			hid: None,
+		})
+		.map(|key| key.user_private_key.to_string());
+		expect(source, "user key", extracted, user_key)
+	};
+
+	let outcome =
+		keys(Sm9KeyUsage::Sign, SM9_KS, "Alice", SM9_PPUB_S, SM9_DS_ALICE).and_then(|()| {
+			let verified = sm9_verify(Sm9VerifyRequest {
+				input: "Chinese IBS standard".to_string(),
+				input_format: None,
+				master_public_key: SM9_PPUB_S.to_string(),
+				user_id: "Alice".to_string(),
+				hid: None,
+				signature: SM9_SIGNATURE.to_string(),
+			})
+			.map(|valid| valid.to_string());
+			expect("GM/T 0044.5 A", "verify", verified, "true")
+		});
+	record(results, "SM9".to_string(), outcome);
+
+	let outcome =
+		keys(Sm9KeyUsage::Encrypt, SM9_KE, "Bob", SM9_PPUB_E, SM9_DE_BOB).and_then(|()| {
+			let plaintext = sm9_decrypt(Sm9DecryptRequest {
+				input: SM9_CIPHERTEXT.to_string(),
+				format: DataFormat::Hex,
+				user_private_key: SM9_DE_BOB.to_string().into(),
+				user_id: "Bob".to_string(),
+				cipher: Sm9Cipher::Stream,
+				plain_format: None,
+			});
+			expect(
+				"GM/T 0044.5 D",
+				"decrypt",
+				plaintext,
+				"Chinese IBE standard",
+			)
+		});
+	record(results, "SM9".to_string(), outcome);
+}
+
+fn record(results: &mut Vec<SelfTestResult>, algorithm: String, outcome: Result<(), String>) {
+	let index = match results.iter().position(|r| r.algorithm == algorithm) {
+		Some(index) => index,
+		None => {
+			results.push(SelfTestResult {
+				algorithm,
+				passed: true,
+				vectors: 0,
+				failures: Vec::new(),
+			});
+			results.len() - 1
+		}
+	};
+	let result = &mut results[index];
+	result.vectors += 1;
+	if let Err(failure) = outcome {
+		result.passed = false;
+		result.failures.push(failure);
+	}
+}
+
+#[tauri::command]
+pub fn crypto_self_test() -> SelfTestReport {
+	let mut results = Vec::new();
+	for &(mode, expected) in SM4_MODE_VECTORS {
+		record(
+			&mut results,
+			BlockAlgorithm::Sm4.to_string(),
+			run_sm4_mode_vector(mode, expected),
+		);
+	}
+	for vector in BLOCK_VECTORS {
+		record(
+			&mut results,
+			vector.algorithm.to_string(),
+			run_block_vector(vector),
+		);
+	}
+	for &vector in KEY_WRAP_VECTORS {
+		let name = match vector.2 {
+			KeyWrapVariant::Kw => "Key wrap (RFC 3394)",
+			KeyWrapVariant::Kwp => "Key wrap with padding (RFC 5649)",
+		};
+		record(&mut results, name.to_string(), run_key_wrap_vector(vector));
+	}
+	record(&mut results, "Trivium".to_string(), run_trivium_vector());
+	for &vector in SM3_VECTORS {
+		record(&mut results, "SM3".to_string(), run_sm3_vector(vector));
+	}
+	for &vector in HMAC_SM3_VECTORS {
+		record(
+			&mut results,
+			"HMAC-SM3".to_string(),
+			run_hmac_sm3_vector(vector),
+		);
+	}
+	for &vector in SM4_MAC_VECTORS {
+		let name = match vector.1 {
+			Sm4MacAlgorithm::CbcMac => "SM4 CBC-MAC",
+			Sm4MacAlgorithm::Cmac => "SM4 CMAC",
+			Sm4MacAlgorithm::Retail => "SM4 retail MAC",
+		};
+		record(&mut results, name.to_string(), run_sm4_mac_vector(vector));
+	}
+	run_sm2_vectors(&mut results);
+	run_sm9_vectors(&mut results);
+
+	SelfTestReport {
+		passed: results.iter().all(|r| r.passed),
+		results,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn every_vector_passes() {
+		let report = crypto_self_test();
+		let failures: Vec<_> = report.results.iter().flat_map(|r| &r.failures).collect();
+		assert!(report.passed, "{failures:#?}");
+	}
+
+	#[test]
+	fn every_block_algorithm_is_covered() {
+		let report = crypto_self_test();
+		let all = [
+			BlockAlgorithm::Sm4,
+			BlockAlgorithm::Aes128,
+			BlockAlgorithm::Aes192,
+			BlockAlgorithm::Aes256,
+			BlockAlgorithm::Des,
+			BlockAlgorithm::Tdes2,
+			BlockAlgorithm::Tdes3,
+			BlockAlgorithm::Blowfish,
+			BlockAlgorithm::Rc2,
+			BlockAlgorithm::Idea,
+			BlockAlgorithm::Cast5,
+			BlockAlgorithm::Aria128,
+			BlockAlgorithm::Aria192,
+			BlockAlgorithm::Aria256,
+			BlockAlgorithm::Seed,
+			BlockAlgorithm::Camellia128,
+			BlockAlgorithm::Camellia192,
+			BlockAlgorithm::Camellia256,
+			BlockAlgorithm::Kuznyechik,
+			BlockAlgorithm::Magma,
+		];
+		for algorithm in all {
+			let name = algorithm.to_string();
+			assert!(
+				report.results.iter().any(|r| r.algorithm == name),
+				"no vector for {name}"
+			);
+		}
+		let sm4 = report
+			.results
+			.iter()
+			.find(|r| r.algorithm == "SM4")
+			.unwrap();
+		assert_eq!(sm4.vectors, SM4_MODE_VECTORS.len() + 1);
+	}
+
+	#[test]
+	fn every_national_standard_primitive_is_covered() {
+		let report = crypto_self_test();
+		let all = [
+			"SM3",
+			"HMAC-SM3",
+			"SM4 CBC-MAC",
+			"SM4 CMAC",
+			"SM4 retail MAC",
+			"SM2",
+			"SM9",
+			"Trivium",
+		];
+		for name in all {
+			assert!(
+				report.results.iter().any(|r| r.algorithm == name),
+				"no vector for {name}"
+			);
+		}
+	}
+}
@@ -0,0 +1,455 @@
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+use sm2::dsa::signature::{Signer, Verifier};
+use sm2::dsa::{Signature, SigningKey, VerifyingKey};
+use sm2::elliptic_curve::common::getrandom::SysRng;
+use sm2::elliptic_curve::sec1::ToSec1Point;
+use sm2::elliptic_curve::Generate;
+use sm2::pke::{DecryptingKey, EncryptingKey, Mode};
+use sm2::{NonZeroScalar, PublicKey, SecretKey};
+use sm3::{Digest, Sm3};
+
+use super::encoding::{decode_bytes, encode_bytes, require_binary_format, DataFormat};
+use super::secret::{SecretBytes, SecretString, Zeroizing};
+
+/// Default signer ID from GM/T 0009, used by most SM2 libraries.
+pub const DEFAULT_USER_ID: &str = "1234567812345678";
+
+/// Curve parameters a, b, xG, yG (GM/T 0003.5), hashed into Z.
+const CURVE_A: &str = "fffffffeffffffffffffffffffffffffffffffff00000000fffffffffffffffc";
+const CURVE_B: &str = "28e9fa9e9d9f5e344d5a9e4bcf6509a7f39789f515ab8f92ddbcbd414d940e93";
+const CURVE_GX: &str = "32c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7";
+const CURVE_GY: &str = "bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sm2CipherMode {
+	/// GM/T 0003-2012 layout, used by BouncyCastle and most current libraries.
+	C1C3C2,
+	/// Layout of the 2010 draft, still common in older systems.
+	C1C2C3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sm2Encoding {
+	/// Plain concatenation (r || s for signatures, C1 C3 C2 for ciphertexts).
+	Raw,
+	/// ASN.1 DER as specified by GM/T 0009.
+	Der,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2KeyPair {
+	pub private_key: SecretString,
+	/// Uncompressed point, 04 || x || y.
+	pub public_key: String,
+	pub public_key_compressed: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2SignRequest {
+	pub input: String,
+	/// Format of `input`; UTF-8 when absent.
+	pub input_format: Option<DataFormat>,
+	/// 32-byte private key as hex.
+	pub private_key: SecretString,
+	/// Defaults to `DEFAULT_USER_ID`.
+	pub user_id: Option<String>,
+	pub encoding: Sm2Encoding,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2SignResponse {
+	pub signature: String,
+	/// Z = SM3(ENTL || ID || a || b || xG || yG || xA || yA).
+	pub z: String,
+	/// e = SM3(Z || M), the value actually signed.
+	pub e: String,
+	pub public_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2VerifyRequest {
+	pub input: String,
+	pub input_format: Option<DataFormat>,
+	/// Hex SEC1 point; a 64-byte x || y without the 04 prefix is accepted too.
+	pub public_key: String,
+	pub user_id: Option<String>,
+	/// Hex signature, raw r || s or DER.
+	pub signature: String,
+	/// Detected from the signature when absent.
+	pub encoding: Option<Sm2Encoding>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2EncryptRequest {
+	pub input: String,
+	/// Format of `input`; UTF-8 when absent.
+	pub input_format: Option<DataFormat>,
+	pub public_key: String,
+	pub mode: Sm2CipherMode,
+	pub encoding: Sm2Encoding,
+	/// Output format of the ciphertext.
+	pub format: DataFormat,
+	/// Drop the leading 04 of C1 in raw output, as the `sm-crypto` JS package does.
+	#[serde(default)]
+	pub omit_point_prefix: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2DecryptRequest {
+	pub input: String,
+	/// Format of the ciphertext in `input`.
+	pub format: DataFormat,
+	pub private_key: SecretString,
+	pub mode: Sm2CipherMode,
+	pub encoding: Sm2Encoding,
+	/// Output format of the plaintext; UTF-8 (with hex fallback) when absent.
+	pub plain_format: Option<DataFormat>,
+}
+
+fn cipher_mode(mode: Sm2CipherMode) -> Mode {
+	match mode {
+		Sm2CipherMode::C1C3C2 => Mode::C1C3C2,
+		Sm2CipherMode::C1C2C3 => Mode::C1C2C3,
+	}
+}
+
+pub(super) fn parse_private_key(value: &str) -> Result<NonZeroScalar, String> {
+	let bytes: SecretBytes = Zeroizing::new(decode_bytes(value, DataFormat::Hex)?);
+	if bytes.is_empty() || bytes.len() > 32 {
+		return Err(format!(
+			"Private key must be 32 bytes (64 hex chars), got {} bytes",
+			bytes.len()
+		));
+	}
+	// Some tools drop leading zero bytes of the scalar.
+	let mut padded = Zeroizing::new([0u8; 32]);
+	padded[32 - bytes.len()..].copy_from_slice(&bytes);
+	NonZeroScalar::try_from(padded.as_slice())
+		.map_err(|_| "Private key is not a valid SM2 scalar".to_string())
+}
+
+fn parse_public_key(value: &str) -> Result<PublicKey, String> {
+	let mut bytes = decode_bytes(value, DataFormat::Hex)?;
+	if bytes.len() == 64 {
+		bytes.insert(0, 0x04);
+	}
+	PublicKey::from_sec1_bytes(&bytes)
+		.map_err(|_| "Public key is not a valid SM2 point".to_string())
+}
+
+pub(super) fn public_key_hex(public_key: &PublicKey, compress: bool) -> String {
+	HEXLOWER.encode(public_key.to_sec1_point(compress).as_bytes())
+}
+
+fn decode_hex_constant(value: &str) -> Vec<u8> {
+	decode_bytes(value, DataFormat::Hex).unwrap_or_default()
+}
+
+/// Signer identity hash Z, computed here as well so the UI can show it.
+pub(super) fn compute_z(user_id: &str, public_key: &PublicKey) -> Result<[u8; 32], String> {
+	let entl = u16::try_from(user_id.len() * 8).map_err(|_| "User ID is too long".to_string())?;
+	let point = public_key.to_sec1_point(false);
+	let mut hasher = Sm3::new();
+	hasher.update(entl.to_be_bytes());
+	hasher.update(user_id.as_bytes());
+	for constant in [CURVE_A, CURVE_B, CURVE_GX, CURVE_GY] {
+		hasher.update(decode_hex_constant(constant));
+	}
+	// Skip the 04 tag: x || y.
+	hasher.update(&point.as_bytes()[1..]);
+	Ok(hasher.finalize().into())
+}
+
+#[tauri::command]
+pub fn sm2_generate_keypair() -> Result<Sm2KeyPair, String> {
+	let secret_key = SecretKey::try_generate_from_rng(&mut SysRng)
+		.map_err(|e| format!("Random number generator failed: {e}"))?;
+	let public_key = secret_key.public_key();
+	Ok(Sm2KeyPair {
+		private_key: Zeroizing::new(HEXLOWER.encode(&secret_key.to_bytes())),
+		public_key: public_key_hex(&public_key, false),
+		public_key_compressed: public_key_hex(&public_key, true),
+	})
+}
+
+#[tauri::command]
+pub fn sm2_sign(request: Sm2SignRequest) -> Result<Sm2SignResponse, String> {
+	let user_id = request.user_id.as_deref().unwrap_or(DEFAULT_USER_ID);
+	let scalar = parse_private_key(&request.private_key)?;
+	let signing_key = SigningKey::from_nonzero_scalar(user_id, scalar)
+		.map_err(|e| format!("Invalid signing key: {e}"))?;
+	let public_key = PublicKey::from(signing_key.verifying_key());
+	let message = decode_bytes(
+		&request.input,
+		request.input_format.unwrap_or(DataFormat::Utf8),
+	)?;
+
+	let z = compute_z(user_id, &public_key)?;
+	let e = Sm3::new().chain_update(z).chain_update(&message).finalize();
+
+	let signature: Signature = signing_key
+		.try_sign(&message)
+		.map_err(|e| format!("Sign failed: {e}"))?;
+	let signature = match request.encoding {
+		Sm2Encoding::Raw => signature.to_vec(),
+		Sm2Encoding::Der => signature.to_der().to_vec(),
+	};
+
+	Ok(Sm2SignResponse {
+		signature: HEXLOWER.encode(&signature),
+		z: HEXLOWER.encode(&z),
+		e: HEXLOWER.encode(&e),
+		public_key: public_key_hex(&public_key, false),
+	})
+}
+
+#[tauri::command]
+pub fn sm2_verify(request: Sm2VerifyRequest) -> Result<bool, String> {
+	let user_id = request.user_id.as_deref().unwrap_or(DEFAULT_USER_ID);
+	let public_key = parse_public_key(&request.public_key)?;
+	let verifying_key = VerifyingKey::new(user_id, public_key)
+		.map_err(|e| format!("Invalid verifying key: {e}"))?;
+	let message = decode_bytes(
+		&request.input,
+		request.input_format.unwrap_or(DataFormat::Utf8),
+	)?;
+
+	let signature_bytes = decode_bytes(&request.signature, DataFormat::Hex)?;
+	let encoding = request.encoding.unwrap_or(match signature_bytes.len() {
+		64 => Sm2Encoding::Raw,
+		_ => Sm2Encoding::Der,
+	});
+	let signature = match encoding {
+		Sm2Encoding::Raw => Signature::from_slice(&signature_bytes),
+		Sm2Encoding::Der => Signature::from_der(&signature_bytes),
+	}
+	.map_err(|_| "Malformed signature".to_string())?;
+
+	Ok(verifying_key.verify(&message, &signature).is_ok())
+}
+
+#[tauri::command]
+pub fn sm2_encrypt(request: Sm2EncryptRequest) -> Result<String, String> {
+	require_binary_format(request.format)?;
+	let public_key = parse_public_key(&request.public_key)?;
+	let encrypting_key = EncryptingKey::new_with_mode(public_key, cipher_mode(request.mode));
+	let plaintext = decode_bytes(
+		&request.input,
+		request.input_format.unwrap_or(DataFormat::Utf8),
+	)?;
+
+	let ciphertext = match request.encoding {
+		Sm2Encoding::Raw => {
+			let mut raw = encrypting_key
+				.encrypt(&mut SysRng, &plaintext)
+				.map_err(|e| format!("Encrypt failed: {e}"))?;
+			if request.omit_point_prefix {
+				raw.remove(0);
+			}
+			raw
+		}
+		Sm2Encoding::Der => encrypting_key
+			.encrypt_der(&mut SysRng, &plaintext)
+			.map_err(|e| format!("Encrypt failed: {e}"))?,
+	};
+
+	encode_bytes(&ciphertext, request.format)
+}
+
+#[tauri::command]
+pub fn sm2_decrypt(request: Sm2DecryptRequest) -> Result<String, String> {
+	require_binary_format(request.format)?;
+	let scalar = parse_private_key(&request.private_key)?;
+	let decrypting_key = DecryptingKey::new_with_mode(scalar, cipher_mode(request.mode));
+	let ciphertext = decode_bytes(&request.input, request.format)?;
+
+	let plaintext: SecretBytes = Zeroizing::new(
+		match request.encoding {
+			Sm2Encoding::Raw => decrypting_key.decrypt(&ciphertext).or_else(|e| {
+				// Raw C1 without its 04 prefix, as produced by `sm-crypto`.
+				let mut prefixed = Vec::with_capacity(ciphertext.len() + 1);
+				prefixed.push(0x04);
+				prefixed.extend_from_slice(&ciphertext);
+				decrypting_key.decrypt(&prefixed).map_err(|_| e)
+			}),
+			Sm2Encoding::Der => decrypting_key.decrypt_der(&ciphertext),
+		}
+		.map_err(|e| format!("Decrypt failed: {e}"))?,
+	);
+
+	encode_bytes(&plaintext, request.plain_format.unwrap_or(DataFormat::Utf8))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sm2::dsa::signature::hazmat::PrehashVerifier;
+
+	// Vectors from the RustCrypto `sm2` test suite.
+	const DSA_PUBLIC_KEY: &str = "0408d77ae04c01cc4c1104360dd8af6b6f7df334283d7c1a6afd5652407b87bee5014e2a57c36c150d16324dc664e31e6432359609c4e79847a5b161c8c7364c8a";
+	const DSA_SIGNATURE: &str = "d1dcccedd9fb785e0f67c16b7c52901625c0b69de9bca2144acc7be713cad2fcf7d1eae6e3a157b36c65f672f738ca8b46298bf149a6510072c431b49cd88b1c";
+	const PKE_PRIVATE_KEY: &str =
+		"3ddd2a3679bf6f1dfc3b49d3e99114718e48ec170eb4e4d3a82052dab19e8b50";
+
+	fn verify_req(signature: &str, input: &str) -> Sm2VerifyRequest {
+		Sm2VerifyRequest {
+			input: input.to_string(),
+			input_format: None,
+			public_key: DSA_PUBLIC_KEY.to_string(),
+			user_id: Some("example@rustcrypto.org".to_string()),
+			signature: signature.to_string(),
+			encoding: None,
+		}
+	}
+
+	#[test]
+	fn sm2_verify_known_signature() {
+		assert!(sm2_verify(verify_req(DSA_SIGNATURE, "testing")).unwrap());
+		assert!(!sm2_verify(verify_req(DSA_SIGNATURE, "testing!")).unwrap());
+
+		// Same key without the 04 prefix.
+		let mut request = verify_req(DSA_SIGNATURE, "testing");
+		request.public_key = DSA_PUBLIC_KEY[2..].to_string();
+		assert!(sm2_verify(request).unwrap());
+	}
+
+	#[test]
+	fn sm2_sign_roundtrip_and_z() {
+		let keypair = sm2_generate_keypair().unwrap();
+		assert_eq!(keypair.public_key.len(), 130);
+		assert_eq!(keypair.public_key_compressed.len(), 66);
+
+		for encoding in [Sm2Encoding::Raw, Sm2Encoding::Der] {
+			let signed = sm2_sign(Sm2SignRequest {
+				input: "message digest".to_string(),
+				input_format: None,
+				private_key: keypair.private_key.clone(),
+				user_id: None,
+				encoding,
+			})
+			.unwrap();
+			assert_eq!(signed.public_key, keypair.public_key);
+
+			// The reported e is exactly what the signature covers.
+			let verifying_key = VerifyingKey::from_sec1_bytes(
+				DEFAULT_USER_ID,
+				&decode_hex_constant(&signed.public_key),
+			)
+			.unwrap();
+			let signature = decode_hex_constant(&signed.signature);
+			let signature = match encoding {
+				Sm2Encoding::Raw => Signature::from_slice(&signature),
+				Sm2Encoding::Der => Signature::from_der(&signature),
+			}
+			.unwrap();
+			assert!(verifying_key
+				.verify_prehash(&decode_hex_constant(&signed.e), &signature)
+				.is_ok());
+
+			assert!(sm2_verify(Sm2VerifyRequest {
+				input: "message digest".to_string(),
+				input_format: None,
+				public_key: keypair.public_key_compressed.clone(),
+				user_id: None,
+				signature: signed.signature,
+				encoding: None,
+			})
+			.unwrap());
+		}
+	}
+
+	#[test]
+	fn sm2_verify_openssl_signature() {
+		// openssl pkeyutl -sign -rawin -digest sm3 -pkeyopt distid:1234567812345678
+		let public_key = "047bf6ce8584088ce8dc036aa44eedda14b6d6be2cc558b31915f81f7a9d4d7323e1f4210c2efbd59bb0e8da4f65be32d22085cd3ebbafe490f5d52239af55c870";
+		let signature = "3046022100acbaa10f58d706b65dc5dc5f03876780a91da89538d10a5deb8c137d80d2dde8022100d14bba58e1e15a22b838c9d3bbae22cb0871f1cc242c617db295d957045a8024";
+		let request = |input: &str| Sm2VerifyRequest {
+			input: input.to_string(),
+			input_format: None,
+			public_key: public_key.to_string(),
+			user_id: None,
+			signature: signature.to_string(),
+			encoding: None,
+		};
+		assert!(sm2_verify(request("message digest")).unwrap());
+		assert!(!sm2_verify(request("message digesT")).unwrap());
+	}
+
+	fn dec_req(input: &str, mode: Sm2CipherMode, encoding: Sm2Encoding) -> Sm2DecryptRequest {
+		Sm2DecryptRequest {
+			input: input.to_string(),
+			format: DataFormat::Hex,
+			private_key: PKE_PRIVATE_KEY.to_string().into(),
+			mode,
+			encoding,
+			plain_format: None,
+		}
+	}
+
+	#[test]
+	fn sm2_decrypt_known_ciphertexts() {
+		let c1 = "041ed68db303f5bc6bce516d5a62e1cd16781d3007df6864d970a56d46a6cecca0e0d33bfc71e78c440ae6afeef1a18cce473b3e27002189a058ddadc9182c80a3";
+		let c3 = "f13be66476ba6ef66d95a7fb11f30de441b3b66d566e48348bd830e584e7ec37";
+		let c2 = "f9b704ef32eba9055c";
+
+		let c1c3c2 = format!("{c1}{c3}{c2}");
+		let got = sm2_decrypt(dec_req(&c1c3c2, Sm2CipherMode::C1C3C2, Sm2Encoding::Raw));
+		assert_eq!(got.unwrap(), "plaintext");
+
+		let c1c2c3 = format!("{c1}{c2}{c3}");
+		let got = sm2_decrypt(dec_req(&c1c2c3, Sm2CipherMode::C1C2C3, Sm2Encoding::Raw));
+		assert_eq!(got.unwrap(), "plaintext");
+		assert!(sm2_decrypt(dec_req(&c1c2c3, Sm2CipherMode::C1C3C2, Sm2Encoding::Raw)).is_err());
+
+		// C1 without the 04 prefix.
+		let got = sm2_decrypt(dec_req(
+			&c1c3c2[2..],
+			Sm2CipherMode::C1C3C2,
+			Sm2Encoding::Raw,
+		));
+		assert_eq!(got.unwrap(), "plaintext");
+
+		// openssl pkeyutl -encrypt
+		let der = "307202206ba17ad462a75beeb2caf8a1282687ab7e2f248b776a481612d89425a519ce6002210083e1de8c57dae995137227839d3880eaf9fe82a885a750be29ebe58193c8e31a0420d513a555087c2b17a88dd62749435133d325a4afca675284c85d754ba35670f80409bd3a294a6d50184b37";
+		let got = sm2_decrypt(dec_req(der, Sm2CipherMode::C1C3C2, Sm2Encoding::Der));
+		assert_eq!(got.unwrap(), "plaintext");
+	}
+
+	#[test]
+	fn sm2_encrypt_roundtrip_all_encodings() {
+		let public_key =
+			PublicKey::from_secret_scalar(&parse_private_key(PKE_PRIVATE_KEY).unwrap());
+		let public_key = public_key_hex(&public_key, true);
+		for mode in [Sm2CipherMode::C1C3C2, Sm2CipherMode::C1C2C3] {
+			for encoding in [Sm2Encoding::Raw, Sm2Encoding::Der] {
+				for omit_point_prefix in [false, true] {
+					let ciphertext = sm2_encrypt(Sm2EncryptRequest {
+						input: "plaintext".to_string(),
+						input_format: None,
+						public_key: public_key.clone(),
+						mode,
+						encoding,
+						format: DataFormat::Hex,
+						omit_point_prefix,
+					})
+					.unwrap();
+					if encoding == Sm2Encoding::Raw {
+						let expected = if omit_point_prefix { 105 } else { 106 };
+						assert_eq!(ciphertext.len(), expected * 2);
+					}
+					let got = sm2_decrypt(dec_req(&ciphertext, mode, encoding));
+					assert_eq!(got.unwrap(), "plaintext");
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,325 @@
+//! Both sides of the SM2 key agreement (GM/T 0003.3 / GB/T 32918.3), run in
+//! one process so every intermediate value can be shown. Initiator is user A,
+//! responder is user B; the cofactor of the recommended curve is 1.
+
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+use sm2::elliptic_curve::common::getrandom::SysRng;
+use sm2::elliptic_curve::ff::PrimeField;
+use sm2::elliptic_curve::group::Group;
+use sm2::elliptic_curve::sec1::ToSec1Point;
+use sm2::elliptic_curve::Generate;
+use sm2::{AffinePoint, FieldBytes, NonZeroScalar, ProjectivePoint, PublicKey, Scalar, SecretKey};
+use sm3::{Digest, Sm3};
+
+use super::secret::{SecretString, Zeroizing};
+use super::sm2::{compute_z, parse_private_key, public_key_hex, DEFAULT_USER_ID};
+use super::sm3::sm3_kdf;
+
+/// Shared keys longer than this are refused; real protocols ask for 16-64 bytes.
+const MAX_KEY_LENGTH: usize = 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2ExchangeParty {
+	/// Defaults to `DEFAULT_USER_ID`.
+	pub user_id: Option<String>,
+	/// Static private key d as hex.
+	pub private_key: SecretString,
+	/// Ephemeral private key r as hex; a random one is generated when absent.
+	pub ephemeral_key: Option<SecretString>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2KeyExchangeRequest {
+	pub initiator: Sm2ExchangeParty,
+	pub responder: Sm2ExchangeParty,
+	/// Length of the agreed key in bytes.
+	pub key_length: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2ExchangeSide {
+	pub user_id: String,
+	pub public_key: String,
+	pub z: String,
+	pub ephemeral_key: SecretString,
+	/// R = [r]G, sent to the peer.
+	pub ephemeral_public_key: String,
+	/// x̄ = 2^w + (x & (2^w - 1)) of the own R, with w = 127.
+	pub x_bar: String,
+	/// t = (d + x̄·r) mod n.
+	pub t: String,
+	/// U for the initiator, V for the responder.
+	pub shared_point: String,
+	pub shared_key: SecretString,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2KeyExchangeResponse {
+	pub initiator: Sm2ExchangeSide,
+	pub responder: Sm2ExchangeSide,
+	/// Responder's confirmation S_B, checked by the initiator against S1.
+	pub sb: String,
+	pub s1: String,
+	/// Initiator's confirmation S_A, checked by the responder against S2.
+	pub sa: String,
+	pub s2: String,
+	pub keys_match: bool,
+	pub responder_confirmed: bool,
+	pub initiator_confirmed: bool,
+}
+
+/// Party state after steps A1-A2 / B1-B2.
+struct Party {
+	user_id: String,
+	d: NonZeroScalar,
+	public_key: PublicKey,
+	z: [u8; 32],
+	r: NonZeroScalar,
+	r_point: AffinePoint,
+}
+
+impl Party {
+	fn new(party: &Sm2ExchangeParty, role: &str) -> Result<Self, String> {
+		let user_id = party
+			.user_id
+			.as_deref()
+			.unwrap_or(DEFAULT_USER_ID)
+			.to_string();
+		let d =
+			parse_private_key(&party.private_key).map_err(|e| format!("{role} static key: {e}"))?;
+		let public_key = PublicKey::from_secret_scalar(&d);
+		let z = compute_z(&user_id, &public_key)?;
+		let r = match &party.ephemeral_key {
+			Some(key) => {
+				parse_private_key(key).map_err(|e| format!("{role} ephemeral key: {e}"))?
+			}
+			None => SecretKey::try_generate_from_rng(&mut SysRng)
+				.map_err(|e| format!("Random number generator failed: {e}"))?
+				.to_nonzero_scalar(),
+		};
+		let r_point = (ProjectivePoint::GENERATOR * *r).to_affine();
+		Ok(Self {
+			user_id,
+			d,
+			public_key,
+			z,
+			r,
+			r_point,
+		})
+	}
+
+	fn x_bar(&self) -> Scalar {
+		x_bar(&self.r_point)
+	}
+
+	/// t = (d + x̄·r) mod n.
+	fn t(&self) -> Scalar {
+		*self.d + self.x_bar() * *self.r
+	}
+
+	/// [t](P + [x̄]R) with the peer's static and ephemeral public keys.
+	fn shared_point(&self, peer: &Party) -> Result<AffinePoint, String> {
+		let point = (ProjectivePoint::from(*peer.public_key.as_affine())
+			+ ProjectivePoint::from(peer.r_point) * peer.x_bar())
+			* self.t();
+		if bool::from(point.is_identity()) {
+			return Err("Shared point is the point at infinity".to_string());
+		}
+		Ok(point.to_affine())
+	}
+}
+
+/// x || y of an affine point, 64 bytes.
+fn coordinates(point: &AffinePoint) -> Vec<u8> {
+	point.to_sec1_point(false).as_bytes()[1..].to_vec()
+}
+
+fn x_bar(point: &AffinePoint) -> Scalar {
+	let x = coordinates(point);
+	let mut bytes = FieldBytes::default();
+	bytes[16..].copy_from_slice(&x[16..32]);
+	bytes[16] |= 0x80;
+	// Below 2^128 < n, so always a valid scalar.
+	Scalar::from_repr(bytes).unwrap()
+}
+
+/// Hash(prefix || y || Hash(x || ZA || ZB || x1 || y1 || x2 || y2)).
+fn confirmation(prefix: u8, shared: &[u8], initiator: &Party, responder: &Party) -> [u8; 32] {
+	let inner = Sm3::new()
+		.chain_update(&shared[..32])
+		.chain_update(initiator.z)
+		.chain_update(responder.z)
+		.chain_update(coordinates(&initiator.r_point))
+		.chain_update(coordinates(&responder.r_point))
+		.finalize();
+	Sm3::new()
+		.chain_update([prefix])
+		.chain_update(&shared[32..])
+		.chain_update(inner)
+		.finalize()
+		.into()
+}
+
+fn scalar_hex(scalar: &Scalar) -> String {
+	HEXLOWER.encode(&scalar.to_repr())
+}
+
+fn point_hex(point: &AffinePoint) -> String {
+	HEXLOWER.encode(point.to_sec1_point(false).as_bytes())
+}
+
+fn describe(party: &Party, shared_point: &AffinePoint, shared_key: &[u8]) -> Sm2ExchangeSide {
+	Sm2ExchangeSide {
+		user_id: party.user_id.clone(),
+		public_key: public_key_hex(&party.public_key, false),
+		z: HEXLOWER.encode(&party.z),
+		ephemeral_key: Zeroizing::new(scalar_hex(&party.r)),
+		ephemeral_public_key: point_hex(&party.r_point),
+		x_bar: scalar_hex(&party.x_bar()),
+		t: scalar_hex(&party.t()),
+		shared_point: point_hex(shared_point),
+		shared_key: Zeroizing::new(HEXLOWER.encode(shared_key)),
+	}
+}
+
+#[tauri::command]
+pub fn sm2_key_exchange(request: Sm2KeyExchangeRequest) -> Result<Sm2KeyExchangeResponse, String> {
+	if request.key_length == 0 || request.key_length > MAX_KEY_LENGTH {
+		return Err(format!(
+			"Key length must be between 1 and {MAX_KEY_LENGTH} bytes"
+		));
+	}
+	let initiator = Party::new(&request.initiator, "Initiator")?;
+	let responder = Party::new(&request.responder, "Responder")?;
+
+	// B5-B9: V, K_B and S_B.
+	let v = responder.shared_point(&initiator)?;
+	let v_bytes = coordinates(&v);
+	let kb = sm3_kdf(
+		&[v_bytes.as_slice(), &initiator.z, &responder.z].concat(),
+		request.key_length,
+	);
+	let sb = confirmation(0x02, &v_bytes, &initiator, &responder);
+
+	// A4-A10: U, K_A, S1 and S_A.
+	let u = initiator.shared_point(&responder)?;
+	let u_bytes = coordinates(&u);
+	let ka = sm3_kdf(
+		&[u_bytes.as_slice(), &initiator.z, &responder.z].concat(),
+		request.key_length,
+	);
+	let s1 = confirmation(0x02, &u_bytes, &initiator, &responder);
+	let sa = confirmation(0x03, &u_bytes, &initiator, &responder);
+
+	// B10: S2.
+	let s2 = confirmation(0x03, &v_bytes, &initiator, &responder);
+
+	Ok(Sm2KeyExchangeResponse {
+		initiator: describe(&initiator, &u, &ka),
+		responder: describe(&responder, &v, &kb),
+		sb: HEXLOWER.encode(&sb),
+		s1: HEXLOWER.encode(&s1),
+		sa: HEXLOWER.encode(&sa),
+		s2: HEXLOWER.encode(&s2),
+		keys_match: ka == kb,
+		responder_confirmed: s1 == sb,
+		initiator_confirmed: s2 == sa,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn party(user_id: &str, private_key: &str, ephemeral_key: Option<&str>) -> Sm2ExchangeParty {
+		Sm2ExchangeParty {
+			user_id: Some(user_id.to_string()),
+			private_key: private_key.to_string().into(),
+			ephemeral_key: ephemeral_key.map(|key| key.to_string().into()),
+		}
+	}
+
+	#[test]
+	fn sm2_key_exchange_fixed_keys() {
+		// Reference values from an independent big-integer implementation.
+		let got = sm2_key_exchange(Sm2KeyExchangeRequest {
+			initiator: party(
+				"ALICE123@YAHOO.COM",
+				"6fcba2ef9ae0ab902bc3bde3ff915d44ba4cc78f88e2f8e7f8996d3b8cceedee",
+				Some("83a2c9c8b96e5af70bd480b472409a9a327257f1ebb73f5b073354b248668563"),
+			),
+			responder: party(
+				"BILL456@YAHOO.COM",
+				"5e35d7d3f3c54dbac72e61819e730b019a84208ca3a35e4c2e353dfccb2a3b53",
+				Some("33fe21940342161c55619c4a0c060293d543c80af19748ce176d83477de71c80"),
+			),
+			key_length: 16,
+		})
+		.unwrap();
+
+		assert_eq!(
+			got.initiator.z,
+			"5d0b1182e1cee9093e96b6bdbf2e32f854d5c753e2ce9865d4984b8b70a8ae2b"
+		);
+		assert_eq!(
+			got.responder.z,
+			"441bf12aca4164c866bf282787dfc63e0cc8268fa34d435491b0cbc7f6ddbd5e"
+		);
+		assert_eq!(
+			&got.initiator.ephemeral_public_key[2..66],
+			"698c93c85618d221a6de87ca8f091a89cfaecc9fff2dd978f92769a40af3b740"
+		);
+		assert_eq!(
+			got.initiator.t,
+			"a4873bd4ce9a316dcd19b3b8197d1db852a16e8c40561dde2efbb3bb1f3fa297"
+		);
+		assert_eq!(
+			got.responder.t,
+			"e8edb121f8e34949794608872e6abdaa592dbd34adec5c0a042ba41613c11e06"
+		);
+		assert_eq!(
+			got.initiator.shared_point,
+			"04588d82da2f0e75a042aab58a707c725e203d1bf1cb8eff56e37af32c24665267e85d7572801c598eeebc061347821a568895aef6fb8448fbef16845cfcc2e6f4"
+		);
+		assert_eq!(
+			*got.initiator.shared_key,
+			"f02f9068ad13e14f2b2602e0dfb2504f"
+		);
+		assert_eq!(got.responder.shared_key, got.initiator.shared_key);
+		assert_eq!(
+			got.s1,
+			"b93374ade30a74e12ddb40e4c03d0c6fcf61badbf2c2c5cc39a91201d9228e2e"
+		);
+		assert_eq!(
+			got.sa,
+			"daefca6c32f53c48444d4ef35f98471e5d1cd1e3b5b8e3322dede310306689f6"
+		);
+		assert!(got.keys_match && got.responder_confirmed && got.initiator_confirmed);
+	}
+
+	#[test]
+	fn sm2_key_exchange_random_ephemeral_keys() {
+		let got = sm2_key_exchange(Sm2KeyExchangeRequest {
+			initiator: party("a", "01", None),
+			responder: party("b", "02", None),
+			key_length: 48,
+		})
+		.unwrap();
+		assert_eq!(got.initiator.shared_key.len(), 96);
+		assert_eq!(got.sb, got.s1);
+		assert_eq!(got.sa, got.s2);
+		assert!(got.keys_match);
+
+		assert!(sm2_key_exchange(Sm2KeyExchangeRequest {
+			initiator: party("a", "01", None),
+			responder: party("b", "02", None),
+			key_length: 0,
+		})
+		.is_err());
+	}
+}
@@ -0,0 +1,173 @@
+use std::fs::File;
+use std::io::Read;
+
+use data_encoding::HEXLOWER;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sm3::digest::Update;
+use sm3::{Digest, Sm3};
+
+use super::encoding::{decode_bytes, decode_key_bytes, DataFormat, KeyEncoding};
+use super::secret::{SecretBytes, SecretString, Zeroizing};
+
+/// Files are hashed in chunks of this size, so memory use does not grow with the file.
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sm3InputType {
+	Text,
+	Hex,
+	/// `input` is a path to a file on disk.
+	File,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm3Request {
+	pub input: String,
+	pub input_type: Sm3InputType,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HmacSm3Request {
+	pub input: String,
+	pub input_type: Sm3InputType,
+	/// Any length; keys longer than 64 bytes are hashed first, as HMAC specifies.
+	pub key: SecretString,
+	pub key_type: KeyEncoding,
+}
+
+fn feed_input<U: Update>(
+	hasher: &mut U,
+	input: &str,
+	input_type: Sm3InputType,
+) -> Result<(), String> {
+	match input_type {
+		Sm3InputType::Text => hasher.update(input.as_bytes()),
+		Sm3InputType::Hex => hasher.update(&decode_bytes(input, DataFormat::Hex)?),
+		Sm3InputType::File => {
+			let mut file =
+				File::open(input.trim()).map_err(|e| format!("Failed to open file: {e}"))?;
+			let mut buf = vec![0u8; READ_CHUNK_SIZE];
+			loop {
+				let n = file
+					.read(&mut buf)
+					.map_err(|e| format!("Failed to read file: {e}"))?;
+				if n == 0 {
+					break;
+				}
+				hasher.update(&buf[..n]);
+			}
+		}
+	}
+	Ok(())
+}
+
+/// KDF shared by SM2 and SM9 (GM/T 0003.4): SM3(Z || ct) with a 32-bit
+/// counter starting at 1, truncated to `length` bytes.
+pub(super) fn sm3_kdf(z: &[u8], length: usize) -> SecretBytes {
+	let mut out = Zeroizing::new(Vec::with_capacity(length + 32));
+	let mut counter: u32 = 1;
+	while out.len() < length {
+		out.extend_from_slice(
+			&Sm3::new()
+				.chain_update(z)
+				.chain_update(counter.to_be_bytes())
+				.finalize(),
+		);
+		counter += 1;
+	}
+	out.truncate(length);
+	out
+}
+
+#[tauri::command(async)]
+pub fn sm3_hash(request: Sm3Request) -> Result<String, String> {
+	let mut hasher = Sm3::new();
+	feed_input(&mut hasher, &request.input, request.input_type)?;
+	Ok(HEXLOWER.encode(&hasher.finalize()))
+}
+
+#[tauri::command(async)]
+pub fn hmac_sm3(request: HmacSm3Request) -> Result<String, String> {
+	let key = decode_key_bytes(&request.key, request.key_type)?;
+	let mut mac = <Hmac<Sm3> as Mac>::new_from_slice(&key).map_err(|e| e.to_string())?;
+	feed_input(&mut mac, &request.input, request.input_type)?;
+	Ok(HEXLOWER.encode(&mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hash(input: &str, input_type: Sm3InputType) -> String {
+		sm3_hash(Sm3Request {
+			input: input.to_string(),
+			input_type,
+		})
+		.unwrap()
+	}
+
+	#[test]
+	fn sm3_standard_vectors() {
+		// GB/T 32905-2016 appendix A.
+		assert_eq!(
+			hash("abc", Sm3InputType::Text),
+			"66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0"
+		);
+		assert_eq!(
+			hash(&"abcd".repeat(16), Sm3InputType::Text),
+			"debe9ff92275b8a138604889c18e5a4d6fdb70e5387e5765293dcba39c0c5732"
+		);
+		assert_eq!(
+			hash("61 62 63", Sm3InputType::Hex),
+			"66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0"
+		);
+	}
+
+	#[test]
+	fn sm3_file_input_is_streamed() {
+		let path = std::env::temp_dir().join("trovekit-sm3-test.bin");
+		let data = "abcd".repeat(16).repeat(READ_CHUNK_SIZE / 16);
+		std::fs::write(&path, &data).unwrap();
+		let got = hash(path.to_str().unwrap(), Sm3InputType::File);
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(got, hash(&data, Sm3InputType::Text));
+
+		assert!(sm3_hash(Sm3Request {
+			input: "/nonexistent/trovekit".to_string(),
+			input_type: Sm3InputType::File,
+		})
+		.is_err());
+	}
+
+	#[test]
+	fn hmac_sm3_vectors() {
+		let got = hmac_sm3(HmacSm3Request {
+			input: "abc".to_string(),
+			input_type: Sm3InputType::Text,
+			key: "key".to_string().into(),
+			key_type: KeyEncoding::Text,
+		})
+		.unwrap();
+		assert_eq!(
+			got,
+			"28e63256e7c5a087b1f073265dc53092163f7b82729735d06f28f10af9d52393"
+		);
+
+		// Key longer than the block size.
+		let got = hmac_sm3(HmacSm3Request {
+			input: "The quick brown fox jumps over the lazy dog".to_string(),
+			input_type: Sm3InputType::Text,
+			key: "6b".repeat(100).into(),
+			key_type: KeyEncoding::Hex,
+		})
+		.unwrap();
+		assert_eq!(
+			got,
+			"4d9349ab326a5e783a6c944a762b5665af9250c24c873a594bbfdaf761c0c276"
+		);
+	}
+}
@@ -1,394 +1,884 @@
-use cfb_mode::cipher::AsyncStreamCipher;
-use cipher::{
-	block_padding::NoPadding, BlockDecryptMut, BlockEncryptMut, KeyInit, KeyIvInit, StreamCipher,
-};
-use data_encoding::{BASE64, HEXLOWER, HEXLOWER_PERMISSIVE};
-use serde::Deserialize;
+use std::collections::BinaryHeap;
+
+use cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use cmac::{Cmac, Mac};
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
 use sm4::Sm4;
 
+use super::block::{
+	apply_padding, decrypt_blocks, encrypt_blocks, remove_padding, BlockMode, Padding,
+};
+use super::encoding::{
+	decode_bytes, encode_bytes, parse_fixed, parse_sized, require_binary_format, DataFormat,
+	KeyEncoding,
+};
+use super::kdf::{derive_for_decrypt, derive_for_encrypt, DerivedKey, PasswordKey};
+use super::secret::{SecretArray, SecretBytes, SecretString, Zeroizing};
+
 #[derive(Debug, Clone, Copy, Deserialize)]
 #[serde(rename_all = "lowercase")]
-pub enum Sm4Mode {
-	Ecb,
-	Cbc,
-	Cfb,
-	Ofb,
-	Ctr,
+pub enum Sm4MacAlgorithm {
+	/// Last block of CBC encryption (ISO/IEC 9797-1 MAC algorithm 1).
+	CbcMac,
+	/// NIST SP 800-38B / GB/T 15852.1 MAC algorithm 5; uses its own padding.
+	Cmac,
+	/// ANSI X9.19-style retail MAC with a double-length key K1 || K2.
+	Retail,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum Sm4Padding {
-	Pkcs7,
-	Zero,
-	None,
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm4Request {
+	#[serde(default)]
+	pub input: String,
+	pub mode: BlockMode,
+	pub padding: Padding,
+	/// Encrypt: output format; Decrypt: input format.
+	pub format: DataFormat,
+	/// Ignored when `password` is set.
+	#[serde(default)]
+	pub key: SecretString,
+	#[serde(default)]
+	pub key_type: KeyEncoding,
+	/// With a password, overrides the derived IV.
+	pub iv: Option<SecretString>,
+	pub iv_type: Option<KeyEncoding>,
+	/// Encrypt: input format; Decrypt: output format. UTF-8 when absent.
+	pub plain_format: Option<DataFormat>,
+	/// Derive the key (and IV) from a password instead of `key`.
+	pub password: Option<PasswordKey>,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum Sm4Format {
-	Hex,
-	Base64,
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm4BatchRequest {
+	/// Shared parameters. When `inputs` is absent, `input` is split into lines.
+	#[serde(flatten)]
+	pub request: Sm4Request,
+	pub inputs: Option<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm4BatchItem {
+	pub index: usize,
+	pub output: Option<String>,
+	pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm4BatchResponse {
+	pub items: Vec<Sm4BatchItem>,
+	pub succeeded: usize,
+	pub failed: usize,
+	/// Blank records, passed through as empty outputs to keep rows aligned.
+	pub skipped: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm4MacRequest {
+	pub input: String,
+	/// Format of `input`; UTF-8 when absent.
+	pub input_format: Option<DataFormat>,
+	pub algorithm: Sm4MacAlgorithm,
+	/// Ignored for CMAC.
+	pub padding: Padding,
+	/// Output format of the MAC.
+	pub format: DataFormat,
+	/// 16 bytes, or 32 bytes (K1 || K2) for the retail MAC.
+	pub key: SecretString,
+	pub key_type: KeyEncoding,
+	/// Initial chaining value for CBC-MAC and retail MAC; all zeros when absent.
+	pub iv: Option<SecretString>,
+	pub iv_type: Option<KeyEncoding>,
+	/// Keep only the leftmost bytes of the MAC (1-16); full block when absent.
+	pub mac_length: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm4GuessRequest {
+	/// At most 1 MiB once decoded.
+	pub input: String,
+	/// Ciphertext format; every binary format that decodes is tried when absent.
+	pub format: Option<DataFormat>,
+	/// Tried as text, hex and base64.
+	pub key: SecretString,
+	/// Tried as text, hex and base64, in addition to a zero IV and an IV prefixed to the ciphertext.
+	pub iv: Option<SecretString>,
+	/// Maximum number of candidates returned; 20 when absent.
+	pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "lowercase")]
-pub enum Sm4KeyType {
+pub enum Sm4IvSource {
+	/// ECB, no IV involved.
+	None,
 	Text,
 	Hex,
+	Base64,
+	Zero,
+	/// First 16 bytes of the ciphertext.
+	Prefix,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Sm4Request {
-	pub input: String,
-	pub mode: Sm4Mode,
-	pub padding: Sm4Padding,
-	/// Encrypt: output format; Decrypt: input format.
-	pub format: Sm4Format,
-	pub key: String,
-	pub key_type: Sm4KeyType,
-	pub iv: Option<String>,
-	pub iv_type: Option<Sm4KeyType>,
+pub struct Sm4GuessCandidate {
+	pub mode: BlockMode,
+	pub padding: Padding,
+	pub format: DataFormat,
+	pub key_type: KeyEncoding,
+	pub iv_source: Sm4IvSource,
+	/// Whether the padding was actually present (PKCS7 / ISO 7816 checked, zero bytes stripped).
+	pub padding_valid: bool,
+	/// Share of printable characters in the plaintext, 0-1.
+	pub printable_ratio: f64,
+	/// Shannon entropy in bits per byte.
+	pub entropy: f64,
+	pub score: f64,
+	/// UTF-8 when valid, hex otherwise.
+	pub plaintext: String,
+	pub plaintext_hex: String,
+}
+
+pub(super) fn sm4_encrypt_bytes(
+	plaintext: &[u8],
+	key: &[u8; 16],
+	iv: Option<&[u8; 16]>,
+	mode: BlockMode,
+	padding: Padding,
+) -> Result<Vec<u8>, String> {
+	let cipher = Sm4::new(key.into());
+	encrypt_blocks(&cipher, iv.map(|iv| &iv[..]), mode, padding, plaintext)
 }
 
-fn clean_whitespace(s: &str) -> String {
-	s.chars().filter(|c| !c.is_whitespace()).collect()
+pub(super) fn sm4_decrypt_bytes(
+	ciphertext: &[u8],
+	key: &[u8; 16],
+	iv: Option<&[u8; 16]>,
+	mode: BlockMode,
+	padding: Padding,
+) -> Result<Vec<u8>, String> {
+	let cipher = Sm4::new(key.into());
+	decrypt_blocks(&cipher, iv.map(|iv| &iv[..]), mode, padding, ciphertext)
 }
 
-fn decode_hex_to_bytes(input: &str) -> Result<Vec<u8>, String> {
-	let clean = clean_whitespace(input).to_ascii_lowercase();
-	HEXLOWER_PERMISSIVE
-		.decode(clean.as_bytes())
-		.map_err(|e| format!("Invalid hex: {e}"))
+fn require_classic_mode(mode: BlockMode) -> Result<(), String> {
+	if !mode.is_classic() {
+		return Err(format!(
+			"{mode} mode is not available here; use block_encrypt / block_decrypt"
+		));
+	}
+	Ok(())
 }
 
-fn decode_base64_to_bytes(input: &str) -> Result<Vec<u8>, String> {
-	let clean = clean_whitespace(input);
-	BASE64
-		.decode(clean.as_bytes())
-		.map_err(|e| format!("Invalid base64: {e}"))
+fn parse_iv(request: &Sm4Request) -> Result<Option<SecretArray<16>>, String> {
+	match request.iv.as_deref() {
+		Some(iv_value) if request.mode != BlockMode::Ecb => {
+			let iv_type = request
+				.iv_type
+				.ok_or_else(|| "ivType is required when IV is provided".to_string())?;
+			Ok(Some(parse_fixed::<16>(iv_value, iv_type, "IV")?))
+		}
+		_ => Ok(None),
+	}
 }
 
-fn encode_bytes(bytes: &[u8], format: Sm4Format) -> String {
-	match format {
-		Sm4Format::Hex => HEXLOWER.encode(bytes),
-		Sm4Format::Base64 => BASE64.encode(bytes),
+pub(super) fn parse_key_iv(
+	request: &Sm4Request,
+) -> Result<(SecretArray<16>, Option<SecretArray<16>>), String> {
+	require_classic_mode(request.mode)?;
+	if request.password.is_some() {
+		return Err("Password keys are not available here; give the key directly".to_string());
 	}
+	let key = parse_fixed::<16>(&request.key, request.key_type, "Key")?;
+
+	let iv = parse_iv(request)?;
+	if request.mode != BlockMode::Ecb && iv.is_none() {
+		return Err("IV is required for this mode".to_string());
+	}
+
+	Ok((key, iv))
 }
 
-fn parse_fixed_16(value: &str, ty: Sm4KeyType, name: &str) -> Result<[u8; 16], String> {
-	match ty {
-		Sm4KeyType::Hex => {
-			let bytes = decode_hex_to_bytes(value)?;
-			if bytes.len() != 16 {
-				return Err(format!(
-					"{name} must be 16 bytes (32 hex chars), got {} bytes",
-					bytes.len()
-				));
-			}
-			let mut out = [0u8; 16];
-			out.copy_from_slice(&bytes);
-			Ok(out)
+/// Key material shared by the records of a request. Password keys are derived
+/// per record, since every `Salted__` header carries its own salt.
+enum Sm4Keys<'a> {
+	Raw(SecretArray<16>, Option<SecretArray<16>>),
+	/// The IV, when given, replaces the derived one.
+	Password(&'a PasswordKey, Option<SecretArray<16>>),
+}
+
+fn resolve_keys(request: &Sm4Request) -> Result<Sm4Keys<'_>, String> {
+	match &request.password {
+		Some(password) => {
+			require_classic_mode(request.mode)?;
+			Ok(Sm4Keys::Password(password, parse_iv(request)?))
 		}
-		Sm4KeyType::Text => {
-			let bytes = value.as_bytes();
-			if bytes.len() != 16 {
-				return Err(format!(
-					"{name} must be exactly 16 bytes in UTF-8, got {} bytes",
-					bytes.len()
-				));
-			}
-			let mut out = [0u8; 16];
-			out.copy_from_slice(bytes);
-			Ok(out)
+		None => {
+			let (key, iv) = parse_key_iv(request)?;
+			Ok(Sm4Keys::Raw(key, iv))
 		}
 	}
 }
 
-fn require_iv(mode: Sm4Mode) -> bool {
-	!matches!(mode, Sm4Mode::Ecb)
+fn derived_iv_len(mode: BlockMode) -> usize {
+	if mode == BlockMode::Ecb {
+		0
+	} else {
+		16
+	}
 }
 
-fn apply_padding(mut data: Vec<u8>, padding: Sm4Padding) -> Result<Vec<u8>, String> {
-	const BLOCK: usize = 16;
-	match padding {
-		Sm4Padding::None => {
-			if data.len() % BLOCK != 0 {
-				return Err(
-					"Data length must be a multiple of 16 bytes when padding is none".to_string(),
-				);
-			}
-			Ok(data)
+/// Borrows the derived key and IV as arrays; an explicit IV wins over the derived one.
+fn split_derived<'a>(
+	derived: &'a DerivedKey,
+	iv: Option<&'a SecretArray<16>>,
+	mode: BlockMode,
+) -> (&'a [u8; 16], Option<&'a [u8; 16]>) {
+	let key = derived.key[..]
+		.try_into()
+		.expect("derived SM4 key is 16 bytes");
+	let iv = match iv {
+		Some(iv) => Some(&**iv),
+		None if mode != BlockMode::Ecb => Some(
+			derived.iv[..]
+				.try_into()
+				.expect("derived SM4 IV is 16 bytes"),
+		),
+		None => None,
+	};
+	(key, iv)
+}
+
+fn sm4_encrypt_record(input: &str, keys: &Sm4Keys, request: &Sm4Request) -> Result<String, String> {
+	let plaintext: SecretBytes = Zeroizing::new(decode_bytes(
+		input,
+		request.plain_format.unwrap_or(DataFormat::Utf8),
+	)?);
+	let ciphertext = match keys {
+		Sm4Keys::Raw(key, iv) => sm4_encrypt_bytes(
+			&plaintext,
+			key,
+			iv.as_deref(),
+			request.mode,
+			request.padding,
+		)?,
+		Sm4Keys::Password(password, iv) => {
+			let derived = derive_for_encrypt(password, 16, derived_iv_len(request.mode))?;
+			let (key, iv) = split_derived(&derived, iv.as_ref(), request.mode);
+			let mut output = derived.header.clone();
+			output.extend(sm4_encrypt_bytes(
+				&plaintext,
+				key,
+				iv,
+				request.mode,
+				request.padding,
+			)?);
+			output
 		}
-		Sm4Padding::Zero => {
-			let rem = data.len() % BLOCK;
-			if rem != 0 {
-				data.extend(std::iter::repeat(0u8).take(BLOCK - rem));
-			}
-			Ok(data)
+	};
+	encode_bytes(&ciphertext, request.format)
+}
+
+fn sm4_decrypt_record(input: &str, keys: &Sm4Keys, request: &Sm4Request) -> Result<String, String> {
+	let data = decode_bytes(input, request.format)?;
+	let plaintext: SecretBytes = Zeroizing::new(match keys {
+		Sm4Keys::Raw(key, iv) => {
+			sm4_decrypt_bytes(&data, key, iv.as_deref(), request.mode, request.padding)?
 		}
-		Sm4Padding::Pkcs7 => {
-			let pad = BLOCK - (data.len() % BLOCK);
-			data.extend(std::iter::repeat(pad as u8).take(pad));
-			Ok(data)
+		Sm4Keys::Password(password, iv) => {
+			let (derived, body) =
+				derive_for_decrypt(password, &data, 16, derived_iv_len(request.mode))?;
+			let (key, iv) = split_derived(&derived, iv.as_ref(), request.mode);
+			sm4_decrypt_bytes(body, key, iv, request.mode, request.padding)?
 		}
-	}
+	});
+	encode_bytes(&plaintext, request.plain_format.unwrap_or(DataFormat::Utf8))
 }
 
-fn remove_padding(mut data: Vec<u8>, padding: Sm4Padding) -> Result<Vec<u8>, String> {
-	const BLOCK: usize = 16;
-	match padding {
-		Sm4Padding::None => Ok(data),
-		Sm4Padding::Zero => {
-			while matches!(data.last(), Some(0u8)) {
-				data.pop();
-			}
-			Ok(data)
-		}
-		Sm4Padding::Pkcs7 => {
-			if data.is_empty() || data.len() % BLOCK != 0 {
-				return Err("Invalid plaintext length for PKCS7 unpadding".to_string());
+type Sm4RecordFn = fn(&str, &Sm4Keys, &Sm4Request) -> Result<String, String>;
+
+fn sm4_run_batch(request: Sm4BatchRequest, op: Sm4RecordFn) -> Result<Sm4BatchResponse, String> {
+	// Shared parameters are validated once; only per-record failures end up in the items.
+	let keys = resolve_keys(&request.request)?;
+	require_binary_format(request.request.format)?;
+
+	let records: Vec<&str> = match &request.inputs {
+		Some(inputs) => inputs.iter().map(String::as_str).collect(),
+		None => request.request.input.lines().collect(),
+	};
+
+	let mut response = Sm4BatchResponse {
+		items: Vec::with_capacity(records.len()),
+		succeeded: 0,
+		failed: 0,
+		skipped: 0,
+	};
+	for (index, record) in records.into_iter().enumerate() {
+		let item = if record.trim().is_empty() {
+			response.skipped += 1;
+			Sm4BatchItem {
+				index,
+				output: Some(String::new()),
+				error: None,
 			}
-			let pad = *data.last().unwrap() as usize;
-			if pad == 0 || pad > BLOCK || pad > data.len() {
-				return Err("Invalid PKCS7 padding".to_string());
+		} else {
+			match op(record, &keys, &request.request) {
+				Ok(output) => {
+					response.succeeded += 1;
+					Sm4BatchItem {
+						index,
+						output: Some(output),
+						error: None,
+					}
+				}
+				Err(error) => {
+					response.failed += 1;
+					Sm4BatchItem {
+						index,
+						output: None,
+						error: Some(error),
+					}
+				}
 			}
-			if !data[data.len() - pad..]
-				.iter()
-				.all(|&b| b as usize == pad)
-			{
-				return Err("Invalid PKCS7 padding".to_string());
-			}
-			data.truncate(data.len() - pad);
-			Ok(data)
-		}
+		};
+		response.items.push(item);
 	}
+
+	Ok(response)
 }
 
-fn decode_cipher_input(input: &str, format: Sm4Format) -> Result<Vec<u8>, String> {
-	match format {
-		Sm4Format::Hex => decode_hex_to_bytes(input),
-		Sm4Format::Base64 => decode_base64_to_bytes(input),
-	}
+#[tauri::command]
+pub fn sm4_encrypt(request: Sm4Request) -> Result<String, String> {
+	let keys = resolve_keys(&request)?;
+	require_binary_format(request.format)?;
+	sm4_encrypt_record(&request.input, &keys, &request)
 }
 
-fn sm4_encrypt_bytes(
-	plaintext: &[u8],
-	key: [u8; 16],
-	iv: Option<[u8; 16]>,
-	mode: Sm4Mode,
-	padding: Sm4Padding,
-) -> Result<Vec<u8>, String> {
-	let padded = apply_padding(plaintext.to_vec(), padding)?;
+#[tauri::command]
+pub fn sm4_decrypt(request: Sm4Request) -> Result<String, String> {
+	let keys = resolve_keys(&request)?;
+	require_binary_format(request.format)?;
+	sm4_decrypt_record(&request.input, &keys, &request)
+}
 
-	match mode {
-		Sm4Mode::Ecb => {
-			let cipher = ecb::Encryptor::<Sm4>::new(&key.into());
-			let mut buf = padded;
-			let msg_len = buf.len();
-			let out = cipher
-				.encrypt_padded_mut::<NoPadding>(&mut buf, msg_len)
-				.map_err(|e| format!("Encrypt failed: {e}"))?;
-			Ok(out.to_vec())
-		}
-		Sm4Mode::Cbc => {
-			let iv = iv.ok_or_else(|| "IV is required for CBC mode".to_string())?;
-			let cipher = cbc::Encryptor::<Sm4>::new(&key.into(), &iv.into());
-			let mut buf = padded;
-			let msg_len = buf.len();
-			let out = cipher
-				.encrypt_padded_mut::<NoPadding>(&mut buf, msg_len)
-				.map_err(|e| format!("Encrypt failed: {e}"))?;
-			Ok(out.to_vec())
+#[tauri::command]
+pub fn sm4_encrypt_batch(request: Sm4BatchRequest) -> Result<Sm4BatchResponse, String> {
+	sm4_run_batch(request, sm4_encrypt_record)
+}
+
+#[tauri::command]
+pub fn sm4_decrypt_batch(request: Sm4BatchRequest) -> Result<Sm4BatchResponse, String> {
+	sm4_run_batch(request, sm4_decrypt_record)
+}
+
+fn sm4_cbc_mac_bytes(cipher: &Sm4, data: &[u8], iv: [u8; 16]) -> [u8; 16] {
+	let mut state = iv;
+	for chunk in data.chunks(16) {
+		for (s, b) in state.iter_mut().zip(chunk) {
+			*s ^= b;
 		}
-		Sm4Mode::Cfb => {
-			let iv = iv.ok_or_else(|| "IV is required for CFB mode".to_string())?;
-			let mut buf = padded;
-			let cipher = cfb_mode::Encryptor::<Sm4>::new(&key.into(), &iv.into());
-			cipher.encrypt(&mut buf);
-			Ok(buf)
+		cipher.encrypt_block((&mut state).into());
+	}
+	state
+}
+
+fn pad_mac_input(data: &[u8], padding: Padding) -> Result<Vec<u8>, String> {
+	let mut padded = apply_padding(data.to_vec(), padding, 16)?;
+	// ISO/IEC 9797-1 padding method 1: an empty message is MACed as one zero block.
+	if padded.is_empty() {
+		padded.resize(16, 0);
+	}
+	Ok(padded)
+}
+
+fn sm4_mac_bytes(
+	data: &[u8],
+	key: &[u8],
+	iv: [u8; 16],
+	algorithm: Sm4MacAlgorithm,
+	padding: Padding,
+) -> Result<[u8; 16], String> {
+	match algorithm {
+		Sm4MacAlgorithm::CbcMac => {
+			let cipher = Sm4::new_from_slice(key)
+				.map_err(|_| "Key must be 16 bytes for CBC-MAC".to_string())?;
+			let padded = pad_mac_input(data, padding)?;
+			Ok(sm4_cbc_mac_bytes(&cipher, &padded, iv))
 		}
-		Sm4Mode::Ofb => {
-			let iv = iv.ok_or_else(|| "IV is required for OFB mode".to_string())?;
-			let mut buf = padded;
-			let mut cipher = ofb::Ofb::<Sm4>::new(&key.into(), &iv.into());
-			cipher.apply_keystream(&mut buf);
-			Ok(buf)
+		Sm4MacAlgorithm::Cmac => {
+			let mut mac = <Cmac<Sm4> as Mac>::new_from_slice(key)
+				.map_err(|_| "Key must be 16 bytes for CMAC".to_string())?;
+			mac.update(data);
+			Ok(mac.finalize().into_bytes().into())
 		}
-		Sm4Mode::Ctr => {
-			let iv = iv.ok_or_else(|| "IV is required for CTR mode".to_string())?;
-			let mut buf = padded;
-			let mut cipher = ctr::Ctr128BE::<Sm4>::new(&key.into(), &iv.into());
-			cipher.apply_keystream(&mut buf);
-			Ok(buf)
+		Sm4MacAlgorithm::Retail => {
+			if key.len() != 32 {
+				return Err(format!(
+					"Key must be 32 bytes (K1 || K2) for the retail MAC, got {} bytes",
+					key.len()
+				));
+			}
+			let k1 = Sm4::new_from_slice(&key[..16]).map_err(|e| e.to_string())?;
+			let k2 = Sm4::new_from_slice(&key[16..]).map_err(|e| e.to_string())?;
+			let padded = pad_mac_input(data, padding)?;
+			let mut state = sm4_cbc_mac_bytes(&k1, &padded, iv);
+			k2.decrypt_block((&mut state).into());
+			k1.encrypt_block((&mut state).into());
+			Ok(state)
 		}
 	}
 }
 
-fn sm4_decrypt_bytes(
-	ciphertext: &[u8],
-	key: [u8; 16],
-	iv: Option<[u8; 16]>,
-	mode: Sm4Mode,
-	padding: Sm4Padding,
-) -> Result<Vec<u8>, String> {
-	let plaintext_padded = match mode {
-		Sm4Mode::Ecb => {
-			let cipher = ecb::Decryptor::<Sm4>::new(&key.into());
-			let mut buf = ciphertext.to_vec();
-			let out = cipher
-				.decrypt_padded_mut::<NoPadding>(&mut buf)
-				.map_err(|e| format!("Decrypt failed: {e}"))?;
-			out.to_vec()
-		}
-		Sm4Mode::Cbc => {
-			let iv = iv.ok_or_else(|| "IV is required for CBC mode".to_string())?;
-			let cipher = cbc::Decryptor::<Sm4>::new(&key.into(), &iv.into());
-			let mut buf = ciphertext.to_vec();
-			let out = cipher
-				.decrypt_padded_mut::<NoPadding>(&mut buf)
-				.map_err(|e| format!("Decrypt failed: {e}"))?;
-			out.to_vec()
-		}
-		Sm4Mode::Cfb => {
-			let iv = iv.ok_or_else(|| "IV is required for CFB mode".to_string())?;
-			let mut buf = ciphertext.to_vec();
-			let cipher = cfb_mode::Decryptor::<Sm4>::new(&key.into(), &iv.into());
-			cipher.decrypt(&mut buf);
-			buf
+#[tauri::command]
+pub fn sm4_mac(request: Sm4MacRequest) -> Result<String, String> {
+	let lengths: &[usize] = match request.algorithm {
+		Sm4MacAlgorithm::Retail => &[32],
+		_ => &[16],
+	};
+	let key = parse_sized(&request.key, request.key_type, "Key", lengths)?;
+
+	let iv = match request.iv.as_deref().filter(|v| !v.is_empty()) {
+		Some(iv_value) => {
+			let iv_type = request
+				.iv_type
+				.ok_or_else(|| "ivType is required when IV is provided".to_string())?;
+			parse_fixed::<16>(iv_value, iv_type, "IV")?
 		}
-		Sm4Mode::Ofb => {
-			let iv = iv.ok_or_else(|| "IV is required for OFB mode".to_string())?;
-			let mut buf = ciphertext.to_vec();
-			let mut cipher = ofb::Ofb::<Sm4>::new(&key.into(), &iv.into());
-			cipher.apply_keystream(&mut buf);
-			buf
+		None => Zeroizing::new([0u8; 16]),
+	};
+
+	let mac_length = request.mac_length.unwrap_or(16);
+	if !(1..=16).contains(&mac_length) {
+		return Err(format!(
+			"MAC length must be between 1 and 16 bytes, got {mac_length}"
+		));
+	}
+
+	require_binary_format(request.format)?;
+	let input = decode_bytes(
+		&request.input,
+		request.input_format.unwrap_or(DataFormat::Utf8),
+	)?;
+	let mac = sm4_mac_bytes(&input, &key, *iv, request.algorithm, request.padding)?;
+
+	encode_bytes(&mac[..mac_length], request.format)
+}
+
+const ALL_MODES: [BlockMode; 5] = [
+	BlockMode::Ecb,
+	BlockMode::Cbc,
+	BlockMode::Cfb,
+	BlockMode::Ofb,
+	BlockMode::Ctr,
+];
+
+const ALL_PADDINGS: [Padding; 4] = [
+	Padding::Pkcs7,
+	Padding::Iso7816,
+	Padding::Zero,
+	Padding::None,
+];
+
+const ALL_KEY_TYPES: [KeyEncoding; 3] = [KeyEncoding::Text, KeyEncoding::Hex, KeyEncoding::Base64];
+
+fn printable_ratio(data: &[u8]) -> f64 {
+	match std::str::from_utf8(data) {
+		Ok(text) => {
+			let total = text.chars().count();
+			let printable = text
+				.chars()
+				.filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+				.count();
+			printable as f64 / total as f64
 		}
-		Sm4Mode::Ctr => {
-			let iv = iv.ok_or_else(|| "IV is required for CTR mode".to_string())?;
-			let mut buf = ciphertext.to_vec();
-			let mut cipher = ctr::Ctr128BE::<Sm4>::new(&key.into(), &iv.into());
-			cipher.apply_keystream(&mut buf);
-			buf
+		Err(_) => {
+			let printable = data
+				.iter()
+				.filter(|b| b.is_ascii_graphic() || matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+				.count();
+			printable as f64 / data.len() as f64
 		}
+	}
+}
+
+fn shannon_entropy(data: &[u8]) -> f64 {
+	let mut counts = [0usize; 256];
+	for &b in data {
+		counts[b as usize] += 1;
+	}
+	let len = data.len() as f64;
+	counts
+		.iter()
+		.filter(|&&c| c > 0)
+		.map(|&c| {
+			let p = c as f64 / len;
+			-p * p.log2()
+		})
+		.sum()
+}
+
+/// Decoded ciphertexts larger than this are rejected; every interpretation
+/// decrypts the sample below and the final block, plus the whole body for OFB.
+const GUESS_MAX_INPUT: usize = 1024 * 1024;
+/// Candidates are ranked on this much leading plaintext.
+const GUESS_SAMPLE: usize = 4096;
+
+struct ScoredPlaintext {
+	padding_valid: bool,
+	printable_ratio: f64,
+	entropy: f64,
+	score: f64,
+}
+
+/// Scores one padding interpretation from the leading `sample` and the final
+/// block `tail` of a `total_len`-byte decryption.
+fn score_candidate(
+	sample: &[u8],
+	tail: &[u8],
+	total_len: usize,
+	padding: Padding,
+) -> Option<ScoredPlaintext> {
+	let stripped = remove_padding(tail.to_vec(), padding, 16).ok()?;
+	let removed = tail.len() - stripped.len();
+	let plaintext_len = total_len - removed;
+	if plaintext_len == 0 {
+		return None;
+	}
+	let (padding_valid, padding_score) = match padding {
+		Padding::Pkcs7 | Padding::Iso7816 => (true, 1.0),
+		// Nothing stripped means the result is identical to the `none` candidate.
+		Padding::Zero if removed == 0 => return None,
+		Padding::Zero => (true, 0.6),
+		Padding::None => (false, 0.3),
 	};
 
-	remove_padding(plaintext_padded, padding)
+	let sample = &sample[..sample.len().min(plaintext_len)];
+	let printable_ratio = printable_ratio(sample);
+	let entropy = shannon_entropy(sample);
+	// Short inputs cannot reach 8 bits/byte, so normalise against the achievable maximum.
+	let max_entropy = (sample.len() as f64).log2().clamp(1.0, 8.0);
+	let score = 0.6 * printable_ratio + 0.25 * padding_score + 0.15 * (1.0 - entropy / max_entropy);
+	Some(ScoredPlaintext {
+		padding_valid,
+		printable_ratio,
+		entropy,
+		score,
+	})
 }
 
-#[tauri::command]
-pub fn sm4_encrypt(request: Sm4Request) -> Result<String, String> {
-	let key = parse_fixed_16(&request.key, request.key_type, "Key")?;
-
-	let iv = if require_iv(request.mode) {
-		let iv_value = request
-			.iv
-			.as_deref()
-			.ok_or_else(|| "IV is required for this mode".to_string())?;
-		let iv_type = request
-			.iv_type
-			.ok_or_else(|| "ivType is required when IV is provided".to_string())?;
-		Some(parse_fixed_16(iv_value, iv_type, "IV")?)
-	} else {
-		None
+/// Plaintext of the last (possibly partial) block, decrypted on its own where
+/// the mode allows it.
+fn decrypt_tail(
+	cipher: &Sm4,
+	iv: Option<&[u8; 16]>,
+	mode: BlockMode,
+	body: &[u8],
+) -> Result<Vec<u8>, String> {
+	let start = (body.len() - 1) / 16 * 16;
+	let tail = &body[start..];
+	let previous = match start {
+		0 => iv.map(|iv| &iv[..]),
+		_ => Some(&body[start - 16..start]),
 	};
+	match mode {
+		BlockMode::Ecb => decrypt_blocks(cipher, None, mode, Padding::None, tail),
+		BlockMode::Cbc | BlockMode::Cfb => {
+			decrypt_blocks(cipher, previous, mode, Padding::None, tail)
+		}
+		BlockMode::Ctr => {
+			let counter = iv.map(|iv| {
+				u128::from_be_bytes(*iv)
+					.wrapping_add((start / 16) as u128)
+					.to_be_bytes()
+			});
+			decrypt_blocks(
+				cipher,
+				counter.as_ref().map(|c| &c[..]),
+				mode,
+				Padding::None,
+				tail,
+			)
+		}
+		// Each OFB keystream block depends on all earlier ones.
+		_ => decrypt_blocks(cipher, iv.map(|iv| &iv[..]), mode, Padding::None, body)
+			.map(|raw| raw[start..].to_vec()),
+	}
+}
 
-	let ciphertext = sm4_encrypt_bytes(
-		request.input.as_bytes(),
-		key,
-		iv,
-		request.mode,
-		request.padding,
-	)?;
+/// Where the IV came from, the ciphertext body and the IV itself.
+type GuessAttempt<'a> = (Sm4IvSource, &'a [u8], Option<&'a [u8; 16]>);
 
-	Ok(encode_bytes(&ciphertext, request.format))
+/// Body and IV interpretations tried for one ciphertext in one mode.
+fn guess_attempts<'a>(
+	ciphertext: &'a [u8],
+	mode: BlockMode,
+	ivs: &'a [(Sm4IvSource, SecretArray<16>)],
+) -> Vec<GuessAttempt<'a>> {
+	let mut attempts = Vec::new();
+	if mode == BlockMode::Ecb {
+		attempts.push((Sm4IvSource::None, ciphertext, None));
+		return attempts;
+	}
+	for (source, iv) in ivs {
+		attempts.push((*source, ciphertext, Some(&**iv)));
+	}
+	if ciphertext.len() > 16 {
+		let (iv, body) = ciphertext.split_at(16);
+		attempts.push((Sm4IvSource::Prefix, body, iv.try_into().ok()));
+	}
+	attempts
+}
+
+/// A scored interpretation, by index into the guess inputs; ordered so the
+/// heap's top is the weakest candidate kept so far.
+struct RankedGuess {
+	score: ScoredPlaintext,
+	order: usize,
+	ciphertext: usize,
+	key: usize,
+	mode: BlockMode,
+	attempt: usize,
+	padding: Padding,
+}
+
+impl PartialEq for RankedGuess {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == std::cmp::Ordering::Equal
+	}
+}
+
+impl Eq for RankedGuess {}
+
+impl PartialOrd for RankedGuess {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for RankedGuess {
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		other
+			.score
+			.score
+			.total_cmp(&self.score.score)
+			.then(self.order.cmp(&other.order))
+	}
 }
 
 #[tauri::command]
-pub fn sm4_decrypt(request: Sm4Request) -> Result<String, String> {
-	let key = parse_fixed_16(&request.key, request.key_type, "Key")?;
-
-	let iv = if require_iv(request.mode) {
-		let iv_value = request
-			.iv
-			.as_deref()
-			.ok_or_else(|| "IV is required for this mode".to_string())?;
-		let iv_type = request
-			.iv_type
-			.ok_or_else(|| "ivType is required when IV is provided".to_string())?;
-		Some(parse_fixed_16(iv_value, iv_type, "IV")?)
-	} else {
-		None
+pub fn sm4_decrypt_guess(request: Sm4GuessRequest) -> Result<Vec<Sm4GuessCandidate>, String> {
+	let formats = match request.format {
+		Some(format) => {
+			require_binary_format(format)?;
+			vec![format]
+		}
+		None => vec![DataFormat::Hex, DataFormat::Base64, DataFormat::Base64url],
 	};
+	let mut ciphertexts: Vec<(DataFormat, Vec<u8>)> = Vec::new();
+	for format in formats {
+		if let Ok(bytes) = decode_bytes(&request.input, format) {
+			if bytes.len() > GUESS_MAX_INPUT {
+				return Err(format!(
+					"Ciphertext is {} bytes; guessing is limited to {GUESS_MAX_INPUT} bytes",
+					bytes.len()
+				));
+			}
+			if !bytes.is_empty() && !ciphertexts.iter().any(|(_, b)| *b == bytes) {
+				ciphertexts.push((format, bytes));
+			}
+		}
+	}
+	if ciphertexts.is_empty() {
+		return Err("Ciphertext could not be decoded as hex, base64 or base64url".to_string());
+	}
 
-	let ciphertext = decode_cipher_input(&request.input, request.format)?;
-	let plaintext_bytes = sm4_decrypt_bytes(
-		&ciphertext,
-		key,
-		iv,
-		request.mode,
-		request.padding,
-	)?;
+	let mut keys: Vec<(KeyEncoding, SecretArray<16>)> = Vec::new();
+	for ty in ALL_KEY_TYPES {
+		if let Ok(key) = parse_fixed::<16>(&request.key, ty, "Key") {
+			if !keys.iter().any(|(_, k)| *k == key) {
+				keys.push((ty, key));
+			}
+		}
+	}
+	if keys.is_empty() {
+		return Err("Key is not 16 bytes as text, hex or base64".to_string());
+	}
 
-	String::from_utf8(plaintext_bytes)
-		.map_err(|_| "Decrypted data is not valid UTF-8 text".to_string())
+	let mut ivs: Vec<(Sm4IvSource, SecretArray<16>)> = Vec::new();
+	if let Some(iv) = request.iv.as_deref().filter(|v| !v.trim().is_empty()) {
+		let sources = [Sm4IvSource::Text, Sm4IvSource::Hex, Sm4IvSource::Base64];
+		for (ty, source) in ALL_KEY_TYPES.into_iter().zip(sources) {
+			if let Ok(iv) = parse_fixed::<16>(iv, ty, "IV") {
+				if !ivs.iter().any(|(_, v)| *v == iv) {
+					ivs.push((source, iv));
+				}
+			}
+		}
+	}
+	ivs.push((Sm4IvSource::Zero, Zeroizing::new([0u8; 16])));
+
+	// Rank every interpretation on a bounded sample and keep only the best
+	// `limit`; full plaintexts are decrypted for those alone.
+	let limit = request.limit.unwrap_or(20);
+	let mut ranked = BinaryHeap::new();
+	let mut order = 0;
+	for (ciphertext_index, (_, ciphertext)) in ciphertexts.iter().enumerate() {
+		for (key_index, (_, key)) in keys.iter().enumerate() {
+			let cipher = Sm4::new((&**key).into());
+			for mode in ALL_MODES {
+				let attempts = guess_attempts(ciphertext, mode, &ivs);
+				for (attempt, (_, body, iv)) in attempts.into_iter().enumerate() {
+					let sample_len = body.len().min(GUESS_SAMPLE);
+					let Ok(sample) = decrypt_blocks(
+						&cipher,
+						iv.map(|iv| &iv[..]),
+						mode,
+						Padding::None,
+						&body[..sample_len],
+					)
+					.map(Zeroizing::new) else {
+						continue;
+					};
+					let Ok(tail) = decrypt_tail(&cipher, iv, mode, body).map(Zeroizing::new) else {
+						continue;
+					};
+					for padding in ALL_PADDINGS {
+						let Some(score) = score_candidate(&sample, &tail, body.len(), padding)
+						else {
+							continue;
+						};
+						ranked.push(RankedGuess {
+							score,
+							order,
+							ciphertext: ciphertext_index,
+							key: key_index,
+							mode,
+							attempt,
+							padding,
+						});
+						order += 1;
+						if ranked.len() > limit {
+							ranked.pop();
+						}
+					}
+				}
+			}
+		}
+	}
+
+	let mut candidates = Vec::with_capacity(ranked.len());
+	for guess in ranked.into_sorted_vec() {
+		let (format, ciphertext) = &ciphertexts[guess.ciphertext];
+		let (key_type, key) = &keys[guess.key];
+		let (iv_source, body, iv) = guess_attempts(ciphertext, guess.mode, &ivs)[guess.attempt];
+		let Ok(plaintext) =
+			sm4_decrypt_bytes(body, key, iv, guess.mode, guess.padding).map(Zeroizing::new)
+		else {
+			continue;
+		};
+		candidates.push(Sm4GuessCandidate {
+			mode: guess.mode,
+			padding: guess.padding,
+			format: *format,
+			key_type: *key_type,
+			iv_source,
+			padding_valid: guess.score.padding_valid,
+			printable_ratio: guess.score.printable_ratio,
+			entropy: guess.score.entropy,
+			score: guess.score.score,
+			plaintext: match std::str::from_utf8(&plaintext) {
+				Ok(text) => text.to_string(),
+				Err(_) => HEXLOWER.encode(&plaintext),
+			},
+			plaintext_hex: HEXLOWER.encode(&plaintext),
+		});
+	}
+	Ok(candidates)
 }
 
 #[cfg(test)]
 mod tests {
+	use data_encoding::BASE64;
+
 	use super::*;
+	use crate::crypto::kdf::{KdfHash, KdfSettings};
 
 	const PLAINTEXT: &str = "0123456789abcdeffedcba9876543210";
 	const KEY_TEXT: &str = "1234567890123456";
 	const IV_TEXT: &str = "1234567890123456";
 
-	fn enc_req(mode: Sm4Mode) -> Sm4Request {
+	fn enc_req(mode: BlockMode) -> Sm4Request {
 		Sm4Request {
 			input: PLAINTEXT.to_string(),
 			mode,
-			padding: Sm4Padding::Pkcs7,
-			format: Sm4Format::Hex,
-			key: KEY_TEXT.to_string(),
-			key_type: Sm4KeyType::Text,
-			iv: Some(IV_TEXT.to_string()),
-			iv_type: Some(Sm4KeyType::Text),
+			padding: Padding::Pkcs7,
+			format: DataFormat::Hex,
+			key: KEY_TEXT.to_string().into(),
+			key_type: KeyEncoding::Text,
+			iv: Some(IV_TEXT.to_string().into()),
+			iv_type: Some(KeyEncoding::Text),
+			plain_format: None,
+			password: None,
 		}
 	}
 
-	fn dec_req(mode: Sm4Mode, ciphertext_hex: &str) -> Sm4Request {
+	fn dec_req(mode: BlockMode, ciphertext_hex: &str) -> Sm4Request {
 		Sm4Request {
 			input: ciphertext_hex.to_string(),
 			mode,
-			padding: Sm4Padding::Pkcs7,
-			format: Sm4Format::Hex,
-			key: KEY_TEXT.to_string(),
-			key_type: Sm4KeyType::Text,
-			iv: Some(IV_TEXT.to_string()),
-			iv_type: Some(Sm4KeyType::Text),
+			padding: Padding::Pkcs7,
+			format: DataFormat::Hex,
+			key: KEY_TEXT.to_string().into(),
+			key_type: KeyEncoding::Text,
+			iv: Some(IV_TEXT.to_string().into()),
+			iv_type: Some(KeyEncoding::Text),
+			plain_format: None,
+			password: None,
+		}
+	}
+
+	fn mac_req(algorithm: Sm4MacAlgorithm, padding: Padding) -> Sm4MacRequest {
+		Sm4MacRequest {
+			input: PLAINTEXT.to_string(),
+			input_format: None,
+			algorithm,
+			padding,
+			format: DataFormat::Hex,
+			key: KEY_TEXT.to_string().into(),
+			key_type: KeyEncoding::Text,
+			iv: None,
+			iv_type: None,
+			mac_length: None,
 		}
 	}
 
 	#[test]
 	fn sm4_cbc_pkcs7_vector() {
 		let expected = "50AAF1EA1040E2B564A39F88D79F140119D813B078261D344CB6EFF909384015265D7CC8ADFE8D99477442FB5912539D";
-		let got = sm4_encrypt(enc_req(Sm4Mode::Cbc)).unwrap();
+		let got = sm4_encrypt(enc_req(BlockMode::Cbc)).unwrap();
 		assert_eq!(got.to_ascii_lowercase(), expected.to_ascii_lowercase());
 
-		let out = sm4_decrypt(dec_req(Sm4Mode::Cbc, expected)).unwrap();
+		let out = sm4_decrypt(dec_req(BlockMode::Cbc, expected)).unwrap();
 		assert_eq!(out, PLAINTEXT);
 	}
 
 	#[test]
 	fn sm4_ecb_pkcs7_vector() {
 		let expected = "BA611296FD1F3F27299EFF1C29B18D63DE48B88F34351E6D653B381CE1355CA66C88F739AF2A29A735381F5677BADEF7";
-		let mut req = enc_req(Sm4Mode::Ecb);
+		let mut req = enc_req(BlockMode::Ecb);
 		req.iv = None;
 		req.iv_type = None;
 		let got = sm4_encrypt(req).unwrap();
 		assert_eq!(got.to_ascii_lowercase(), expected.to_ascii_lowercase());
 
-		let mut dreq = dec_req(Sm4Mode::Ecb, expected);
+		let mut dreq = dec_req(BlockMode::Ecb, expected);
 		dreq.iv = None;
 		dreq.iv_type = None;
 		let out = sm4_decrypt(dreq).unwrap();
@@ -398,30 +888,255 @@ mod tests {
 	#[test]
 	fn sm4_ofb_vector() {
 		let expected = "80B2EEF39DC37DEEC2DB9BEEF00A5811052C7433380B380F300571626D4498FECAD5947E958E9F1823260B144963213A";
-		let got = sm4_encrypt(enc_req(Sm4Mode::Ofb)).unwrap();
+		let got = sm4_encrypt(enc_req(BlockMode::Ofb)).unwrap();
 		assert_eq!(got.to_ascii_lowercase(), expected.to_ascii_lowercase());
 
-		let out = sm4_decrypt(dec_req(Sm4Mode::Ofb, expected)).unwrap();
+		let out = sm4_decrypt(dec_req(BlockMode::Ofb, expected)).unwrap();
 		assert_eq!(out, PLAINTEXT);
 	}
 
 	#[test]
 	fn sm4_cfb_vector() {
 		let expected = "80B2EEF39DC37DEEC2DB9BEEF00A58111CA93628EE96AD2C29C91379C729C05F649C4BD0852DDDE13384464C912E6C22";
-		let got = sm4_encrypt(enc_req(Sm4Mode::Cfb)).unwrap();
+		let got = sm4_encrypt(enc_req(BlockMode::Cfb)).unwrap();
 		assert_eq!(got.to_ascii_lowercase(), expected.to_ascii_lowercase());
 
-		let out = sm4_decrypt(dec_req(Sm4Mode::Cfb, expected)).unwrap();
+		let out = sm4_decrypt(dec_req(BlockMode::Cfb, expected)).unwrap();
 		assert_eq!(out, PLAINTEXT);
 	}
 
 	#[test]
 	fn sm4_ctr_vector() {
 		let expected = "80b2eef39dc37deec2db9beef00a581128692ce012c17cfd95986ded8b1c824fa629037b38944cc69e23312e2f94dbf6";
-		let got = sm4_encrypt(enc_req(Sm4Mode::Ctr)).unwrap();
+		let got = sm4_encrypt(enc_req(BlockMode::Ctr)).unwrap();
 		assert_eq!(got.to_ascii_lowercase(), expected.to_ascii_lowercase());
 
-		let out = sm4_decrypt(dec_req(Sm4Mode::Ctr, expected)).unwrap();
+		let out = sm4_decrypt(dec_req(BlockMode::Ctr, expected)).unwrap();
 		assert_eq!(out, PLAINTEXT);
 	}
+
+	#[test]
+	fn sm4_cbc_mac_matches_last_cbc_block() {
+		let mut req = mac_req(Sm4MacAlgorithm::CbcMac, Padding::Pkcs7);
+		req.iv = Some(IV_TEXT.to_string().into());
+		req.iv_type = Some(KeyEncoding::Text);
+		assert_eq!(sm4_mac(req).unwrap(), "265d7cc8adfe8d99477442fb5912539d");
+
+		let req = mac_req(Sm4MacAlgorithm::CbcMac, Padding::Iso7816);
+		assert_eq!(sm4_mac(req).unwrap(), "68724db8dcfc5ba9a56d01e03e1ba1b3");
+	}
+
+	#[test]
+	fn sm4_cmac_vector() {
+		let req = mac_req(Sm4MacAlgorithm::Cmac, Padding::None);
+		assert_eq!(sm4_mac(req).unwrap(), "5b3bfb90d37fb0acd4edfca6ac78c63d");
+
+		let mut req = mac_req(Sm4MacAlgorithm::Cmac, Padding::None);
+		req.input = "abc".to_string();
+		req.mac_length = Some(4);
+		assert_eq!(sm4_mac(req).unwrap(), "a54dbc9b");
+	}
+
+	#[test]
+	fn sm4_retail_mac_vector() {
+		let mut req = mac_req(Sm4MacAlgorithm::Retail, Padding::Iso7816);
+		req.key = "31323334353637383930313233343536 0123456789abcdeffedcba9876543210"
+			.to_string()
+			.into();
+		req.key_type = KeyEncoding::Hex;
+		assert_eq!(sm4_mac(req).unwrap(), "e6e41cfcd08d7df42d7983e9ea47232e");
+
+		let mut req = mac_req(Sm4MacAlgorithm::Retail, Padding::Iso7816);
+		req.mac_length = Some(17);
+		assert!(sm4_mac(req).is_err());
+	}
+
+	#[test]
+	fn sm4_binary_plaintext_formats() {
+		let expected = "50aaf1ea1040e2b564a39f88d79f140119d813b078261d344cb6eff909384015265d7cc8adfe8d99477442fb5912539d";
+		let mut req = enc_req(BlockMode::Cbc);
+		req.input = HEXLOWER.encode(PLAINTEXT.as_bytes());
+		req.plain_format = Some(DataFormat::Hex);
+		assert_eq!(sm4_encrypt(req).unwrap(), expected);
+
+		let mut req = enc_req(BlockMode::Cbc);
+		req.key = BASE64.encode(KEY_TEXT.as_bytes()).into();
+		req.key_type = KeyEncoding::Base64;
+		req.format = DataFormat::Base64url;
+		let got = sm4_encrypt(req).unwrap();
+		assert!(!got.contains('=') && !got.contains('+') && !got.contains('/'));
+		let mut dreq = dec_req(BlockMode::Cbc, &got);
+		dreq.format = DataFormat::Base64url;
+		dreq.plain_format = Some(DataFormat::Hex);
+		assert_eq!(
+			sm4_decrypt(dreq).unwrap(),
+			HEXLOWER.encode(PLAINTEXT.as_bytes())
+		);
+
+		let mut req = enc_req(BlockMode::Cbc);
+		req.format = DataFormat::Utf8;
+		assert!(sm4_encrypt(req).is_err());
+	}
+
+	#[test]
+	fn sm4_decrypt_non_utf8_needs_a_binary_format() {
+		let mut req = enc_req(BlockMode::Ecb);
+		req.input = "ff00fe01".to_string();
+		req.plain_format = Some(DataFormat::Hex);
+		let ciphertext = sm4_encrypt(req).unwrap();
+
+		let err = sm4_decrypt(dec_req(BlockMode::Ecb, &ciphertext)).unwrap_err();
+		assert!(err.contains("not valid UTF-8"), "{err}");
+		let mut dreq = dec_req(BlockMode::Ecb, &ciphertext);
+		dreq.plain_format = Some(DataFormat::Hex);
+		assert_eq!(sm4_decrypt(dreq).unwrap(), "ff00fe01");
+	}
+
+	#[test]
+	fn sm4_batch_reports_per_record_errors() {
+		let expected = "50AAF1EA1040E2B564A39F88D79F140119D813B078261D344CB6EFF909384015265D7CC8ADFE8D99477442FB5912539D";
+		let request = Sm4BatchRequest {
+			request: dec_req(
+				BlockMode::Cbc,
+				&format!("{expected}\r\n\nnot-hex\n{expected}\n"),
+			),
+			inputs: None,
+		};
+		let response = sm4_decrypt_batch(request).unwrap();
+		assert_eq!(response.items.len(), 4);
+		assert_eq!(
+			(response.succeeded, response.failed, response.skipped),
+			(2, 1, 1)
+		);
+		assert_eq!(response.items[0].output.as_deref(), Some(PLAINTEXT));
+		assert_eq!(response.items[1].output.as_deref(), Some(""));
+		assert!(response.items[2].error.is_some());
+		assert_eq!(response.items[3].output.as_deref(), Some(PLAINTEXT));
+
+		let request = Sm4BatchRequest {
+			request: enc_req(BlockMode::Cbc),
+			inputs: Some(vec![PLAINTEXT.to_string(), PLAINTEXT.to_string()]),
+		};
+		let response = sm4_encrypt_batch(request).unwrap();
+		assert_eq!(response.succeeded, 2);
+		assert_eq!(
+			response.items[1].output.as_deref(),
+			Some(expected.to_ascii_lowercase().as_str())
+		);
+
+		let mut bad_key = enc_req(BlockMode::Cbc);
+		bad_key.key = "short".to_string().into();
+		let request = Sm4BatchRequest {
+			request: bad_key,
+			inputs: Some(vec![PLAINTEXT.to_string()]),
+		};
+		assert!(sm4_encrypt_batch(request).is_err());
+	}
+
+	#[test]
+	fn sm4_guess_finds_iv_prefixed_cbc() {
+		let mut req = enc_req(BlockMode::Cbc);
+		req.key = HEXLOWER.encode(KEY_TEXT.as_bytes()).into();
+		req.key_type = KeyEncoding::Hex;
+		let ciphertext = sm4_encrypt(req).unwrap();
+		let input = format!("{}{ciphertext}", HEXLOWER.encode(IV_TEXT.as_bytes()));
+
+		let candidates = sm4_decrypt_guess(Sm4GuessRequest {
+			input,
+			format: None,
+			key: HEXLOWER.encode(KEY_TEXT.as_bytes()).into(),
+			iv: None,
+			limit: Some(5),
+		})
+		.unwrap();
+		assert!(candidates.len() <= 5);
+		let best = &candidates[0];
+		assert_eq!(best.mode, BlockMode::Cbc);
+		assert_eq!(best.padding, Padding::Pkcs7);
+		assert_eq!(best.key_type, KeyEncoding::Hex);
+		assert_eq!(best.iv_source, Sm4IvSource::Prefix);
+		assert!(best.padding_valid);
+		assert_eq!(best.plaintext, PLAINTEXT);
+	}
+
+	#[test]
+	fn sm4_guess_scores_long_input_from_sample_and_final_block() {
+		// Longer than the scoring sample, with a partial final block for the stream modes.
+		let plaintext = "lorem ipsum dolor sit amet ".repeat(400);
+		for mode in ALL_MODES {
+			let mut req = enc_req(mode);
+			req.input = plaintext.clone();
+			let ciphertext = sm4_encrypt(req).unwrap();
+			let candidates = sm4_decrypt_guess(Sm4GuessRequest {
+				input: ciphertext,
+				format: Some(DataFormat::Hex),
+				key: KEY_TEXT.to_string().into(),
+				iv: Some(IV_TEXT.to_string().into()),
+				limit: Some(3),
+			})
+			.unwrap();
+			assert_eq!(candidates.len(), 3, "{mode}");
+			// Dropping the first block and using it as the IV also reads cleanly.
+			assert!(
+				candidates.iter().any(|c| c.mode == mode
+					&& c.padding == Padding::Pkcs7
+					&& c.iv_source != Sm4IvSource::Prefix
+					&& c.plaintext == plaintext),
+				"{mode}"
+			);
+		}
+
+		let oversized = Sm4GuessRequest {
+			input: "00".repeat(GUESS_MAX_INPUT + 16),
+			format: Some(DataFormat::Hex),
+			key: KEY_TEXT.to_string().into(),
+			iv: None,
+			limit: None,
+		};
+		assert!(sm4_decrypt_guess(oversized).is_err());
+	}
+
+	#[test]
+	fn sm4_decrypts_openssl_pbkdf2_output() {
+		// printf 'openssl compatible' | openssl enc -sm4-cbc -pbkdf2 -pass pass:secret
+		let mut req = dec_req(
+			BlockMode::Cbc,
+			"53616c7465645f5f48c12220f24905bd2c6c29945da52ffa\
+			 3dd037682ed1b77988d1710ee8c6543ad1df5b635bcb1af6",
+		);
+		req.iv = None;
+		req.password = Some(PasswordKey {
+			password: "secret".to_string().into(),
+			kdf: KdfSettings::Pbkdf2 {
+				hash: KdfHash::Sha256,
+				iterations: 10000,
+			},
+			salt: None,
+			salted_header: false,
+		});
+		assert_eq!(sm4_decrypt(req).unwrap(), "openssl compatible");
+	}
+
+	#[test]
+	fn sm4_password_round_trip_with_salted_header() {
+		let password = || PasswordKey {
+			password: "secret".to_string().into(),
+			kdf: KdfSettings::Pbkdf2 {
+				hash: KdfHash::Sm3,
+				iterations: 1000,
+			},
+			salt: None,
+			salted_header: true,
+		};
+		let mut req = enc_req(BlockMode::Cbc);
+		req.iv = None;
+		req.password = Some(password());
+		let ciphertext = sm4_encrypt(req).unwrap();
+		assert!(ciphertext.starts_with("53616c7465645f5f"));
+
+		let mut req = dec_req(BlockMode::Cbc, &ciphertext);
+		req.iv = None;
+		req.password = Some(password());
+		assert_eq!(sm4_decrypt(req).unwrap(), PLAINTEXT);
+	}
 }
@@ -0,0 +1,361 @@
+//! Step-by-step SM4 (GB/T 32907-2016) used to expose the key schedule, the
+//! round values and the mode chaining. The actual encrypt/decrypt commands
+//! keep using the RustCrypto implementation; tests cross-check the two.
+
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+
+use super::block::{apply_padding, remove_padding, BlockMode, Padding};
+use super::encoding::{decode_bytes, encode_bytes, require_binary_format, DataFormat};
+use super::secret::Zeroizing;
+use super::sm4::{parse_key_iv, Sm4Request};
+
+/// Larger inputs are still processed completely, only the listing stops here.
+const MAX_TRACE_BLOCKS: usize = 256;
+
+const SBOX: [u8; 256] = [
+	0xd6, 0x90, 0xe9, 0xfe, 0xcc, 0xe1, 0x3d, 0xb7, 0x16, 0xb6, 0x14, 0xc2, 0x28, 0xfb, 0x2c, 0x05,
+	0x2b, 0x67, 0x9a, 0x76, 0x2a, 0xbe, 0x04, 0xc3, 0xaa, 0x44, 0x13, 0x26, 0x49, 0x86, 0x06, 0x99,
+	0x9c, 0x42, 0x50, 0xf4, 0x91, 0xef, 0x98, 0x7a, 0x33, 0x54, 0x0b, 0x43, 0xed, 0xcf, 0xac, 0x62,
+	0xe4, 0xb3, 0x1c, 0xa9, 0xc9, 0x08, 0xe8, 0x95, 0x80, 0xdf, 0x94, 0xfa, 0x75, 0x8f, 0x3f, 0xa6,
+	0x47, 0x07, 0xa7, 0xfc, 0xf3, 0x73, 0x17, 0xba, 0x83, 0x59, 0x3c, 0x19, 0xe6, 0x85, 0x4f, 0xa8,
+	0x68, 0x6b, 0x81, 0xb2, 0x71, 0x64, 0xda, 0x8b, 0xf8, 0xeb, 0x0f, 0x4b, 0x70, 0x56, 0x9d, 0x35,
+	0x1e, 0x24, 0x0e, 0x5e, 0x63, 0x58, 0xd1, 0xa2, 0x25, 0x22, 0x7c, 0x3b, 0x01, 0x21, 0x78, 0x87,
+	0xd4, 0x00, 0x46, 0x57, 0x9f, 0xd3, 0x27, 0x52, 0x4c, 0x36, 0x02, 0xe7, 0xa0, 0xc4, 0xc8, 0x9e,
+	0xea, 0xbf, 0x8a, 0xd2, 0x40, 0xc7, 0x38, 0xb5, 0xa3, 0xf7, 0xf2, 0xce, 0xf9, 0x61, 0x15, 0xa1,
+	0xe0, 0xae, 0x5d, 0xa4, 0x9b, 0x34, 0x1a, 0x55, 0xad, 0x93, 0x32, 0x30, 0xf5, 0x8c, 0xb1, 0xe3,
+	0x1d, 0xf6, 0xe2, 0x2e, 0x82, 0x66, 0xca, 0x60, 0xc0, 0x29, 0x23, 0xab, 0x0d, 0x53, 0x4e, 0x6f,
+	0xd5, 0xdb, 0x37, 0x45, 0xde, 0xfd, 0x8e, 0x2f, 0x03, 0xff, 0x6a, 0x72, 0x6d, 0x6c, 0x5b, 0x51,
+	0x8d, 0x1b, 0xaf, 0x92, 0xbb, 0xdd, 0xbc, 0x7f, 0x11, 0xd9, 0x5c, 0x41, 0x1f, 0x10, 0x5a, 0xd8,
+	0x0a, 0xc1, 0x31, 0x88, 0xa5, 0xcd, 0x7b, 0xbd, 0x2d, 0x74, 0xd0, 0x12, 0xb8, 0xe5, 0xb4, 0xb0,
+	0x89, 0x69, 0x97, 0x4a, 0x0c, 0x96, 0x77, 0x7e, 0x65, 0xb9, 0xf1, 0x09, 0xc5, 0x6e, 0xc6, 0x84,
+	0x18, 0xf0, 0x7d, 0xec, 0x3a, 0xdc, 0x4d, 0x20, 0x79, 0xee, 0x5f, 0x3e, 0xd7, 0xcb, 0x39, 0x48,
+];
+
+const FK: [u32; 4] = [0xa3b1bac6, 0x56aa3350, 0x677d9197, 0xb27022dc];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm4TraceRequest {
+	#[serde(flatten)]
+	pub request: Sm4Request,
+	/// Trace decryption of `input` instead of encryption.
+	#[serde(default)]
+	pub decrypt: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm4RoundTrace {
+	pub round: usize,
+	/// Round key used in this round (rk31 first when decrypting).
+	pub rk: String,
+	/// X(round + 4).
+	pub x: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm4BlockTrace {
+	pub index: usize,
+	/// Plaintext block when encrypting, ciphertext block when decrypting.
+	pub input: String,
+	/// IV / previous block for CBC, CFB and OFB, counter block for CTR.
+	pub chaining: Option<String>,
+	/// Block fed into the SM4 function.
+	pub cipher_input: String,
+	/// Block returned by the SM4 function (the keystream for CFB/OFB/CTR).
+	pub cipher_output: String,
+	pub output: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm4Trace {
+	/// rk0 to rk31.
+	pub round_keys: Vec<String>,
+	/// X0 to X3 of the first SM4 block call.
+	pub initial_state: Vec<String>,
+	/// The 32 rounds of the first SM4 block call.
+	pub rounds: Vec<Sm4RoundTrace>,
+	pub blocks: Vec<Sm4BlockTrace>,
+	pub blocks_truncated: bool,
+	/// Same encoding as `sm4_encrypt` / `sm4_decrypt` would return.
+	pub output: String,
+}
+
+fn tau(a: u32) -> u32 {
+	let b = a.to_be_bytes().map(|x| SBOX[x as usize]);
+	u32::from_be_bytes(b)
+}
+
+fn round_t(a: u32) -> u32 {
+	let b = tau(a);
+	b ^ b.rotate_left(2) ^ b.rotate_left(10) ^ b.rotate_left(18) ^ b.rotate_left(24)
+}
+
+fn key_t(a: u32) -> u32 {
+	let b = tau(a);
+	b ^ b.rotate_left(13) ^ b.rotate_left(23)
+}
+
+fn ck(i: usize) -> u32 {
+	(0..4).fold(0u32, |acc, j| (acc << 8) | (((4 * i + j) * 7) % 256) as u32)
+}
+
+fn words(block: &[u8; 16]) -> [u32; 4] {
+	let mut out = [0u32; 4];
+	for (w, chunk) in out.iter_mut().zip(block.chunks_exact(4)) {
+		*w = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+	}
+	out
+}
+
+fn expand_key(key: &[u8; 16]) -> Zeroizing<[u32; 32]> {
+	let mk = Zeroizing::new(words(key));
+	let mut k = Zeroizing::new([0u32; 36]);
+	for i in 0..4 {
+		k[i] = mk[i] ^ FK[i];
+	}
+	let mut rk = Zeroizing::new([0u32; 32]);
+	for i in 0..32 {
+		k[i + 4] = k[i] ^ key_t(k[i + 1] ^ k[i + 2] ^ k[i + 3] ^ ck(i));
+		rk[i] = k[i + 4];
+	}
+	rk
+}
+
+/// Runs one block and returns the output together with X0..X35.
+fn crypt_block(rk: &[u32; 32], decrypt: bool, block: &[u8; 16]) -> ([u8; 16], [u32; 36]) {
+	let mut x = [0u32; 36];
+	x[..4].copy_from_slice(&words(block));
+	for i in 0..32 {
+		let key = if decrypt { rk[31 - i] } else { rk[i] };
+		x[i + 4] = x[i] ^ round_t(x[i + 1] ^ x[i + 2] ^ x[i + 3] ^ key);
+	}
+	let mut out = [0u8; 16];
+	for (chunk, w) in out.chunks_exact_mut(4).zip([x[35], x[34], x[33], x[32]]) {
+		chunk.copy_from_slice(&w.to_be_bytes());
+	}
+	(out, x)
+}
+
+fn xor_into(data: &[u8], mask: &[u8; 16]) -> Vec<u8> {
+	data.iter().zip(mask).map(|(a, b)| a ^ b).collect()
+}
+
+fn to_block(data: &[u8]) -> [u8; 16] {
+	let mut block = [0u8; 16];
+	block[..data.len()].copy_from_slice(data);
+	block
+}
+
+#[tauri::command]
+pub fn sm4_trace(request: Sm4TraceRequest) -> Result<Sm4Trace, String> {
+	let Sm4TraceRequest { request, decrypt } = request;
+	let (key, iv) = parse_key_iv(&request)?;
+	require_binary_format(request.format)?;
+	let plain_format = request.plain_format.unwrap_or(DataFormat::Utf8);
+
+	let data = if decrypt {
+		decode_bytes(&request.input, request.format)?
+	} else if request.mode.is_stream() && request.padding == Padding::None {
+		// Like sm4_encrypt, stream modes take any length unpadded.
+		decode_bytes(&request.input, plain_format)?
+	} else {
+		apply_padding(
+			decode_bytes(&request.input, plain_format)?,
+			request.padding,
+			16,
+		)?
+	};
+	if matches!(request.mode, BlockMode::Ecb | BlockMode::Cbc) && !data.len().is_multiple_of(16) {
+		return Err("Data length must be a multiple of 16 bytes for ECB and CBC".to_string());
+	}
+
+	let rk = expand_key(&key);
+	// Only ECB/CBC decryption runs the block function backwards.
+	let inverse = decrypt && matches!(request.mode, BlockMode::Ecb | BlockMode::Cbc);
+	let mut chain = iv.map_or([0u8; 16], |iv| *iv);
+	let mut initial_state = Vec::new();
+	let mut rounds = Vec::new();
+	let mut blocks = Vec::new();
+	let mut output = Vec::with_capacity(data.len());
+
+	for (index, chunk) in data.chunks(16).enumerate() {
+		let (chaining, cipher_input) = match request.mode {
+			BlockMode::Ecb => (None, to_block(chunk)),
+			BlockMode::Cbc if decrypt => (Some(chain), to_block(chunk)),
+			BlockMode::Cbc => (Some(chain), to_block(&xor_into(chunk, &chain))),
+			BlockMode::Cfb | BlockMode::Ofb | BlockMode::Ctr => (Some(chain), chain),
+			_ => unreachable!("parse_key_iv only accepts the classic modes"),
+		};
+
+		let (cipher_output, x) = crypt_block(&rk, inverse, &cipher_input);
+		if index == 0 {
+			initial_state = x[..4].iter().map(|w| format!("{w:08x}")).collect();
+			rounds = (0..32)
+				.map(|i| Sm4RoundTrace {
+					round: i,
+					rk: format!("{:08x}", if inverse { rk[31 - i] } else { rk[i] }),
+					x: format!("{:08x}", x[i + 4]),
+				})
+				.collect();
+		}
+
+		let result = match request.mode {
+			BlockMode::Ecb => cipher_output.to_vec(),
+			BlockMode::Cbc if decrypt => {
+				let result = xor_into(&cipher_output, &chain);
+				chain = to_block(chunk);
+				result
+			}
+			BlockMode::Cbc => {
+				chain = cipher_output;
+				cipher_output.to_vec()
+			}
+			BlockMode::Cfb => {
+				let result = xor_into(chunk, &cipher_output);
+				// CFB feeds back the ciphertext block in both directions.
+				chain = to_block(if decrypt { chunk } else { &result });
+				result
+			}
+			BlockMode::Ofb => {
+				chain = cipher_output;
+				xor_into(chunk, &cipher_output)
+			}
+			BlockMode::Ctr => {
+				chain = u128::from_be_bytes(chain).wrapping_add(1).to_be_bytes();
+				xor_into(chunk, &cipher_output)
+			}
+			_ => unreachable!("parse_key_iv only accepts the classic modes"),
+		};
+
+		if index < MAX_TRACE_BLOCKS {
+			blocks.push(Sm4BlockTrace {
+				index,
+				input: HEXLOWER.encode(chunk),
+				chaining: chaining.map(|c| HEXLOWER.encode(&c)),
+				cipher_input: HEXLOWER.encode(&cipher_input),
+				cipher_output: HEXLOWER.encode(&cipher_output),
+				output: HEXLOWER.encode(&result),
+			});
+		}
+		output.extend_from_slice(&result);
+	}
+
+	let output = if decrypt {
+		let plaintext = Zeroizing::new(remove_padding(output, request.padding, 16)?);
+		encode_bytes(&plaintext, plain_format)?
+	} else {
+		encode_bytes(&output, request.format)?
+	};
+
+	Ok(Sm4Trace {
+		round_keys: rk.iter().map(|k| format!("{k:08x}")).collect(),
+		initial_state,
+		rounds,
+		blocks_truncated: data.len().div_ceil(16) > MAX_TRACE_BLOCKS,
+		blocks,
+		output,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::crypto::encoding::KeyEncoding;
+	use crate::crypto::sm4::{sm4_decrypt, sm4_encrypt};
+
+	fn request(mode: BlockMode, input: &str) -> Sm4Request {
+		Sm4Request {
+			input: input.to_string(),
+			mode,
+			padding: Padding::Pkcs7,
+			format: DataFormat::Hex,
+			key: "1234567890123456".to_string().into(),
+			key_type: KeyEncoding::Text,
+			iv: Some("1234567890123456".to_string().into()),
+			iv_type: Some(KeyEncoding::Text),
+			plain_format: None,
+			password: None,
+		}
+	}
+
+	#[test]
+	fn sm4_trace_standard_example() {
+		// GB/T 32907-2016 appendix A, example 1.
+		let mut req = request(BlockMode::Ecb, "0123456789abcdeffedcba9876543210");
+		req.plain_format = Some(DataFormat::Hex);
+		req.padding = Padding::None;
+		req.key = "0123456789abcdeffedcba9876543210".to_string().into();
+		req.key_type = KeyEncoding::Hex;
+		let trace = sm4_trace(Sm4TraceRequest {
+			request: req,
+			decrypt: false,
+		})
+		.unwrap();
+
+		assert_eq!(trace.output, "681edf34d206965e86b3e94f536e4246");
+		assert_eq!(trace.round_keys[0], "f12186f9");
+		assert_eq!(trace.round_keys[31], "9124a012");
+		assert_eq!(trace.rounds[0].x, "27fad345");
+		assert_eq!(trace.rounds[31].x, "681edf34");
+		assert_eq!(trace.initial_state[0], "01234567");
+	}
+
+	#[test]
+	fn sm4_trace_matches_backend_in_every_mode() {
+		let plaintext = "TroveKit SM4 trace, three blocks long!";
+		for mode in [
+			BlockMode::Ecb,
+			BlockMode::Cbc,
+			BlockMode::Cfb,
+			BlockMode::Ofb,
+			BlockMode::Ctr,
+		] {
+			let expected = sm4_encrypt(request(mode, plaintext)).unwrap();
+			let trace = sm4_trace(Sm4TraceRequest {
+				request: request(mode, plaintext),
+				decrypt: false,
+			})
+			.unwrap();
+			assert_eq!(trace.output, expected, "{mode:?}");
+			assert_eq!(trace.blocks.len(), 3);
+
+			let trace = sm4_trace(Sm4TraceRequest {
+				request: request(mode, &expected),
+				decrypt: true,
+			})
+			.unwrap();
+			assert_eq!(trace.output, plaintext, "{mode:?}");
+			assert_eq!(sm4_decrypt(request(mode, &expected)).unwrap(), trace.output);
+		}
+	}
+
+	#[test]
+	fn sm4_trace_stream_modes_take_a_partial_block_unpadded() {
+		let unpadded = |mode, input: &str| Sm4Request {
+			padding: Padding::None,
+			..request(mode, input)
+		};
+		let plaintext = "twenty-one bytes long";
+		for mode in [BlockMode::Cfb, BlockMode::Ofb, BlockMode::Ctr] {
+			let expected = sm4_encrypt(unpadded(mode, plaintext)).unwrap();
+			assert_eq!(expected.len(), 42, "{mode:?}");
+			let trace = sm4_trace(Sm4TraceRequest {
+				request: unpadded(mode, plaintext),
+				decrypt: false,
+			})
+			.unwrap();
+			assert_eq!(trace.output, expected, "{mode:?}");
+
+			let trace = sm4_trace(Sm4TraceRequest {
+				request: unpadded(mode, &expected),
+				decrypt: true,
+			})
+			.unwrap();
+			assert_eq!(trace.output, plaintext, "{mode:?}");
+		}
+	}
+}
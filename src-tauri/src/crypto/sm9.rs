@@ -0,0 +1,623 @@
+//! SM9 identity-based cryptography (GM/T 0044 / GB/T 38635) on top of the
+//! BN256 pairing from `sm9_core`: master keys, user key extraction,
+//! signatures and public-key encryption.
+//!
+//! Points are hex encoded: G1 as 04 || x || y (65 bytes), G2 as
+//! 04 || x || y with each coordinate in F(q^2) (129 bytes). Parsing also
+//! accepts the encodings without the 04 tag and the compressed forms.
+
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+use sm3::{Digest, Sm3};
+use sm9_core::{fast_pairing, Fr, Group, Gt, G1, G2};
+use subtle::ConstantTimeEq;
+
+use super::block::{BlockMode, Padding};
+use super::encoding::{decode_bytes, encode_bytes, require_binary_format, DataFormat};
+use super::secret::{SecretBytes, SecretString, Zeroizing};
+use super::sm3::sm3_kdf;
+use super::sm4::{sm4_decrypt_bytes, sm4_encrypt_bytes};
+
+/// Default hid for signature keys.
+const HID_SIGN: u8 = 0x01;
+/// Default hid for encryption keys.
+const HID_ENCRYPT: u8 = 0x03;
+/// MAC key length K2 for encryption, matching SM3's output size.
+const MAC_KEY_LENGTH: usize = 32;
+/// K1 length when the message is encrypted with SM4.
+const SM4_KEY_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sm9KeyUsage {
+	/// ks in Z_n, Ppub-s = [ks]P2, user keys in G1.
+	Sign,
+	/// ke in Z_n, Ppub-e = [ke]P1, user keys in G2.
+	Encrypt,
+}
+
+impl Sm9KeyUsage {
+	fn default_hid(self) -> u8 {
+		match self {
+			Sm9KeyUsage::Sign => HID_SIGN,
+			Sm9KeyUsage::Encrypt => HID_ENCRYPT,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sm9Cipher {
+	/// KDF output XORed with the message.
+	Stream,
+	/// SM4-ECB with PKCS#7 padding, K1 is 16 bytes.
+	Sm4,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm9MasterKeyRequest {
+	pub usage: Sm9KeyUsage,
+	/// Hex master private key; a random one is generated when absent.
+	pub master_private_key: Option<SecretString>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm9MasterKeyPair {
+	pub master_private_key: SecretString,
+	pub master_public_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm9UserKeyRequest {
+	pub usage: Sm9KeyUsage,
+	pub master_private_key: SecretString,
+	pub user_id: String,
+	/// Defaults to 0x01 for signing and 0x03 for encryption.
+	pub hid: Option<u8>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm9UserKey {
+	pub user_private_key: SecretString,
+	pub hid: u8,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm9SignRequest {
+	pub input: String,
+	/// Format of `input`; UTF-8 when absent.
+	pub input_format: Option<DataFormat>,
+	pub master_public_key: String,
+	pub user_private_key: SecretString,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm9SignResponse {
+	/// h || S, as hex.
+	pub signature: String,
+	pub h: String,
+	pub s: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm9VerifyRequest {
+	pub input: String,
+	pub input_format: Option<DataFormat>,
+	pub master_public_key: String,
+	pub user_id: String,
+	pub hid: Option<u8>,
+	/// Hex h || S.
+	pub signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm9EncryptRequest {
+	pub input: String,
+	/// Format of `input`; UTF-8 when absent.
+	pub input_format: Option<DataFormat>,
+	pub master_public_key: String,
+	pub user_id: String,
+	pub hid: Option<u8>,
+	pub cipher: Sm9Cipher,
+	/// Output format of C1 || C3 || C2.
+	pub format: DataFormat,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm9DecryptRequest {
+	pub input: String,
+	/// Format of the ciphertext in `input`.
+	pub format: DataFormat,
+	pub user_private_key: SecretString,
+	pub user_id: String,
+	pub cipher: Sm9Cipher,
+	/// Output format of the plaintext; UTF-8 (with hex fallback) when absent.
+	pub plain_format: Option<DataFormat>,
+}
+
+/// Scalar in [1, n-1]; shorter values are left-padded, values >= n are rejected.
+fn parse_scalar(value: &str, name: &str) -> Result<Fr, String> {
+	let bytes: SecretBytes = Zeroizing::new(decode_bytes(value, DataFormat::Hex)?);
+	if bytes.is_empty() || bytes.len() > 32 {
+		return Err(format!(
+			"{name} must be at most 32 bytes (64 hex chars), got {} bytes",
+			bytes.len()
+		));
+	}
+	let mut wide = Zeroizing::new([0u8; 64]);
+	wide[64 - bytes.len()..].copy_from_slice(&bytes);
+	let scalar = Fr::interpret(&wide);
+	if scalar.is_zero() || scalar.to_slice()[..] != wide[32..] {
+		return Err(format!("{name} must be in [1, n-1]"));
+	}
+	Ok(scalar)
+}
+
+fn random_scalar() -> Result<Fr, String> {
+	loop {
+		// 512 bits reduced mod n, so the bias is negligible.
+		let mut wide = Zeroizing::new([0u8; 64]);
+		getrandom::fill(wide.as_mut_slice())
+			.map_err(|e| format!("Random number generator failed: {e}"))?;
+		let scalar = Fr::interpret(&wide);
+		if !scalar.is_zero() {
+			return Ok(scalar);
+		}
+	}
+}
+
+fn parse_g1(value: &str, name: &str) -> Result<G1, String> {
+	let bytes = decode_bytes(value, DataFormat::Hex)?;
+	match bytes.len() {
+		64 => G1::from_slice(&bytes),
+		65 if bytes[0] == 0x04 => G1::from_slice(&bytes[1..]),
+		33 => G1::from_compressed(&bytes),
+		n => {
+			return Err(format!(
+				"{name} must be a G1 point (64, 65 or 33 bytes), got {n} bytes"
+			))
+		}
+	}
+	.map_err(|_| format!("{name} is not a valid G1 point"))
+}
+
+fn parse_g2(value: &str, name: &str) -> Result<G2, String> {
+	let bytes = decode_bytes(value, DataFormat::Hex)?;
+	match bytes.len() {
+		128 => G2::from_slice(&bytes),
+		129 if bytes[0] == 0x04 => G2::from_slice(&bytes[1..]),
+		65 => G2::from_compressed(&bytes),
+		n => {
+			return Err(format!(
+				"{name} must be a G2 point (128, 129 or 65 bytes), got {n} bytes"
+			))
+		}
+	}
+	.map_err(|_| format!("{name} is not a valid G2 point"))
+}
+
+fn g1_hex(point: G1) -> String {
+	HEXLOWER.encode(&point.to_uncompressed())
+}
+
+fn g2_hex(point: G2) -> String {
+	HEXLOWER.encode(&point.to_uncompressed())
+}
+
+/// H1/H2 from GM/T 0044.2 5.3.2: (SM3(prefix || Z || 1) || SM3(prefix || Z || 2))
+/// truncated to 40 bytes, then reduced to [1, n-1].
+fn hash_to_scalar(prefix: u8, z: &[&[u8]]) -> Fr {
+	let block = |counter: u32| {
+		let mut hasher = Sm3::new();
+		hasher.update([prefix]);
+		for part in z {
+			hasher.update(part);
+		}
+		hasher.update(counter.to_be_bytes());
+		hasher.finalize()
+	};
+	let mut ha = [0u8; 64];
+	ha[..32].copy_from_slice(&block(1));
+	ha[32..].copy_from_slice(&block(2));
+	Fr::from_hash(&ha[..40]).expect("40-byte input is always in range")
+}
+
+fn h1(user_id: &[u8], hid: u8) -> Fr {
+	hash_to_scalar(0x01, &[user_id, &[hid]])
+}
+
+fn h2(message: &[u8], w: Gt) -> Fr {
+	hash_to_scalar(0x02, &[message, &w.to_slice()])
+}
+
+/// ks·(H1(ID || hid) + ks)^-1, the scalar shared by both key kinds.
+fn user_key_scalar(master: Fr, user_id: &[u8], hid: u8) -> Result<Fr, String> {
+	(h1(user_id, hid) + master)
+		.inverse()
+		.map(|t1| master * t1)
+		.ok_or_else(|| "t1 is zero for this identity, a new master key is required".to_string())
+}
+
+/// Signature with a caller-chosen r, so the standard's example can be replayed.
+fn sign_with(message: &[u8], ppub_s: G2, user_key: G1, r: Fr) -> Option<(Fr, G1)> {
+	let w = fast_pairing(G1::one(), ppub_s).pow(r);
+	let h = h2(message, w);
+	let l = r - h;
+	if l.is_zero() {
+		return None;
+	}
+	Some((h, user_key * l))
+}
+
+fn verify_with(message: &[u8], ppub_s: G2, user_id: &[u8], hid: u8, h: Fr, s: G1) -> bool {
+	if h.is_zero() || s.is_zero() {
+		return false;
+	}
+	let t = fast_pairing(G1::one(), ppub_s).pow(h);
+	let p = G2::one() * h1(user_id, hid) + ppub_s;
+	let w = fast_pairing(s, p) * t;
+	h2(message, w) == h
+}
+
+/// C1 || C3 || C2 with a caller-chosen r. `None` when K1 is all zeros.
+fn encrypt_with(
+	message: &[u8],
+	ppub_e: G1,
+	user_id: &[u8],
+	hid: u8,
+	cipher: Sm9Cipher,
+	r: Fr,
+) -> Result<Option<Vec<u8>>, String> {
+	let q = G1::one() * h1(user_id, hid) + ppub_e;
+	let c1 = (q * r).to_slice();
+	let w = fast_pairing(ppub_e, G2::one()).pow(r);
+
+	let k1_length = match cipher {
+		Sm9Cipher::Stream => message.len(),
+		Sm9Cipher::Sm4 => SM4_KEY_LENGTH,
+	};
+	let k = sm3_kdf(
+		&[c1.as_slice(), &w.to_slice(), user_id].concat(),
+		k1_length + MAC_KEY_LENGTH,
+	);
+	let (k1, k2) = k.split_at(k1_length);
+	if k1.iter().all(|&b| b == 0) {
+		return Ok(None);
+	}
+
+	let c2 = match cipher {
+		Sm9Cipher::Stream => message.iter().zip(k1).map(|(m, k)| m ^ k).collect(),
+		Sm9Cipher::Sm4 => sm4_encrypt_bytes(
+			message,
+			k1.try_into().unwrap(),
+			None,
+			BlockMode::Ecb,
+			Padding::Pkcs7,
+		)?,
+	};
+	let c3 = Sm3::new().chain_update(&c2).chain_update(k2).finalize();
+
+	Ok(Some([c1.as_slice(), &c3, &c2].concat()))
+}
+
+fn decrypt_with(
+	ciphertext: &[u8],
+	user_key: G2,
+	user_id: &[u8],
+	cipher: Sm9Cipher,
+) -> Result<Vec<u8>, String> {
+	if ciphertext.len() < 64 + 32 {
+		return Err("Ciphertext is too short for C1 || C3 || C2".to_string());
+	}
+	let (c1, rest) = ciphertext.split_at(64);
+	let (c3, c2) = rest.split_at(32);
+	let c1_point = G1::from_slice(c1).map_err(|_| "C1 is not a valid G1 point".to_string())?;
+	let w = fast_pairing(c1_point, user_key);
+
+	let k1_length = match cipher {
+		Sm9Cipher::Stream => c2.len(),
+		Sm9Cipher::Sm4 => SM4_KEY_LENGTH,
+	};
+	let k = sm3_kdf(
+		&[c1, &w.to_slice(), user_id].concat(),
+		k1_length + MAC_KEY_LENGTH,
+	);
+	let (k1, k2) = k.split_at(k1_length);
+	if k1.iter().all(|&b| b == 0) {
+		return Err("Derived key K1 is all zeros".to_string());
+	}
+
+	let u = Sm3::new().chain_update(c2).chain_update(k2).finalize();
+	if !bool::from(u.as_slice().ct_eq(c3)) {
+		return Err("C3 mismatch: wrong key, identity or cipher".to_string());
+	}
+
+	match cipher {
+		Sm9Cipher::Stream => Ok(c2.iter().zip(k1).map(|(c, k)| c ^ k).collect()),
+		Sm9Cipher::Sm4 => sm4_decrypt_bytes(
+			c2,
+			k1.try_into().unwrap(),
+			None,
+			BlockMode::Ecb,
+			Padding::Pkcs7,
+		),
+	}
+}
+
+#[tauri::command]
+pub fn sm9_generate_master_key(request: Sm9MasterKeyRequest) -> Result<Sm9MasterKeyPair, String> {
+	let master = match &request.master_private_key {
+		Some(key) => parse_scalar(key, "Master private key")?,
+		None => random_scalar()?,
+	};
+	let master_public_key = match request.usage {
+		Sm9KeyUsage::Sign => g2_hex(G2::one() * master),
+		Sm9KeyUsage::Encrypt => g1_hex(G1::one() * master),
+	};
+	Ok(Sm9MasterKeyPair {
+		master_private_key: Zeroizing::new(HEXLOWER.encode(&master.to_slice())),
+		master_public_key,
+	})
+}
+
+#[tauri::command]
+pub fn sm9_extract_user_key(request: Sm9UserKeyRequest) -> Result<Sm9UserKey, String> {
+	let master = parse_scalar(&request.master_private_key, "Master private key")?;
+	let hid = request.hid.unwrap_or(request.usage.default_hid());
+	let t2 = user_key_scalar(master, request.user_id.as_bytes(), hid)?;
+	let user_private_key = match request.usage {
+		Sm9KeyUsage::Sign => g1_hex(G1::one() * t2),
+		Sm9KeyUsage::Encrypt => g2_hex(G2::one() * t2),
+	};
+	Ok(Sm9UserKey {
+		user_private_key: Zeroizing::new(user_private_key),
+		hid,
+	})
+}
+
+#[tauri::command]
+pub fn sm9_sign(request: Sm9SignRequest) -> Result<Sm9SignResponse, String> {
+	let ppub_s = parse_g2(&request.master_public_key, "Master public key")?;
+	let user_key = parse_g1(&request.user_private_key, "User private key")?;
+	let message = decode_bytes(
+		&request.input,
+		request.input_format.unwrap_or(DataFormat::Utf8),
+	)?;
+
+	let (h, s) = loop {
+		if let Some(signature) = sign_with(&message, ppub_s, user_key, random_scalar()?) {
+			break signature;
+		}
+	};
+	let h = HEXLOWER.encode(&h.to_slice());
+	let s = g1_hex(s);
+	Ok(Sm9SignResponse {
+		signature: format!("{h}{s}"),
+		h,
+		s,
+	})
+}
+
+#[tauri::command]
+pub fn sm9_verify(request: Sm9VerifyRequest) -> Result<bool, String> {
+	let ppub_s = parse_g2(&request.master_public_key, "Master public key")?;
+	let message = decode_bytes(
+		&request.input,
+		request.input_format.unwrap_or(DataFormat::Utf8),
+	)?;
+	let signature = decode_bytes(&request.signature, DataFormat::Hex)?;
+	if signature.len() < 32 + 33 {
+		return Err("Signature must be h (32 bytes) followed by the point S".to_string());
+	}
+	let (h, s) = signature.split_at(32);
+	let Ok(h) = parse_scalar(&HEXLOWER.encode(h), "h") else {
+		return Ok(false);
+	};
+	let Ok(s) = parse_g1(&HEXLOWER.encode(s), "S") else {
+		return Ok(false);
+	};
+
+	let hid = request.hid.unwrap_or(HID_SIGN);
+	Ok(verify_with(
+		&message,
+		ppub_s,
+		request.user_id.as_bytes(),
+		hid,
+		h,
+		s,
+	))
+}
+
+#[tauri::command]
+pub fn sm9_encrypt(request: Sm9EncryptRequest) -> Result<String, String> {
+	require_binary_format(request.format)?;
+	let ppub_e = parse_g1(&request.master_public_key, "Master public key")?;
+	let message = decode_bytes(
+		&request.input,
+		request.input_format.unwrap_or(DataFormat::Utf8),
+	)?;
+	if message.is_empty() {
+		return Err("Message is empty".to_string());
+	}
+
+	let hid = request.hid.unwrap_or(HID_ENCRYPT);
+	let ciphertext = loop {
+		let attempt = encrypt_with(
+			&message,
+			ppub_e,
+			request.user_id.as_bytes(),
+			hid,
+			request.cipher,
+			random_scalar()?,
+		)?;
+		if let Some(ciphertext) = attempt {
+			break ciphertext;
+		}
+	};
+	encode_bytes(&ciphertext, request.format)
+}
+
+#[tauri::command]
+pub fn sm9_decrypt(request: Sm9DecryptRequest) -> Result<String, String> {
+	require_binary_format(request.format)?;
+	let user_key = parse_g2(&request.user_private_key, "User private key")?;
+	let ciphertext = decode_bytes(&request.input, request.format)?;
+	let plaintext: SecretBytes = Zeroizing::new(decrypt_with(
+		&ciphertext,
+		user_key,
+		request.user_id.as_bytes(),
+		request.cipher,
+	)?);
+	encode_bytes(&plaintext, request.plain_format.unwrap_or(DataFormat::Utf8))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// GM/T 0044.5 annex A: signature example.
+	const KS: &str = "0130e78459d78545cb54c587e02cf480ce0b66340f319f348a1d5b1f2dc5f4";
+	const SIGN_R: &str = "033c8616b06704813203dfd00965022ed15975c662337aed648835dc4b1cbe";
+	const PPUB_S: &str = "049f64080b3084f733e48aff4b41b565011ce0711c5e392cfb0ab1b6791b94c40829dba116152d1f786ce843ed24a3b573414d2177386a92dd8f14d65696ea5e3269850938abea0112b57329f447e3a0cbad3e2fdb1a77f335e89e1408d0ef1c2541e00a53dda532da1a7ce027b7a46f741006e85f5cdff0730e75c05fb4e3216d";
+	const DS_ALICE: &str = "04a5702f05cf1315305e2d6eb64b0deb923db1a0bcf0caff90523ac8754aa6982078559a844411f9825c109f5ee3f52d720dd01785392a727bb1556952b2b013d3";
+	const SIGNATURE: &str = "823c4b21e4bd2dfe1ed92c606653e996668563152fc33f55d7bfbb9bd9705adb0473bf96923ce58b6ad0e13e9643a406d8eb98417c50ef1b29cef9adb48b6d598c856712f1c2e0968ab7769f42a99586aed139d5b8b3e15891827cc2aced9baa05";
+
+	// GM/T 0044.5 annex D: encryption example.
+	const KE: &str = "01edee3778f441f8dea3d9fa0acc4e07ee36c93f9a08618af4ad85cede1c22";
+	const ENC_R: &str = "aac0541779c8fc45e3e2cb25c12b5d2576b2129ae8bb5ee2cbe5ec9e785c";
+	const PPUB_E: &str = "04787ed7b8a51f3ab84e0a66003f32da5c720b17eca7137d39abc66e3c80a892ff769de61791e5adc4b9ff85a31354900b202871279a8c49dc3f220f644c57a7b1";
+	const DE_BOB: &str = "0494736acd2c8c8796cc4785e938301a139a059d3537b6414140b2d31eecf41683115bae85f5d8bc6c3dbd9e5342979acccf3c2f4f28420b1cb4f8c0b59a19b1587aa5e47570da7600cd760a0cf7beaf71c447f3844753fe74fa7ba92ca7d3b55f27538a62e7f7bfb51dce08704796d94c9d56734f119ea44732b50e31cdeb75c1";
+	const CIPHERTEXT: &str = "2445471164490618e1ee20528ff1d545b0f14c8bcaa44544f03dab5dac07d8ff42ffca97d57cddc05ea405f2e586feb3a6930715532b8000759f13059ed59ac0ba672387bcd6de5016a158a52bb2e7fc429197bcab70b25afee37a2b9db9f3671b5f5b0e951489682f3e64e1378cdd5da9513b1c";
+
+	fn master(usage: Sm9KeyUsage, key: &str) -> String {
+		sm9_generate_master_key(Sm9MasterKeyRequest {
+			usage,
+			master_private_key: Some(key.to_string().into()),
+		})
+		.unwrap()
+		.master_public_key
+	}
+
+	fn user_key(usage: Sm9KeyUsage, key: &str, user_id: &str) -> String {
+		sm9_extract_user_key(Sm9UserKeyRequest {
+			usage,
+			master_private_key: key.to_string().into(),
+			user_id: user_id.to_string(),
+			hid: None,
+		})
+		.unwrap()
+		.user_private_key
+		.to_string()
+	}
+
+	fn verify(input: &str, signature: &str) -> bool {
+		sm9_verify(Sm9VerifyRequest {
+			input: input.to_string(),
+			input_format: None,
+			master_public_key: PPUB_S.to_string(),
+			user_id: "Alice".to_string(),
+			hid: None,
+			signature: signature.to_string(),
+		})
+		.unwrap()
+	}
+
+	#[test]
+	fn sm9_signature_standard_vector() {
+		assert_eq!(master(Sm9KeyUsage::Sign, KS), PPUB_S);
+		assert_eq!(user_key(Sm9KeyUsage::Sign, KS, "Alice"), DS_ALICE);
+
+		let (h, s) = sign_with(
+			b"Chinese IBS standard",
+			parse_g2(PPUB_S, "").unwrap(),
+			parse_g1(DS_ALICE, "").unwrap(),
+			parse_scalar(SIGN_R, "").unwrap(),
+		)
+		.unwrap();
+		assert_eq!(
+			format!("{}{}", HEXLOWER.encode(&h.to_slice()), g1_hex(s)),
+			SIGNATURE
+		);
+
+		assert!(verify("Chinese IBS standard", SIGNATURE));
+		assert!(!verify("Chinese IBS standarD", SIGNATURE));
+	}
+
+	#[test]
+	fn sm9_sign_verify_roundtrip() {
+		let signed = sm9_sign(Sm9SignRequest {
+			input: "Chinese IBS standard".to_string(),
+			input_format: None,
+			master_public_key: PPUB_S.to_string(),
+			user_private_key: DS_ALICE.to_string().into(),
+		})
+		.unwrap();
+		assert_eq!(signed.signature.len(), (32 + 65) * 2);
+		assert!(verify("Chinese IBS standard", &signed.signature));
+	}
+
+	#[test]
+	fn sm9_encryption_standard_vector() {
+		assert_eq!(master(Sm9KeyUsage::Encrypt, KE), PPUB_E);
+		assert_eq!(user_key(Sm9KeyUsage::Encrypt, KE, "Bob"), DE_BOB);
+
+		let ciphertext = encrypt_with(
+			b"Chinese IBE standard",
+			parse_g1(PPUB_E, "").unwrap(),
+			b"Bob",
+			HID_ENCRYPT,
+			Sm9Cipher::Stream,
+			parse_scalar(ENC_R, "").unwrap(),
+		)
+		.unwrap()
+		.unwrap();
+		assert_eq!(HEXLOWER.encode(&ciphertext), CIPHERTEXT);
+
+		let plaintext = sm9_decrypt(Sm9DecryptRequest {
+			input: CIPHERTEXT.to_string(),
+			format: DataFormat::Hex,
+			user_private_key: DE_BOB.to_string().into(),
+			user_id: "Bob".to_string(),
+			cipher: Sm9Cipher::Stream,
+			plain_format: None,
+		});
+		assert_eq!(plaintext.unwrap(), "Chinese IBE standard");
+	}
+
+	#[test]
+	fn sm9_encrypt_decrypt_roundtrip() {
+		for cipher in [Sm9Cipher::Stream, Sm9Cipher::Sm4] {
+			let ciphertext = sm9_encrypt(Sm9EncryptRequest {
+				input: "Chinese IBE standard".to_string(),
+				input_format: None,
+				master_public_key: PPUB_E.to_string(),
+				user_id: "Bob".to_string(),
+				hid: None,
+				cipher,
+				format: DataFormat::Base64,
+			})
+			.unwrap();
+			let decrypt = |user_id: &str| {
+				sm9_decrypt(Sm9DecryptRequest {
+					input: ciphertext.clone(),
+					format: DataFormat::Base64,
+					user_private_key: DE_BOB.to_string().into(),
+					user_id: user_id.to_string(),
+					cipher,
+					plain_format: None,
+				})
+			};
+			assert_eq!(decrypt("Bob").unwrap(), "Chinese IBE standard");
+			assert!(decrypt("Alice").is_err());
+		}
+	}
+}
@@ -38,8 +38,50 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             greet,
             command::trivium_xor,
+            crypto::block::block_encrypt,
+            crypto::block::block_decrypt,
+            crypto::des::des_key_check,
+            crypto::keygen::generate_key,
+            crypto::keygen::key_check_value,
+            crypto::keywrap::key_wrap,
+            crypto::keywrap::key_unwrap,
+            crypto::self_test::crypto_self_test,
             crypto::sm4::sm4_encrypt,
             crypto::sm4::sm4_decrypt,
+            crypto::sm4::sm4_encrypt_batch,
+            crypto::sm4::sm4_decrypt_batch,
+            crypto::sm4::sm4_mac,
+            crypto::sm4::sm4_decrypt_guess,
+            crypto::sm4_trace::sm4_trace,
+            crypto::sm3::sm3_hash,
+            crypto::sm3::hmac_sm3,
+            crypto::hash::hash_digest,
+            crypto::checksum::checksum_verify,
+            crypto::checksum::checksum_generate,
+            crypto::mac::mac_compute,
+            crypto::password::password_hash,
+            crypto::password::password_verify,
+            crypto::password::password_hash_info,
+            crypto::crypt::crypt_hash,
+            crypto::crypt::crypt_verify,
+            crypto::identify::hash_identify,
+            crypto::identify::hash_identify_verify,
+            crypto::crc::crc_catalogue,
+            crypto::crc::crc_compute,
+            crypto::crc::crc_find,
+            crypto::fast_hash::fast_hash,
+            crypto::sm2::sm2_generate_keypair,
+            crypto::sm2::sm2_sign,
+            crypto::sm2::sm2_verify,
+            crypto::sm2::sm2_encrypt,
+            crypto::sm2::sm2_decrypt,
+            crypto::sm2_exchange::sm2_key_exchange,
+            crypto::sm9::sm9_generate_master_key,
+            crypto::sm9::sm9_extract_user_key,
+            crypto::sm9::sm9_sign,
+            crypto::sm9::sm9_verify,
+            crypto::sm9::sm9_encrypt,
+            crypto::sm9::sm9_decrypt,
             basex::basex_encode,
             basex::basex_decode,
             log_manager::append_log,